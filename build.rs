@@ -29,4 +29,7 @@ fn main() {
     // 确保每次构建都重新运行此脚本
     println!("cargo:rerun-if-changed=.git/HEAD");
     println!("cargo:rerun-if-changed=.git/index");
+
+    // 迁移文件在编译时嵌入，新增迁移后需要重新编译
+    println!("cargo:rerun-if-changed=migrations");
 }
@@ -0,0 +1,2 @@
+-- 上行迁移可能只是接管了已有的用户表，不能删除；migrate down 不会回滚到此版本之前
+SELECT 1;
@@ -0,0 +1,17 @@
+CREATE TYPE post_status AS ENUM ('draft', 'published', 'scheduled', 'archived');
+
+CREATE TABLE posts (
+    id SERIAL PRIMARY KEY,
+    title VARCHAR(255) NOT NULL,
+    slug VARCHAR(255) NOT NULL UNIQUE,
+    body_markdown TEXT NOT NULL DEFAULT '',
+    excerpt TEXT,
+    author_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status post_status NOT NULL DEFAULT 'draft',
+    published_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_posts_status_published_at ON posts (status, published_at DESC);
+CREATE INDEX idx_posts_author_id ON posts (author_id);
@@ -0,0 +1,2 @@
+-- NULL 表示尚未渲染，读取时会按需渲染并回写
+ALTER TABLE posts ADD COLUMN body_html TEXT;
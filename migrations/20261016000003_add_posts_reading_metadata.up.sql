@@ -0,0 +1,7 @@
+ALTER TABLE posts
+    ADD COLUMN toc JSONB NOT NULL DEFAULT '[]',
+    ADD COLUMN word_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN reading_time_minutes INTEGER NOT NULL DEFAULT 0;
+
+-- 清空 HTML 缓存，下次读取时重新渲染并填充目录和字数
+UPDATE posts SET body_html = NULL;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS comments;
+DROP TYPE IF EXISTS comment_status;
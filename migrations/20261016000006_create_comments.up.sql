@@ -0,0 +1,20 @@
+CREATE TYPE comment_status AS ENUM ('pending', 'approved', 'spam', 'deleted');
+
+CREATE TABLE comments (
+    id SERIAL PRIMARY KEY,
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    parent_id INTEGER REFERENCES comments(id) ON DELETE CASCADE,
+    -- 匿名评论没有 user_id，作者名称始终保存一份快照
+    user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    author_name VARCHAR(100) NOT NULL,
+    author_email VARCHAR(255),
+    body TEXT NOT NULL,
+    status comment_status NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (parent_id IS NULL OR parent_id <> id)
+);
+
+CREATE INDEX idx_comments_post_id_status ON comments (post_id, status, created_at);
+CREATE INDEX idx_comments_parent_id ON comments (parent_id);
+CREATE INDEX idx_comments_status_created_at ON comments (status, created_at);
@@ -0,0 +1,3 @@
+ALTER TABLE comments
+    DROP COLUMN IF EXISTS ip_address,
+    DROP COLUMN IF EXISTS moderation_reason;
@@ -0,0 +1,4 @@
+-- 记录垃圾过滤的判定原因及提交者 IP，供审核时参考
+ALTER TABLE comments
+    ADD COLUMN moderation_reason TEXT,
+    ADD COLUMN ip_address VARCHAR(45);
@@ -0,0 +1,15 @@
+CREATE TABLE media (
+    id SERIAL PRIMARY KEY,
+    uploader_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- 存储键由内容哈希决定，相同内容的文件只保存一份
+    storage_key VARCHAR(255) NOT NULL,
+    content_hash CHAR(64) NOT NULL,
+    mime_type VARCHAR(100) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    original_filename VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (uploader_id, content_hash)
+);
+
+CREATE INDEX idx_media_uploader_id_created_at ON media (uploader_id, created_at DESC);
+CREATE INDEX idx_media_storage_key ON media (storage_key);
@@ -0,0 +1,8 @@
+ALTER TABLE media
+    DROP COLUMN IF EXISTS variants,
+    DROP COLUMN IF EXISTS blurhash,
+    DROP COLUMN IF EXISTS height,
+    DROP COLUMN IF EXISTS width,
+    DROP COLUMN IF EXISTS processing_started_at,
+    DROP COLUMN IF EXISTS status;
+DROP TYPE IF EXISTS media_status;
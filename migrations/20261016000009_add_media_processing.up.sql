@@ -0,0 +1,12 @@
+CREATE TYPE media_status AS ENUM ('pending', 'processing', 'ready', 'failed');
+
+-- 图片在后台处理：去除元数据并生成不同宽度、格式的变体
+ALTER TABLE media
+    ADD COLUMN status media_status NOT NULL DEFAULT 'pending',
+    ADD COLUMN processing_started_at TIMESTAMPTZ,
+    ADD COLUMN width INTEGER,
+    ADD COLUMN height INTEGER,
+    ADD COLUMN blurhash VARCHAR(64),
+    ADD COLUMN variants JSONB NOT NULL DEFAULT '[]';
+
+CREATE INDEX idx_media_status ON media (status) WHERE status IN ('pending', 'processing');
@@ -0,0 +1,10 @@
+DROP TRIGGER IF EXISTS tags_search_index ON tags;
+DROP TRIGGER IF EXISTS post_tags_search_index ON post_tags;
+DROP TRIGGER IF EXISTS posts_search_index ON posts;
+DROP FUNCTION IF EXISTS tags_search_index();
+DROP FUNCTION IF EXISTS post_tags_search_index();
+DROP FUNCTION IF EXISTS posts_search_index();
+ALTER TABLE posts
+    DROP COLUMN IF EXISTS search_vector,
+    DROP COLUMN IF EXISTS search_text;
+DROP TABLE IF EXISTS search_settings;
@@ -0,0 +1,70 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- 全文检索使用的分词配置，应用启动时按 search.text_search_config 写入
+CREATE TABLE search_settings (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    text_search_config REGCONFIG NOT NULL DEFAULT 'simple'
+);
+INSERT INTO search_settings DEFAULT VALUES;
+
+-- search_text 供 pg_trgm 子串匹配（中文等无空格的文字），search_vector 供全文检索
+ALTER TABLE posts
+    ADD COLUMN search_text TEXT NOT NULL DEFAULT '',
+    ADD COLUMN search_vector TSVECTOR NOT NULL DEFAULT ''::TSVECTOR;
+
+CREATE FUNCTION posts_search_index() RETURNS TRIGGER AS $$
+DECLARE
+    config REGCONFIG;
+    tag_names TEXT;
+BEGIN
+    SELECT text_search_config INTO config FROM search_settings;
+    SELECT COALESCE(string_agg(t.name, ' '), '') INTO tag_names
+    FROM post_tags pt JOIN tags t ON t.id = pt.tag_id
+    WHERE pt.post_id = NEW.id;
+
+    NEW.search_text := NEW.title || ' ' || tag_names || ' ' || NEW.body_markdown;
+    NEW.search_vector :=
+        setweight(to_tsvector(config, NEW.title), 'A') ||
+        setweight(to_tsvector(config, tag_names), 'B') ||
+        setweight(to_tsvector(config, NEW.body_markdown), 'C');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- 标题、正文变化时重建索引；SET title = title 可强制重建
+CREATE TRIGGER posts_search_index
+    BEFORE INSERT OR UPDATE OF title, body_markdown ON posts
+    FOR EACH ROW EXECUTE FUNCTION posts_search_index();
+
+-- 标签增删、改名时重建相关文章的索引
+CREATE FUNCTION post_tags_search_index() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        UPDATE posts SET title = title WHERE id = OLD.post_id;
+    ELSE
+        UPDATE posts SET title = title WHERE id = NEW.post_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER post_tags_search_index
+    AFTER INSERT OR DELETE ON post_tags
+    FOR EACH ROW EXECUTE FUNCTION post_tags_search_index();
+
+CREATE FUNCTION tags_search_index() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE posts SET title = title
+    WHERE id IN (SELECT post_id FROM post_tags WHERE tag_id = NEW.id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER tags_search_index
+    AFTER UPDATE OF name ON tags
+    FOR EACH ROW EXECUTE FUNCTION tags_search_index();
+
+UPDATE posts SET title = title;
+
+CREATE INDEX idx_posts_search_vector ON posts USING GIN (search_vector);
+CREATE INDEX idx_posts_search_text_trgm ON posts USING GIN (search_text gin_trgm_ops);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_tags_name_trgm;
+DROP INDEX IF EXISTS idx_posts_title_trgm;
@@ -0,0 +1,3 @@
+-- 输入联想按标题和标签名做前缀及三元组匹配
+CREATE INDEX idx_posts_title_trgm ON posts USING GIN (title gin_trgm_ops);
+CREATE INDEX idx_tags_name_trgm ON tags USING GIN (name gin_trgm_ops);
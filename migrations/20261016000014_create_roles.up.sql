@@ -0,0 +1,52 @@
+-- 角色和权限；admin.user_ids 中的用户无需角色即拥有全部权限
+CREATE TABLE roles (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(50) NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT ''
+);
+
+CREATE TABLE permissions (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT ''
+);
+
+CREATE TABLE role_permissions (
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    permission_id INTEGER NOT NULL REFERENCES permissions(id) ON DELETE CASCADE,
+    PRIMARY KEY (role_id, permission_id)
+);
+
+CREATE TABLE user_roles (
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    -- 分配角色的管理员，新用户的默认角色为空
+    granted_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, role_id)
+);
+
+CREATE INDEX idx_user_roles_role_id ON user_roles(role_id);
+
+INSERT INTO roles (name, description) VALUES
+    ('admin', 'Full access, including assigning roles'),
+    ('editor', 'Writes posts, manages categories and tags, moderates all comments'),
+    ('author', 'Writes posts and uploads media'),
+    ('reader', 'Comments only');
+
+INSERT INTO permissions (name, description) VALUES
+    ('posts.write', 'Create and edit own posts'),
+    ('media.upload', 'Upload media'),
+    ('taxonomy.manage', 'Create, rename, merge and delete categories and tags'),
+    ('comments.moderate', 'Moderate comments on any post'),
+    ('roles.manage', 'Assign roles to users');
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id FROM roles r JOIN permissions p ON
+    r.name = 'admin'
+    OR (r.name = 'editor' AND p.name IN ('posts.write', 'media.upload', 'taxonomy.manage', 'comments.moderate'))
+    OR (r.name = 'author' AND p.name IN ('posts.write', 'media.upload'));
+
+-- 现有用户保留原来的写作能力
+INSERT INTO user_roles (user_id, role_id)
+SELECT u.id, r.id FROM users u JOIN roles r ON r.name = 'author';
@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS post_categories_sitemap_version ON post_categories;
+DROP TRIGGER IF EXISTS post_tags_sitemap_version ON post_tags;
+DROP TRIGGER IF EXISTS categories_sitemap_version ON categories;
+DROP TRIGGER IF EXISTS tags_sitemap_version ON tags;
+DROP TRIGGER IF EXISTS posts_sitemap_version ON posts;
+DROP FUNCTION IF EXISTS bump_content_version();
+DROP TABLE IF EXISTS content_versions;
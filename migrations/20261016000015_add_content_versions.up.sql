@@ -0,0 +1,38 @@
+-- 内容版本号，相关表有写入时由触发器递增，供缓存判断内容是否变化
+CREATE TABLE content_versions (
+    name VARCHAR(50) PRIMARY KEY,
+    version BIGINT NOT NULL DEFAULT 0,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO content_versions (name) VALUES ('sitemap');
+
+-- 触发器参数为要递增的版本名
+CREATE FUNCTION bump_content_version() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE content_versions SET version = version + 1, changed_at = NOW()
+    WHERE name = TG_ARGV[0];
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- sitemap 由文章、标签、分类及其关联生成
+CREATE TRIGGER posts_sitemap_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON posts
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_content_version('sitemap');
+
+CREATE TRIGGER tags_sitemap_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON tags
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_content_version('sitemap');
+
+CREATE TRIGGER categories_sitemap_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON categories
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_content_version('sitemap');
+
+CREATE TRIGGER post_tags_sitemap_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON post_tags
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_content_version('sitemap');
+
+CREATE TRIGGER post_categories_sitemap_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON post_categories
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_content_version('sitemap');
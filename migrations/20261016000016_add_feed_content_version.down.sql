@@ -0,0 +1,4 @@
+DROP TRIGGER IF EXISTS post_tags_feed_version ON post_tags;
+DROP TRIGGER IF EXISTS tags_feed_version ON tags;
+DROP TRIGGER IF EXISTS posts_feed_version ON posts;
+DELETE FROM content_versions WHERE name = 'feed';
@@ -0,0 +1,14 @@
+-- 订阅源的 Last-Modified 取自该版本的变更时间，删除或撤回文章时也会前移
+INSERT INTO content_versions (name) VALUES ('feed');
+
+CREATE TRIGGER posts_feed_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON posts
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_content_version('feed');
+
+CREATE TRIGGER tags_feed_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON tags
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_content_version('feed');
+
+CREATE TRIGGER post_tags_feed_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON post_tags
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_content_version('feed');
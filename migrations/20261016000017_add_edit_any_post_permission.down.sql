@@ -0,0 +1,4 @@
+UPDATE roles SET description = 'Writes posts, manages categories and tags, moderates all comments'
+WHERE name = 'editor';
+
+DELETE FROM permissions WHERE name = 'posts.edit_any';
@@ -0,0 +1,10 @@
+-- 编辑可以修改、发布和删除他人的文章，作者只能修改自己的
+INSERT INTO permissions (name, description) VALUES
+    ('posts.edit_any', 'Edit, publish and delete posts written by others');
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id FROM roles r JOIN permissions p ON p.name = 'posts.edit_any'
+WHERE r.name IN ('admin', 'editor');
+
+UPDATE roles SET description = 'Writes posts, edits anyone''s posts, manages categories and tags, moderates all comments'
+WHERE name = 'editor';
@@ -0,0 +1,58 @@
+use clap::{Parser, Subcommand};
+
+#[derive(Debug, Parser)]
+#[command(about = "Blog backend", version = option_env!("GIT_VERSION").unwrap_or("unknown"))]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (default)
+    Serve,
+    /// Manage the database schema
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateAction {
+    /// Apply all pending migrations
+    Up,
+    /// Revert the latest applied migration
+    Down {
+        /// Revert every migration newer than this version instead (the first
+        /// migration, which adopts the users table, is never reverted)
+        #[arg(long)]
+        target: Option<i64>,
+    },
+    /// List known and applied migrations
+    Status,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert!(
+            Cli::try_parse_from(["blog-axum"])
+                .unwrap()
+                .command
+                .is_none()
+        );
+        assert!(matches!(
+            Cli::try_parse_from(["blog-axum", "migrate", "down", "--target", "0"])
+                .unwrap()
+                .command,
+            Some(Command::Migrate {
+                action: MigrateAction::Down { target: Some(0) }
+            })
+        ));
+        assert!(Cli::try_parse_from(["blog-axum", "migrate"]).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, bail};
+use chrono::{DateTime, Utc};
+use sqlx::{
+    FromRow, PgPool,
+    migrate::{Migration, Migrator},
+};
+use tracing::{info, warn};
+
+/// Migrations under `migrations/`, embedded at compile time
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// The migration adopting the `users` table of databases created before
+/// migrations existed
+///
+/// Reverting it would drop accounts it never created, so `migrate down`
+/// stops here.
+pub const BASELINE_VERSION: i64 = 20261016000000;
+
+/// A row of sqlx's `_sqlx_migrations` bookkeeping table
+#[derive(Debug, Clone, FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub checksum: Vec<u8>,
+    pub success: bool,
+    pub installed_on: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the SQL in the binary has changed since
+    Modified,
+    /// Started but never finished
+    Failed,
+    /// Applied by a newer binary
+    Unknown,
+}
+
+impl MigrationState {
+    pub fn label(self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+            MigrationState::Failed => "failed",
+            MigrationState::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+    pub installed_on: Option<DateTime<Utc>>,
+}
+
+/// Compares the migrations a binary knows with the ones applied to a database
+///
+/// The result is ordered by version and contains every migration from both
+/// sides.
+pub fn compare(migrations: &[Migration], applied: &[AppliedMigration]) -> Vec<MigrationStatus> {
+    let mut applied: HashMap<i64, &AppliedMigration> =
+        applied.iter().map(|row| (row.version, row)).collect();
+
+    let mut statuses: Vec<MigrationStatus> = migrations
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| {
+            let row = applied.remove(&migration.version);
+            let state = match row {
+                None => MigrationState::Pending,
+                Some(row) if !row.success => MigrationState::Failed,
+                Some(row) if row.checksum != *migration.checksum => MigrationState::Modified,
+                Some(_) => MigrationState::Applied,
+            };
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+                installed_on: row.map(|row| row.installed_on),
+            }
+        })
+        .collect();
+
+    statuses.extend(applied.into_values().map(|row| MigrationStatus {
+        version: row.version,
+        description: row.description.clone(),
+        state: MigrationState::Unknown,
+        installed_on: Some(row.installed_on),
+    }));
+    statuses.sort_by_key(|status| status.version);
+    statuses
+}
+
+async fn applied_migrations(pool: &PgPool) -> anyhow::Result<Vec<AppliedMigration>> {
+    // 新数据库还没有迁移记录表
+    let exists: bool = sqlx::query_scalar(r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL"#)
+        .fetch_one(pool)
+        .await
+        .context("Failed to query migrations table")?;
+    if !exists {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_as::<_, AppliedMigration>(
+        r#"SELECT version, description, checksum, success, installed_on
+        FROM _sqlx_migrations ORDER BY version"#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query applied migrations")
+}
+
+/// Status of every known and applied migration
+pub async fn status(pool: &PgPool) -> anyhow::Result<Vec<MigrationStatus>> {
+    let applied = applied_migrations(pool).await?;
+    Ok(compare(MIGRATOR.migrations.as_ref(), &applied))
+}
+
+/// Refuses to work with a database this binary cannot safely use
+///
+/// Fails when the schema has migrations the binary does not know (a newer
+/// version was deployed before), or when an applied migration failed or was
+/// edited afterwards. Returns the number of pending migrations.
+pub async fn check(pool: &PgPool) -> anyhow::Result<usize> {
+    let statuses = status(pool).await?;
+
+    let unknown: Vec<i64> = statuses
+        .iter()
+        .filter(|status| status.state == MigrationState::Unknown)
+        .map(|status| status.version)
+        .collect();
+    if !unknown.is_empty() {
+        let latest = MIGRATOR
+            .iter()
+            .map(|migration| migration.version)
+            .max()
+            .unwrap_or_default();
+        bail!(
+            "Database schema is ahead of this binary: migrations {:?} are unknown (latest known is {}). Deploy a newer version or revert them with its `migrate down`",
+            unknown,
+            latest
+        );
+    }
+
+    for status in &statuses {
+        match status.state {
+            MigrationState::Failed => bail!(
+                "Migration {} ({}) did not finish; fix the schema by hand and remove its row from _sqlx_migrations",
+                status.version,
+                status.description
+            ),
+            MigrationState::Modified => bail!(
+                "Migration {} ({}) was changed after it had been applied",
+                status.version,
+                status.description
+            ),
+            _ => {}
+        }
+    }
+
+    Ok(statuses
+        .iter()
+        .filter(|status| status.state == MigrationState::Pending)
+        .count())
+}
+
+/// Applies all pending migrations
+pub async fn run(pool: &PgPool) -> anyhow::Result<()> {
+    let pending = check(pool).await?;
+    if pending == 0 {
+        info!("Database schema is up to date");
+        return Ok(());
+    }
+
+    info!(pending, "Applying database migrations");
+    MIGRATOR
+        .run(pool)
+        .await
+        .context("Failed to apply migrations")?;
+    info!("Database migrations applied");
+
+    Ok(())
+}
+
+fn check_revert_target(target: i64) -> anyhow::Result<()> {
+    if target < BASELINE_VERSION {
+        bail!(
+            "Cannot revert below migration {}: it adopts an existing users table, and reverting it would delete every account",
+            BASELINE_VERSION
+        );
+    }
+
+    Ok(())
+}
+
+/// Reverts applied migrations newer than `target`
+///
+/// Without a target only the latest applied migration is reverted. Returns
+/// the reverted versions, newest first. The baseline migration is never
+/// reverted; see [`BASELINE_VERSION`].
+pub async fn revert(pool: &PgPool, target: Option<i64>) -> anyhow::Result<Vec<i64>> {
+    check(pool).await?;
+
+    let applied: Vec<i64> = status(pool)
+        .await?
+        .into_iter()
+        .filter(|status| status.state == MigrationState::Applied)
+        .map(|status| status.version)
+        .collect();
+    let target = target.unwrap_or_else(|| {
+        applied
+            .len()
+            .checked_sub(2)
+            .map_or(0, |index| applied[index])
+    });
+    check_revert_target(target)?;
+
+    let mut reverted: Vec<i64> = applied
+        .into_iter()
+        .filter(|&version| version > target)
+        .collect();
+    reverted.reverse();
+    if reverted.is_empty() {
+        warn!(target, "No applied migrations to revert");
+        return Ok(reverted);
+    }
+
+    MIGRATOR
+        .undo(pool, target)
+        .await
+        .context("Failed to revert migrations")?;
+
+    Ok(reverted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::migrate::MigrationType;
+
+    fn migration(version: i64, sql: &'static str) -> Migration {
+        Migration::new(
+            version,
+            format!("migration {}", version).into(),
+            MigrationType::ReversibleUp,
+            sql.into(),
+            false,
+        )
+    }
+
+    fn applied(migration: &Migration, success: bool) -> AppliedMigration {
+        AppliedMigration {
+            version: migration.version,
+            description: migration.description.to_string(),
+            checksum: migration.checksum.to_vec(),
+            success,
+            installed_on: Utc::now(),
+        }
+    }
+
+    fn states(statuses: &[MigrationStatus]) -> Vec<(i64, MigrationState)> {
+        statuses
+            .iter()
+            .map(|status| (status.version, status.state))
+            .collect()
+    }
+
+    #[test]
+    fn test_compare_pending_and_unknown() {
+        let known = vec![migration(1, "SELECT 1"), migration(2, "SELECT 2")];
+        let mut newer = applied(&migration(3, "SELECT 3"), true);
+        newer.description = "from a newer binary".to_string();
+
+        let statuses = compare(&known, &[applied(&known[0], true), newer]);
+        assert_eq!(
+            states(&statuses),
+            vec![
+                (1, MigrationState::Applied),
+                (2, MigrationState::Pending),
+                (3, MigrationState::Unknown),
+            ]
+        );
+        assert!(statuses[1].installed_on.is_none());
+        assert_eq!(statuses[2].description, "from a newer binary");
+    }
+
+    #[test]
+    fn test_compare_failed_and_modified() {
+        let known = vec![migration(1, "SELECT 1"), migration(2, "SELECT 2")];
+        let edited = applied(&migration(2, "SELECT 'before'"), true);
+
+        let statuses = compare(&known, &[applied(&known[0], false), edited]);
+        assert_eq!(
+            states(&statuses),
+            vec![(1, MigrationState::Failed), (2, MigrationState::Modified)]
+        );
+    }
+
+    #[test]
+    fn test_compare_ignores_down_migrations() {
+        let down = Migration::new(
+            1,
+            "migration 1".into(),
+            MigrationType::ReversibleDown,
+            "DROP TABLE t".into(),
+            false,
+        );
+        let known = vec![migration(1, "CREATE TABLE t ()"), down];
+
+        let statuses = compare(&known, &[applied(&known[0], true)]);
+        assert_eq!(states(&statuses), vec![(1, MigrationState::Applied)]);
+    }
+
+    #[test]
+    fn test_revert_stops_at_baseline() {
+        assert!(check_revert_target(0).is_err());
+        assert!(check_revert_target(BASELINE_VERSION - 1).is_err());
+        assert!(check_revert_target(BASELINE_VERSION).is_ok());
+
+        let first = MIGRATOR
+            .iter()
+            .map(|migration| migration.version)
+            .min()
+            .unwrap();
+        assert_eq!(first, BASELINE_VERSION);
+    }
+
+    #[test]
+    fn test_embedded_migrations_are_reversible() {
+        let versions: Vec<i64> = MIGRATOR
+            .iter()
+            .filter(|migration| migration.migration_type.is_up_migration())
+            .map(|migration| migration.version)
+            .collect();
+        assert!(!versions.is_empty());
+        for version in versions {
+            assert!(
+                MIGRATOR.iter().any(|migration| migration.version == version
+                    && migration.migration_type.is_down_migration()),
+                "migration {} has no down script",
+                version
+            );
+        }
+    }
+}
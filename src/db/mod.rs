@@ -0,0 +1,230 @@
+pub mod migrations;
+mod replicas;
+
+pub use replicas::{CheckReplicas, Database, ReadPool};
+
+use std::time::Duration;
+
+use anyhow::Context;
+use sqlx::{
+    PgPool,
+    postgres::{PgConnectOptions, PgPoolOptions, PgSslMode},
+};
+use tracing::{info, warn};
+
+use crate::utils::{
+    config::{PostgresConfig, SslMode},
+    parse_duration,
+};
+
+const DEFAULT_APPLICATION_NAME: &str = "blog-axum";
+const DEFAULT_CONNECT_RETRIES: u32 = 10;
+
+// 重试间隔从 500ms 开始逐次翻倍，最长 30 秒
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+impl From<SslMode> for PgSslMode {
+    fn from(mode: SslMode) -> Self {
+        match mode {
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Allow => PgSslMode::Allow,
+            SslMode::Prefer => PgSslMode::Prefer,
+            SslMode::Require => PgSslMode::Require,
+            SslMode::VerifyCa => PgSslMode::VerifyCa,
+            SslMode::VerifyFull => PgSslMode::VerifyFull,
+        }
+    }
+}
+
+fn duration(value: &Option<String>, name: &str) -> anyhow::Result<Option<Duration>> {
+    value
+        .as_deref()
+        .map(|value| parse_duration(value).with_context(|| format!("Invalid postgresql.{}", name)))
+        .transpose()
+}
+
+/// Connection options for a server; the credentials are passed as is, so
+/// they need no URL escaping
+pub fn connect_options(config: &PostgresConfig) -> anyhow::Result<PgConnectOptions> {
+    let mut options = PgConnectOptions::new()
+        .host(&config.host)
+        .port(config.port)
+        .username(&config.user)
+        .password(&config.password)
+        .database(&config.database)
+        .ssl_mode(config.ssl_mode.into())
+        .application_name(
+            config
+                .application_name
+                .as_deref()
+                .unwrap_or(DEFAULT_APPLICATION_NAME),
+        );
+
+    if let Some(path) = &config.ssl_root_cert {
+        options = options.ssl_root_cert(path);
+    }
+    if let Some(path) = &config.ssl_client_cert {
+        options = options.ssl_client_cert(path);
+    }
+    if let Some(path) = &config.ssl_client_key {
+        options = options.ssl_client_key(path);
+    }
+    if let Some(timeout) = duration(&config.statement_timeout, "statement_timeout")? {
+        options = options.options([("statement_timeout", timeout.as_millis().to_string())]);
+    }
+
+    Ok(options)
+}
+
+pub fn pool_options(config: &PostgresConfig) -> anyhow::Result<PgPoolOptions> {
+    let mut options = PgPoolOptions::new();
+
+    if let Some(max) = config.max_connections {
+        options = options.max_connections(max);
+    }
+    if let Some(min) = config.min_connections {
+        options = options.min_connections(min);
+    }
+    if let Some(timeout) = duration(&config.acquire_timeout, "acquire_timeout")? {
+        options = options.acquire_timeout(timeout);
+    }
+    if let Some(timeout) = duration(&config.idle_timeout, "idle_timeout")? {
+        options = options.idle_timeout(timeout);
+    }
+
+    Ok(options)
+}
+
+/// Delay before retry number `attempt` (starting at 1)
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+/// Errors that may go away by themselves, such as the server still starting
+///
+/// Configuration mistakes like a wrong password fail immediately.
+fn is_transient(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut => true,
+        sqlx::Error::Database(e) => matches!(
+            e.code().as_deref(),
+            // cannot_connect_now、too_many_connections、admin_shutdown
+            Some("57P03" | "53300" | "57P01")
+        ),
+        _ => false,
+    }
+}
+
+/// Connects to the configured PostgreSQL database
+///
+/// The first connection is retried with exponential backoff, so the service
+/// can start alongside a database that is not ready yet.
+pub async fn connect(config: &PostgresConfig) -> anyhow::Result<PgPool> {
+    let connect_options = connect_options(config)?;
+    let pool_options = pool_options(config)?;
+    let retries = config.connect_retries.unwrap_or(DEFAULT_CONNECT_RETRIES);
+
+    info!("Connecting databse: {}", config.host);
+    let mut attempt = 0;
+    let pool = loop {
+        match pool_options
+            .clone()
+            .connect_with(connect_options.clone())
+            .await
+        {
+            Ok(pool) => break pool,
+            Err(e) if attempt < retries && is_transient(&e) => {
+                attempt += 1;
+                let delay = backoff(attempt);
+                warn!(attempt, retries, ?delay, error = %e, "Database unavailable, retrying");
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e).with_context(|| "Failed to connect to database"),
+        }
+    };
+
+    info!("Database connection pool created successfully");
+
+    Ok(pool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> PostgresConfig {
+        PostgresConfig {
+            host: "db.internal".to_string(),
+            port: 5433,
+            user: "blog".to_string(),
+            password: "p@ss:w/rd?#".to_string(),
+            database: "blog".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_connect_options() {
+        let mut config = config();
+        config.ssl_mode = SslMode::VerifyFull;
+        config.statement_timeout = Some("30s".to_string());
+
+        let options = connect_options(&config).unwrap();
+        assert_eq!(options.get_host(), "db.internal");
+        assert_eq!(options.get_port(), 5433);
+        assert_eq!(options.get_username(), "blog");
+        assert_eq!(options.get_database(), Some("blog"));
+        assert_eq!(options.get_application_name(), Some("blog-axum"));
+        assert!(matches!(options.get_ssl_mode(), PgSslMode::VerifyFull));
+        assert_eq!(options.get_options(), Some("-c statement_timeout=30000"));
+    }
+
+    #[test]
+    fn test_invalid_durations_are_rejected() {
+        let mut config = config();
+        config.acquire_timeout = Some("soon".to_string());
+        assert!(pool_options(&config).is_err());
+
+        config.acquire_timeout = None;
+        config.statement_timeout = Some("5 minutes".to_string());
+        assert!(connect_options(&config).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL server with TLS enabled"]
+    async fn test_verify_full_against_server() {
+        let env =
+            |name: &str| std::env::var(name).unwrap_or_else(|_| panic!("{} is not set", name));
+        let config = PostgresConfig {
+            host: env("POSTGRES_TLS_TEST_HOST"),
+            port: 5432,
+            user: env("POSTGRES_TLS_TEST_USER"),
+            password: std::env::var("POSTGRES_TLS_TEST_PASSWORD").unwrap_or_default(),
+            database: env("POSTGRES_TLS_TEST_DATABASE"),
+            ssl_mode: SslMode::VerifyFull,
+            ssl_root_cert: Some(env("POSTGRES_TLS_TEST_ROOT_CERT").into()),
+            connect_retries: Some(0),
+            ..Default::default()
+        };
+
+        let pool = connect(&config).await.unwrap();
+        let encrypted: Option<bool> =
+            sqlx::query_scalar(r#"SELECT ssl FROM pg_stat_ssl WHERE pid = pg_backend_pid()"#)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(encrypted, Some(true));
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(2), Duration::from_secs(1));
+        assert_eq!(backoff(4), Duration::from_secs(4));
+        assert_eq!(backoff(7), MAX_BACKOFF);
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+use super::{connect_options, pool_options};
+use crate::jobs::Job;
+use crate::utils::config::{PostgresConfig, ReplicaConfig};
+
+// 单个副本健康检查的最长等待时间
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Replica {
+    name: String,
+    pool: PgPool,
+    healthy: AtomicBool,
+}
+
+struct Pools {
+    primary: PgPool,
+    replicas: Vec<Replica>,
+    next: AtomicUsize,
+}
+
+/// The primary database plus any read replicas
+///
+/// Replicas start out unhealthy and only receive reads once
+/// [`CheckReplicas`] has seen them caught up.
+#[derive(Clone)]
+pub struct Database {
+    pools: Arc<Pools>,
+}
+
+impl Database {
+    pub fn new(primary: PgPool) -> Self {
+        Self {
+            pools: Arc::new(Pools {
+                primary,
+                replicas: Vec::new(),
+                next: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Adds replicas sharing the primary's credentials and pool settings
+    ///
+    /// The replica pools connect lazily, so an unreachable replica does not
+    /// keep the service from starting.
+    pub fn with_replicas(
+        primary: PgPool,
+        config: &PostgresConfig,
+        replicas: &[ReplicaConfig],
+    ) -> anyhow::Result<Self> {
+        let replicas = replicas
+            .iter()
+            .map(|replica| {
+                let options = connect_options(config)?
+                    .host(&replica.host)
+                    .port(replica.port);
+                Ok(Replica {
+                    name: format!("{}:{}", replica.host, replica.port),
+                    pool: pool_options(config)?.connect_lazy_with(options),
+                    healthy: AtomicBool::new(false),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            pools: Arc::new(Pools {
+                primary,
+                replicas,
+                next: AtomicUsize::new(0),
+            }),
+        })
+    }
+
+    /// Pool for writes and for reads that must see them
+    pub fn primary(&self) -> &PgPool {
+        &self.pools.primary
+    }
+
+    /// Pool for reads that tolerate replication lag
+    ///
+    /// Healthy replicas are used in turn; without one the primary serves.
+    pub fn read(&self) -> &PgPool {
+        let replicas = &self.pools.replicas;
+        if replicas.is_empty() {
+            return &self.pools.primary;
+        }
+
+        let start = self.pools.next.fetch_add(1, Ordering::Relaxed);
+        (0..replicas.len())
+            .map(|offset| &replicas[(start + offset) % replicas.len()])
+            .find(|replica| replica.healthy.load(Ordering::Relaxed))
+            .map_or(&self.pools.primary, |replica| &replica.pool)
+    }
+
+    pub fn has_replicas(&self) -> bool {
+        !self.pools.replicas.is_empty()
+    }
+}
+
+/// A pool from [`Database::read`], extracted with `State<ReadPool>`
+///
+/// Use it in handlers that only read and can show slightly stale data, such
+/// as listings. Anything that writes, or reads right after a write, takes
+/// `State<PgPool>` (the primary) instead.
+#[derive(Clone)]
+pub struct ReadPool(pub PgPool);
+
+/// Whether a replica lagging `lag_seconds` behind may serve reads
+fn within_lag(lag_seconds: Option<f64>, max_lag: Duration) -> bool {
+    lag_seconds.is_some_and(|lag| lag <= max_lag.as_secs_f64())
+}
+
+/// Seconds a replica is behind the primary
+///
+/// A replica that is streaming from the primary and has replayed everything
+/// it received counts as caught up, however long ago the last transaction
+/// was. Without a streaming WAL receiver it may be arbitrarily stale, so the
+/// lag is unknown (`NULL`). Reading the receiver status takes the
+/// `pg_read_all_stats` role; without it every replica counts as unhealthy.
+async fn replication_lag(pool: &PgPool) -> anyhow::Result<Option<f64>> {
+    sqlx::query_scalar!(
+        r#"SELECT CASE
+            WHEN NOT pg_is_in_recovery() THEN 0
+            WHEN NOT EXISTS (
+                SELECT 1 FROM pg_stat_wal_receiver WHERE status = 'streaming'
+            ) THEN NULL
+            WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+            ELSE EXTRACT(EPOCH FROM NOW() - pg_last_xact_replay_timestamp())
+        END::FLOAT8"#,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to query replication lag")
+}
+
+/// Marks replicas healthy or unhealthy by reachability and replication lag
+pub struct CheckReplicas {
+    db: Database,
+    max_lag: Duration,
+    interval: Duration,
+}
+
+impl CheckReplicas {
+    pub fn new(db: Database, max_lag: Duration, interval: Duration) -> Self {
+        Self {
+            db,
+            max_lag,
+            interval,
+        }
+    }
+}
+
+#[async_trait]
+impl Job for CheckReplicas {
+    fn name(&self) -> &'static str {
+        "check_replicas"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, _now: DateTime<Utc>) -> anyhow::Result<()> {
+        for replica in &self.db.pools.replicas {
+            let lag = tokio::time::timeout(CHECK_TIMEOUT, replication_lag(&replica.pool))
+                .await
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("Replica health check timed out")));
+
+            let healthy = match &lag {
+                Ok(lag) => within_lag(*lag, self.max_lag),
+                Err(_) => false,
+            };
+            let was_healthy = replica.healthy.swap(healthy, Ordering::Relaxed);
+
+            match (was_healthy, healthy, lag) {
+                (false, true, _) => info!(replica = %replica.name, "Replica is serving reads"),
+                (true, false, Ok(None)) => warn!(
+                    replica = %replica.name,
+                    "Replica is not streaming from the primary, reading from the primary"
+                ),
+                (true, false, Ok(lag)) => warn!(
+                    replica = %replica.name,
+                    lag_seconds = ?lag,
+                    "Replica is lagging, reading from the primary"
+                ),
+                (true, false, Err(e)) => warn!(
+                    replica = %replica.name,
+                    error = ?e,
+                    "Replica is unavailable, reading from the primary"
+                ),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lazy_pool() -> PgPool {
+        PgPool::connect_lazy("postgres://localhost/blog").unwrap()
+    }
+
+    fn database(healthy: &[bool]) -> Database {
+        let replicas = healthy
+            .iter()
+            .enumerate()
+            .map(|(index, &healthy)| Replica {
+                name: format!("replica-{}", index),
+                pool: lazy_pool(),
+                healthy: AtomicBool::new(healthy),
+            })
+            .collect();
+
+        Database {
+            pools: Arc::new(Pools {
+                primary: lazy_pool(),
+                replicas,
+                next: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    fn pick(db: &Database) -> Option<usize> {
+        let pool = db.read();
+        db.pools
+            .replicas
+            .iter()
+            .position(|replica| std::ptr::eq(&replica.pool, pool))
+    }
+
+    #[test]
+    fn test_within_lag() {
+        let max_lag = Duration::from_secs(30);
+        assert!(within_lag(Some(0.0), max_lag));
+        assert!(within_lag(Some(30.0), max_lag));
+        assert!(!within_lag(Some(30.5), max_lag));
+        assert!(!within_lag(None, max_lag));
+    }
+
+    #[tokio::test]
+    async fn test_read_rotates_over_healthy_replicas() {
+        let db = database(&[true, false, true]);
+        let picks: Vec<_> = (0..4).map(|_| pick(&db)).collect();
+        assert_eq!(picks, vec![Some(0), Some(2), Some(2), Some(0)]);
+    }
+
+    #[tokio::test]
+    async fn test_read_falls_back_to_primary() {
+        let db = database(&[false, false]);
+        assert_eq!(pick(&db), None);
+        assert!(std::ptr::eq(db.read(), db.primary()));
+
+        let db = database(&[]);
+        assert!(std::ptr::eq(db.read(), db.primary()));
+    }
+}
@@ -2,23 +2,44 @@ use anyhow::Error;
 use axum::response::{IntoResponse, Response};
 use tracing::error;
 
-use crate::response::StatusCode;
+use crate::response::{ErrorResponse, StatusCode};
 
 #[derive(Debug)]
-pub struct AppError(Error);
+pub enum AppError {
+    // 未预期的错误，统一返回 500
+    Internal(Error),
+    // 已构造好的业务错误响应（404、403、409 等）
+    Response(Box<ErrorResponse>),
+}
 
 impl AppError {
     pub fn new<E: Into<Error>>(err: E) -> Self {
-        AppError(err.into())
+        AppError::Internal(err.into())
     }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        error!("{:?}", self);
-        StatusCode::internal_error()
-            .with_debug(self.0.to_string())
-            .into_response()
+        match self {
+            AppError::Internal(err) => {
+                // 唯一约束冲突属于客户端错误，不应返回 500
+                if let Some(db_err) = err
+                    .downcast_ref::<sqlx::Error>()
+                    .and_then(|e| e.as_database_error())
+                    && db_err.is_unique_violation()
+                {
+                    return StatusCode::duplicate_resource()
+                        .with_debug(err.to_string())
+                        .into_response();
+                }
+
+                error!("{:?}", err);
+                StatusCode::internal_error()
+                    .with_debug(err.to_string())
+                    .into_response()
+            }
+            AppError::Response(response) => response.into_response(),
+        }
     }
 }
 
@@ -26,7 +47,13 @@ pub type AppResult<T> = anyhow::Result<T, AppError>;
 
 impl From<Error> for AppError {
     fn from(err: Error) -> Self {
-        AppError(err)
+        AppError::Internal(err)
+    }
+}
+
+impl From<ErrorResponse> for AppError {
+    fn from(response: ErrorResponse) -> Self {
+        AppError::Response(Box::new(response))
     }
 }
 
@@ -45,6 +72,16 @@ impl From<config::ConfigError> for AppError {
 
 impl From<crate::utils::jwt::JwtError> for AppError {
     fn from(err: crate::utils::jwt::JwtError) -> Self {
-        AppError::new(err)
+        use crate::utils::jwt::JwtError;
+
+        match err {
+            JwtError::InvalidToken
+            | JwtError::RevokedToken
+            | JwtError::InvalidIssuer
+            | JwtError::InvalidAudience
+            | JwtError::ImmatureToken => StatusCode::token_invalid().into(),
+            JwtError::ExpiredToken => StatusCode::token_expired().into(),
+            err => AppError::new(err),
+        }
     }
 }
@@ -1,15 +1,26 @@
-use std::{fmt, ops::Deref};
+use std::{
+    convert::Infallible,
+    fmt,
+    marker::PhantomData,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
 
 use axum::{
-    extract::{FromRequest, FromRequestParts, Json},
-    http::{StatusCode, request::Parts},
+    extract::{ConnectInfo, FromRef, FromRequest, FromRequestParts, Json},
+    http::request::Parts,
     response::{IntoResponse, Response},
 };
 use validator::Validate;
 
 use crate::{
+    permissions::{Permission, has_permission},
+    repositories::RoleRepository,
     response::{ErrorDetail, StatusCode as AppStatusCode},
-    utils::jwt::{Claims, JwtError, JwtService},
+    utils::{
+        config::AppConfig,
+        jwt::{Claims, JwtError, JwtService},
+    },
 };
 
 pub struct ValidatedJson<T>(pub T);
@@ -77,38 +88,190 @@ impl<T> std::ops::DerefMut for ValidatedJson<T> {
 }
 
 /// Authentication extractor that validates JWT tokens
+///
+/// Works with any state that exposes a [`JwtService`] through `FromRef`,
+/// which in practice means [`crate::state::AppState`].
 #[derive(Debug, Clone)]
 pub struct Auth(pub Claims);
 
 impl<S> FromRequestParts<S> for Auth
 where
-    S: Deref<Target = JwtService> + Send + Sync,
+    JwtService: FromRef<S>,
+    S: Send + Sync,
 {
     type Rejection = AuthError;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        // Extract token from Authorization header
-        let auth_header = parts
-            .headers
-            .get(axum::http::header::AUTHORIZATION)
-            .ok_or(AuthError::MissingAuthHeader)?;
-
-        let auth_header = auth_header
-            .to_str()
-            .map_err(|_| AuthError::InvalidAuthHeader)?;
-
-        // Bearer token format: "Bearer <token>"
-        let token = auth_header
-            .strip_prefix("Bearer ")
-            .ok_or(AuthError::InvalidTokenFormat)?;
+        let token = bearer_token(parts)?.ok_or(AuthError::MissingAuthHeader)?;
 
         // Validate token
-        let claims = state.validate_token(token).map_err(AuthError::Jwt)?;
+        let jwt_service = JwtService::from_ref(state);
+        let claims = jwt_service.validate_token(token).map_err(AuthError::Jwt)?;
 
         Ok(Auth(claims))
     }
 }
 
+/// Authentication extractor for routes that are public but behave
+/// differently for signed-in users
+///
+/// A request without an Authorization header yields `OptionalAuth(None)`.
+/// A header that is present but malformed, or carries an invalid token, is
+/// still rejected so clients notice that their credentials are broken.
+#[derive(Debug, Clone)]
+pub struct OptionalAuth(pub Option<Claims>);
+
+impl<S> FromRequestParts<S> for OptionalAuth
+where
+    JwtService: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Some(token) = bearer_token(parts)? else {
+            return Ok(OptionalAuth(None));
+        };
+
+        let jwt_service = JwtService::from_ref(state);
+        let claims = jwt_service.validate_token(token).map_err(AuthError::Jwt)?;
+
+        Ok(OptionalAuth(Some(claims)))
+    }
+}
+
+/// [`Auth`] for routes that also need a permission, e.g.
+/// `RequirePermission<WritePosts>`
+///
+/// A signed-in user holding `P` neither through a role nor as one of the
+/// `admin.user_ids` superusers is rejected with `Forbidden`.
+pub struct RequirePermission<P> {
+    pub auth: Auth,
+    permission: PhantomData<fn() -> P>,
+}
+
+impl<P> RequirePermission<P> {
+    /// Skips the permission check, so handlers can be called directly in
+    /// tests
+    #[cfg(test)]
+    pub fn new(auth: Auth) -> Self {
+        Self {
+            auth,
+            permission: PhantomData,
+        }
+    }
+}
+
+impl<S, P> FromRequestParts<S> for RequirePermission<P>
+where
+    JwtService: FromRef<S>,
+    Arc<dyn RoleRepository>: FromRef<S>,
+    Arc<AppConfig>: FromRef<S>,
+    S: Send + Sync,
+    P: Permission,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let auth = Auth::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let user_id = auth
+            .0
+            .user_id()
+            .map_err(|e| AuthError::Jwt(e).into_response())?;
+
+        let roles = Arc::<dyn RoleRepository>::from_ref(state);
+        let config = Arc::<AppConfig>::from_ref(state);
+        let granted = has_permission(roles.as_ref(), &config.admin, user_id, P::NAME)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        if !granted {
+            return Err(AppStatusCode::forbidden()
+                .with_debug(format!("Missing permission {}", P::NAME))
+                .into_response());
+        }
+
+        Ok(Self {
+            auth,
+            permission: PhantomData,
+        })
+    }
+}
+
+/// Extracts the token from a `Bearer <token>` Authorization header
+///
+/// Returns `Ok(None)` when the header is absent.
+fn bearer_token(parts: &Parts) -> Result<Option<&str>, AuthError> {
+    let Some(auth_header) = parts.headers.get(axum::http::header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+
+    let auth_header = auth_header
+        .to_str()
+        .map_err(|_| AuthError::InvalidAuthHeader)?;
+
+    // Bearer token format: "Bearer <token>"
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AuthError::InvalidTokenFormat)?;
+
+    Ok(Some(token))
+}
+
+/// Address of the client that sent the request
+///
+/// Taken from the TCP connection, or from `X-Forwarded-For` when
+/// `server.trust_proxy_headers` is enabled. `None` when neither is
+/// available, e.g. when the router is served without connect info.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    Arc<AppConfig>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = Arc::<AppConfig>::from_ref(state);
+
+        if config.server.trust_proxy_headers
+            && let Some(ip) = forwarded_for(parts, config.server.trusted_proxy_hops)
+        {
+            return Ok(ClientIp(Some(ip)));
+        }
+
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+
+        Ok(ClientIp(ip))
+    }
+}
+
+/// The `X-Forwarded-For` entry added by the outermost of `hops` trusted proxies
+///
+/// Entries further left come from the client and can be forged, so they are
+/// never used. A header with fewer entries than `hops` did not pass through
+/// every proxy and is ignored.
+fn forwarded_for(parts: &Parts, hops: usize) -> Option<IpAddr> {
+    let entries: Vec<&str> = parts
+        .headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .map(|value| value.to_str().ok())
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .collect();
+
+    let index = entries.len().checked_sub(hops.max(1))?;
+    entries[index].trim().parse().ok()
+}
+
 #[derive(Debug)]
 pub enum AuthError {
     MissingAuthHeader,
@@ -132,18 +295,243 @@ impl fmt::Display for AuthError {
 
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
-        let status = match &self {
+        let response = match &self {
             AuthError::MissingAuthHeader
             | AuthError::InvalidAuthHeader
-            | AuthError::InvalidTokenFormat => StatusCode::UNAUTHORIZED,
-            AuthError::Jwt(JwtError::InvalidToken) | AuthError::Jwt(JwtError::ExpiredToken) => {
-                StatusCode::UNAUTHORIZED
-            }
-            AuthError::Jwt(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            | AuthError::InvalidTokenFormat => AppStatusCode::unauthorized(),
+            AuthError::Jwt(JwtError::ExpiredToken) => AppStatusCode::token_expired(),
+            AuthError::Jwt(
+                JwtError::InvalidToken
+                | JwtError::RevokedToken
+                | JwtError::InvalidIssuer
+                | JwtError::InvalidAudience
+                | JwtError::ImmatureToken,
+            ) => AppStatusCode::token_invalid(),
+            AuthError::Jwt(_) => AppStatusCode::internal_error(),
         };
 
-        (status, self.to_string()).into_response()
+        response.with_debug(self.to_string()).into_response()
     }
 }
 
 impl std::error::Error for AuthError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permissions::{ManageTaxonomy, WritePosts};
+    use crate::repositories::InMemoryRoleRepository;
+    use crate::utils::config::{AppConfig, JwtConfig};
+    use axum::http::Request;
+
+    fn jwt_service() -> JwtService {
+        let config = AppConfig {
+            jwt: JwtConfig {
+                secret: "test-secret".to_string(),
+                expires_in: "1h".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        JwtService::from_config(&config).unwrap()
+    }
+
+    fn parts_with_auth(value: Option<&str>) -> Parts {
+        let mut builder = Request::builder().uri("/");
+        if let Some(value) = value {
+            builder = builder.header(axum::http::header::AUTHORIZATION, value);
+        }
+        builder.body(()).unwrap().into_parts().0
+    }
+
+    #[tokio::test]
+    async fn test_auth_accepts_valid_token() {
+        let jwt = jwt_service();
+        let token = jwt.generate_token("42", None, Vec::new()).unwrap();
+        let mut parts = parts_with_auth(Some(&format!("Bearer {}", token)));
+
+        let Auth(claims) = Auth::from_request_parts(&mut parts, &jwt).await.unwrap();
+        assert_eq!(claims.sub, "42");
+    }
+
+    #[tokio::test]
+    async fn test_auth_rejects_missing_and_malformed_header() {
+        let jwt = jwt_service();
+
+        let mut parts = parts_with_auth(None);
+        let err = Auth::from_request_parts(&mut parts, &jwt)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AuthError::MissingAuthHeader));
+
+        let mut parts = parts_with_auth(Some("Token abc"));
+        let err = Auth::from_request_parts(&mut parts, &jwt)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AuthError::InvalidTokenFormat));
+
+        let mut parts = parts_with_auth(Some("Bearer not-a-jwt"));
+        let err = Auth::from_request_parts(&mut parts, &jwt)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AuthError::Jwt(JwtError::InvalidToken)));
+    }
+
+    #[tokio::test]
+    async fn test_optional_auth() {
+        let jwt = jwt_service();
+
+        let mut parts = parts_with_auth(None);
+        let OptionalAuth(claims) = OptionalAuth::from_request_parts(&mut parts, &jwt)
+            .await
+            .unwrap();
+        assert!(claims.is_none());
+
+        let token = jwt.generate_token("7", None, Vec::new()).unwrap();
+        let mut parts = parts_with_auth(Some(&format!("Bearer {}", token)));
+        let OptionalAuth(claims) = OptionalAuth::from_request_parts(&mut parts, &jwt)
+            .await
+            .unwrap();
+        assert_eq!(claims.unwrap().sub, "7");
+
+        let mut parts = parts_with_auth(Some("Bearer not-a-jwt"));
+        assert!(
+            OptionalAuth::from_request_parts(&mut parts, &jwt)
+                .await
+                .is_err()
+        );
+    }
+
+    #[derive(Clone)]
+    struct PermissionState {
+        jwt: JwtService,
+        roles: Arc<dyn RoleRepository>,
+        config: Arc<AppConfig>,
+    }
+
+    impl FromRef<PermissionState> for JwtService {
+        fn from_ref(state: &PermissionState) -> Self {
+            state.jwt.clone()
+        }
+    }
+
+    impl FromRef<PermissionState> for Arc<dyn RoleRepository> {
+        fn from_ref(state: &PermissionState) -> Self {
+            state.roles.clone()
+        }
+    }
+
+    impl FromRef<PermissionState> for Arc<AppConfig> {
+        fn from_ref(state: &PermissionState) -> Self {
+            state.config.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_require_permission() {
+        let roles: Arc<dyn RoleRepository> = Arc::new(InMemoryRoleRepository::default());
+        roles
+            .set_roles(2, &["author".to_string()], None)
+            .await
+            .unwrap();
+        let mut config = AppConfig::default();
+        config.admin.user_ids = vec![1];
+        let state = PermissionState {
+            jwt: jwt_service(),
+            roles,
+            config: Arc::new(config),
+        };
+
+        let parts_for = |user_id: &str| {
+            let token = state.jwt.generate_token(user_id, None, Vec::new()).unwrap();
+            parts_with_auth(Some(&format!("Bearer {}", token)))
+        };
+
+        // 作者可以写文章，但不能管理分类；超级用户拥有全部权限
+        let mut parts = parts_for("2");
+        let RequirePermission { auth, .. } =
+            RequirePermission::<WritePosts>::from_request_parts(&mut parts, &state)
+                .await
+                .unwrap();
+        assert_eq!(auth.0.sub, "2");
+
+        let mut parts = parts_for("2");
+        let rejection =
+            RequirePermission::<ManageTaxonomy>::from_request_parts(&mut parts, &state).await;
+        assert_eq!(
+            rejection.err().unwrap().status(),
+            axum::http::StatusCode::FORBIDDEN
+        );
+
+        let mut parts = parts_for("1");
+        assert!(
+            RequirePermission::<ManageTaxonomy>::from_request_parts(&mut parts, &state)
+                .await
+                .is_ok()
+        );
+
+        let mut parts = parts_with_auth(None);
+        let rejection =
+            RequirePermission::<WritePosts>::from_request_parts(&mut parts, &state).await;
+        assert_eq!(
+            rejection.err().unwrap().status(),
+            axum::http::StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_ip() {
+        let forwarded = |value: &str| {
+            let mut parts = parts_with_auth(None);
+            parts
+                .headers
+                .insert("x-forwarded-for", value.parse().unwrap());
+            parts
+                .extensions
+                .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 2], 4000))));
+            parts
+        };
+        let client_ip = |mut parts: Parts, config: &AppConfig| {
+            let config = Arc::new(config.clone());
+            async move {
+                let ClientIp(ip) = ClientIp::from_request_parts(&mut parts, &config)
+                    .await
+                    .unwrap();
+                ip
+            }
+        };
+
+        // 最左边的一项由客户端自行填写，不可信
+        let mut config = AppConfig::default();
+        let header = "198.51.100.66, 203.0.113.7";
+        assert_eq!(
+            client_ip(forwarded(header), &config).await,
+            Some("10.0.0.2".parse().unwrap())
+        );
+
+        config.server.trust_proxy_headers = true;
+        assert_eq!(
+            client_ip(forwarded(header), &config).await,
+            Some("203.0.113.7".parse().unwrap())
+        );
+
+        // 两层代理时，外层代理追加的一项是客户端地址
+        config.server.trusted_proxy_hops = 2;
+        let mut parts = forwarded(header);
+        parts
+            .headers
+            .append("x-forwarded-for", "10.0.0.1".parse().unwrap());
+        assert_eq!(
+            client_ip(parts, &config).await,
+            Some("203.0.113.7".parse().unwrap())
+        );
+
+        // 经过的代理少于配置时不信任该请求头
+        assert_eq!(
+            client_ip(forwarded("203.0.113.7"), &config).await,
+            Some("10.0.0.2".parse().unwrap())
+        );
+
+        assert!(client_ip(parts_with_auth(None), &config).await.is_none());
+    }
+}
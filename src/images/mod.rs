@@ -0,0 +1,479 @@
+mod worker;
+
+pub use worker::{ImageQueue, ImageWorkers, ProcessPendingImages};
+
+use std::io::Cursor;
+
+use anyhow::{Context, bail};
+use image::{
+    DynamicImage, ImageDecoder, ImageFormat as SourceFormat, ImageReader,
+    codecs::{avif::AvifEncoder, jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+    metadata::Orientation,
+};
+
+use crate::utils::config::{ImageFormat, ImagesConfig};
+
+// 原图去除元数据后重新编码时使用的 JPEG 质量，尽量接近原图
+const ORIGINAL_JPEG_QUALITY: u8 = 92;
+
+// rav1e 的编码速度（1-10），越大越快
+const AVIF_SPEED: u8 = 8;
+
+// blurhash 的横向和纵向分量数
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+
+impl ImageFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Png => "image/png",
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Avif => "image/avif",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+            ImageFormat::Webp => "webp",
+            ImageFormat::Avif => "avif",
+        }
+    }
+}
+
+/// An image encoded in one of the output formats
+pub struct EncodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: ImageFormat,
+    pub data: Vec<u8>,
+}
+
+/// Result of processing an uploaded image
+pub struct ProcessedImage {
+    /// Dimensions after applying the EXIF orientation
+    pub width: u32,
+    pub height: u32,
+    pub blurhash: String,
+    pub variants: Vec<EncodedImage>,
+}
+
+/// Widths to generate for an image `original` pixels wide
+///
+/// Images are never upscaled. An image narrower than the largest configured
+/// width also gets a variant at its own width, so every format covers the
+/// full resolution.
+pub fn variant_widths(original: u32, widths: &[u32]) -> Vec<u32> {
+    let mut result: Vec<u32> = widths
+        .iter()
+        .copied()
+        .filter(|&width| width > 0 && width < original)
+        .collect();
+    if widths.iter().any(|&width| width >= original) {
+        result.push(original);
+    }
+
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+/// Formats uploads may have; the others cannot be decoded and re-encoded
+fn source_format(data: &[u8]) -> Option<SourceFormat> {
+    match image::guess_format(data) {
+        Ok(
+            format @ (SourceFormat::Jpeg
+            | SourceFormat::Png
+            | SourceFormat::WebP
+            | SourceFormat::Gif),
+        ) => Some(format),
+        _ => None,
+    }
+}
+
+fn decode(data: &[u8], source: SourceFormat) -> anyhow::Result<DynamicImage> {
+    let mut decoder = ImageReader::with_format(Cursor::new(data), source)
+        .into_decoder()
+        .context("Failed to read image")?;
+    // 相机拍摄的照片常靠 EXIF 方向标记旋转，去除元数据前先把方向应用到像素上
+    let orientation = decoder
+        .orientation()
+        .context("Failed to read image orientation")?;
+    let mut image = DynamicImage::from_decoder(decoder).context("Failed to decode image")?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// Re-encodes an upload without its metadata, applying the EXIF orientation
+///
+/// Runs before an upload is stored, so GPS positions and camera details are
+/// never published. WebP files keep their pixel data and only lose their
+/// metadata chunks, as the WebP encoder is lossless and would inflate lossy
+/// uploads; they are only re-encoded when an orientation has to be applied.
+/// Returns `None` for GIFs, which carry no EXIF and would lose their
+/// animation; formats that cannot be re-encoded (such as AVIF) are an error.
+pub fn strip_metadata(data: &[u8]) -> anyhow::Result<Option<EncodedImage>> {
+    let Some(source) = source_format(data) else {
+        bail!("Images of this format cannot be re-encoded");
+    };
+    let format = match source {
+        SourceFormat::Jpeg => ImageFormat::Jpeg,
+        SourceFormat::Png => ImageFormat::Png,
+        SourceFormat::WebP => ImageFormat::Webp,
+        _ => return Ok(None),
+    };
+
+    if source == SourceFormat::WebP {
+        let mut decoder = ImageReader::with_format(Cursor::new(data), source)
+            .into_decoder()
+            .context("Failed to read image")?;
+        let orientation = decoder
+            .orientation()
+            .context("Failed to read image orientation")?;
+        if orientation == Orientation::NoTransforms {
+            let (width, height) = decoder.dimensions();
+            return Ok(Some(EncodedImage {
+                width,
+                height,
+                format,
+                data: strip_webp_chunks(data)?,
+            }));
+        }
+    }
+
+    let image = decode(data, source)?;
+    encode(&image, format, ORIGINAL_JPEG_QUALITY).map(Some)
+}
+
+/// Copies a WebP file keeping only the chunks that describe the image
+///
+/// EXIF, XMP and unknown chunks are dropped and the matching VP8X flags are
+/// cleared, so the compressed pixel data is left untouched.
+fn strip_webp_chunks(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    // VP8X 标志位中的 EXIF 和 XMP
+    const VP8X_METADATA_FLAGS: u8 = 0x08 | 0x04;
+
+    if data.len() < 12 || &data[..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        bail!("Invalid WebP file");
+    }
+
+    let mut stripped = Vec::with_capacity(data.len());
+    stripped.extend_from_slice(b"RIFF\0\0\0\0WEBP");
+
+    let mut rest = &data[12..];
+    while rest.len() >= 8 {
+        let fourcc = &rest[..4];
+        let size = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        if rest.len() - 8 < size {
+            bail!("Truncated WebP chunk");
+        }
+        // 块长度为奇数时后面跟一个填充字节，文件末尾的填充字节可能缺失
+        let padded = (8 + size + (size & 1)).min(rest.len());
+        let chunk = &rest[..padded];
+        rest = &rest[padded..];
+
+        match fourcc {
+            b"VP8X" if size > 0 => {
+                stripped.extend_from_slice(&chunk[..8]);
+                stripped.push(chunk[8] & !VP8X_METADATA_FLAGS);
+                stripped.extend_from_slice(&chunk[9..]);
+            }
+            b"VP8 " | b"VP8L" | b"ALPH" | b"ANIM" | b"ANMF" | b"ICCP" => {
+                stripped.extend_from_slice(chunk);
+            }
+            _ => continue,
+        }
+        if chunk.len() < 8 + size + (size & 1) {
+            stripped.push(0);
+        }
+    }
+
+    let riff_size = u32::try_from(stripped.len() - 8).context("WebP file too large")?;
+    stripped[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Ok(stripped)
+}
+
+/// Decodes an upload and renders the configured variants
+///
+/// Uploads have already gone through [`strip_metadata`]. This is CPU heavy
+/// and must not run on the async runtime. Returns `None` for formats that
+/// cannot be decoded; those are served as uploaded.
+pub fn process(data: &[u8], config: &ImagesConfig) -> anyhow::Result<Option<ProcessedImage>> {
+    let Some(source) = source_format(data) else {
+        return Ok(None);
+    };
+
+    let image = decode(data, source)?;
+    let blurhash = blurhash(&image)?;
+
+    // GIF 重新编码会丢失动画，不生成变体
+    if source == SourceFormat::Gif {
+        return Ok(Some(ProcessedImage {
+            width: image.width(),
+            height: image.height(),
+            blurhash,
+            variants: Vec::new(),
+        }));
+    }
+
+    let mut variants = Vec::new();
+    for width in variant_widths(image.width(), &config.widths) {
+        let resized = if width == image.width() {
+            image.clone()
+        } else {
+            image.resize(width, u32::MAX, FilterType::Lanczos3)
+        };
+        for &format in &config.formats {
+            variants.push(encode(&resized, format, config.quality)?);
+        }
+    }
+
+    Ok(Some(ProcessedImage {
+        width: image.width(),
+        height: image.height(),
+        blurhash,
+        variants,
+    }))
+}
+
+/// Encodes an image; the encoders only write pixel data, never metadata
+fn encode(image: &DynamicImage, format: ImageFormat, quality: u8) -> anyhow::Result<EncodedImage> {
+    // JPEG 不支持透明通道，其余编码器只接受 8 位 RGB/RGBA
+    let pixels = match format {
+        ImageFormat::Png => image.clone(),
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8()),
+        _ if image.color().has_alpha() => DynamicImage::ImageRgba8(image.to_rgba8()),
+        _ => DynamicImage::ImageRgb8(image.to_rgb8()),
+    };
+
+    let quality = quality.clamp(1, 100);
+    let mut data = Vec::new();
+    let result = match format {
+        ImageFormat::Jpeg => {
+            pixels.write_with_encoder(JpegEncoder::new_with_quality(&mut data, quality))
+        }
+        ImageFormat::Png => pixels.write_with_encoder(PngEncoder::new(&mut data)),
+        ImageFormat::Webp => pixels.write_with_encoder(WebPEncoder::new_lossless(&mut data)),
+        ImageFormat::Avif => pixels.write_with_encoder(AvifEncoder::new_with_speed_quality(
+            &mut data, AVIF_SPEED, quality,
+        )),
+    };
+    result.with_context(|| format!("Failed to encode {}", format.mime_type()))?;
+
+    Ok(EncodedImage {
+        width: image.width(),
+        height: image.height(),
+        format,
+        data,
+    })
+}
+
+fn blurhash(image: &DynamicImage) -> anyhow::Result<String> {
+    let thumbnail = image.thumbnail(32, 32).to_rgba8();
+    let (x, y) = BLURHASH_COMPONENTS;
+
+    blurhash::encode(
+        x,
+        y,
+        thumbnail.width(),
+        thumbnail.height(),
+        thumbnail.as_raw(),
+    )
+    .context("Failed to compute blurhash")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, y| {
+            Rgb([(x * 255 / width) as u8, (y * 255 / height) as u8, 128])
+        });
+        let mut data = Vec::new();
+        DynamicImage::ImageRgb8(image)
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut data, 90))
+            .unwrap();
+        data
+    }
+
+    /// Inserts an APP1 segment with an EXIF orientation tag after the SOI marker
+    fn with_exif_orientation(jpeg: &[u8], orientation: u8) -> Vec<u8> {
+        let mut tiff = b"MM\0\x2a\0\0\0\x08".to_vec();
+        // 一个 IFD 条目：0x0112 Orientation, SHORT, count 1
+        tiff.extend_from_slice(&[0, 1, 0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, orientation, 0, 0]);
+        tiff.extend_from_slice(&[0, 0, 0, 0]);
+
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend_from_slice(&tiff);
+        let length = (app1.len() + 2) as u16;
+
+        let mut data = jpeg[..2].to_vec();
+        data.extend_from_slice(&[0xFF, 0xE1]);
+        data.extend_from_slice(&length.to_be_bytes());
+        data.extend_from_slice(&app1);
+        data.extend_from_slice(&jpeg[2..]);
+        data
+    }
+
+    fn orientation_of(data: &[u8]) -> Orientation {
+        ImageReader::with_format(Cursor::new(data), SourceFormat::Jpeg)
+            .into_decoder()
+            .unwrap()
+            .orientation()
+            .unwrap()
+    }
+
+    fn config(widths: Vec<u32>, formats: Vec<ImageFormat>) -> ImagesConfig {
+        ImagesConfig {
+            widths,
+            formats,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_variant_widths() {
+        assert_eq!(
+            variant_widths(2000, &[320, 768, 1280]),
+            vec![320, 768, 1280]
+        );
+        assert_eq!(
+            variant_widths(1000, &[320, 768, 1280]),
+            vec![320, 768, 1000]
+        );
+        assert_eq!(variant_widths(768, &[320, 768, 1280]), vec![320, 768]);
+        assert_eq!(variant_widths(100, &[320, 768]), vec![100]);
+        assert!(variant_widths(100, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_process_renders_variants() {
+        let config = config(
+            vec![16, 32, 128],
+            vec![ImageFormat::Webp, ImageFormat::Avif],
+        );
+        let processed = process(&jpeg(64, 32), &config).unwrap().unwrap();
+
+        assert_eq!((processed.width, processed.height), (64, 32));
+        assert!(!processed.blurhash.is_empty());
+
+        let variants: Vec<_> = processed
+            .variants
+            .iter()
+            .map(|variant| (variant.width, variant.height, variant.format))
+            .collect();
+        assert_eq!(
+            variants,
+            vec![
+                (16, 8, ImageFormat::Webp),
+                (16, 8, ImageFormat::Avif),
+                (32, 16, ImageFormat::Webp),
+                (32, 16, ImageFormat::Avif),
+                (64, 32, ImageFormat::Webp),
+                (64, 32, ImageFormat::Avif),
+            ]
+        );
+        for variant in &processed.variants {
+            assert_eq!(
+                infer::get(&variant.data).unwrap().mime_type(),
+                variant.format.mime_type()
+            );
+        }
+    }
+
+    #[test]
+    fn test_strip_metadata_applies_exif_orientation() {
+        // 6 表示需要顺时针旋转 90 度显示
+        let upload = with_exif_orientation(&jpeg(40, 20), 6);
+        assert_eq!(orientation_of(&upload), Orientation::Rotate90);
+
+        let stripped = strip_metadata(&upload).unwrap().unwrap();
+        assert_eq!(stripped.format, ImageFormat::Jpeg);
+        assert_eq!((stripped.width, stripped.height), (20, 40));
+        assert!(!stripped.data.windows(4).any(|window| window == b"Exif"));
+        assert_eq!(orientation_of(&stripped.data), Orientation::NoTransforms);
+
+        let processed = process(&stripped.data, &config(vec![], vec![]))
+            .unwrap()
+            .unwrap();
+        assert_eq!((processed.width, processed.height), (20, 40));
+    }
+
+    /// Wraps a WebP file in a VP8X container with EXIF and XMP chunks
+    fn with_webp_metadata(webp: &[u8]) -> Vec<u8> {
+        fn chunk(fourcc: &[u8], payload: &[u8]) -> Vec<u8> {
+            let mut chunk = fourcc.to_vec();
+            chunk.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            chunk.extend_from_slice(payload);
+            if payload.len() % 2 == 1 {
+                chunk.push(0);
+            }
+            chunk
+        }
+
+        // VP8X：EXIF 和 XMP 标志，画布宽高减一各占 3 字节
+        let mut vp8x = vec![0x08 | 0x04, 0, 0, 0];
+        vp8x.extend_from_slice(&[7, 0, 0, 3, 0, 0]);
+
+        let mut body = b"WEBP".to_vec();
+        body.extend(chunk(b"VP8X", &vp8x));
+        body.extend_from_slice(&webp[12..]);
+        body.extend(chunk(b"EXIF", b"MM\0\x2a\0\0\0\x08GPS"));
+        body.extend(chunk(b"XMP ", b"<x:xmpmeta/>"));
+
+        let mut data = b"RIFF".to_vec();
+        data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        data.extend(body);
+        data
+    }
+
+    #[test]
+    fn test_strip_metadata_keeps_webp_pixels() {
+        let webp = encode(
+            &DynamicImage::ImageRgb8(RgbImage::new(8, 4)),
+            ImageFormat::Webp,
+            80,
+        )
+        .unwrap();
+        let upload = with_webp_metadata(&webp.data);
+
+        let stripped = strip_metadata(&upload).unwrap().unwrap();
+        assert_eq!(stripped.format, ImageFormat::Webp);
+        assert_eq!((stripped.width, stripped.height), (8, 4));
+        assert!(stripped.data.len() <= upload.len());
+        assert!(!stripped.data.windows(4).any(|window| window == b"EXIF"));
+        assert!(!stripped.data.windows(4).any(|window| window == b"XMP "));
+        // 像素数据原样保留，不重新编码
+        assert!(
+            stripped
+                .data
+                .windows(webp.data.len() - 12)
+                .any(|window| window == &webp.data[12..])
+        );
+
+        let decoded = image::load_from_memory(&stripped.data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (8, 4));
+    }
+
+    #[test]
+    fn test_strip_metadata_rejects_formats_it_cannot_encode() {
+        let avif = encode(
+            &DynamicImage::ImageRgb8(RgbImage::new(8, 8)),
+            ImageFormat::Avif,
+            80,
+        )
+        .unwrap();
+        assert!(strip_metadata(&avif.data).is_err());
+        assert!(strip_metadata(b"not an image").is_err());
+
+        assert!(
+            process(b"not an image", &ImagesConfig::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+}
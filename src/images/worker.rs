@@ -0,0 +1,426 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, types::Json};
+use tokio::{
+    sync::{Mutex, Semaphore, mpsc, watch},
+    task::JoinHandle,
+};
+use tracing::{Instrument, debug, error, info, warn};
+
+use super::{EncodedImage, process, strip_metadata};
+use crate::jobs::Job;
+use crate::models::MediaVariant;
+use crate::storage::Storage;
+use crate::utils::config::ImagesConfig;
+
+// 处理中的记录超过该时间仍未完成，视为工作线程已中断，重新排队
+const STALE_AFTER: chrono::Duration = chrono::Duration::minutes(10);
+
+/// Handle for queueing uploaded media for background processing
+///
+/// The queue is bounded. When it is full the media simply stays pending and
+/// [`ProcessPendingImages`] picks it up on its next sweep. It also hands out
+/// the permits that bound how many images are decoded at once, shared by the
+/// workers and the metadata stripping done during uploads.
+#[derive(Clone)]
+pub struct ImageQueue {
+    sender: Option<mpsc::Sender<i32>>,
+    permits: Arc<Semaphore>,
+}
+
+impl ImageQueue {
+    /// A queue that drops everything; used when image processing is disabled
+    ///
+    /// Uploads are still stripped of their metadata, at most `workers` at once.
+    pub fn disabled(workers: usize) -> Self {
+        Self {
+            sender: None,
+            permits: Arc::new(Semaphore::new(workers.max(1))),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    pub fn enqueue(&self, media_id: i32) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        if let Err(e) = sender.try_send(media_id) {
+            debug!(media_id, error = %e, "Image queue unavailable, leaving media for the sweep");
+        }
+    }
+
+    /// Runs [`strip_metadata`] on the blocking thread pool once a permit is free
+    pub async fn strip_metadata(&self, data: Bytes) -> anyhow::Result<Option<EncodedImage>> {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .context("Image permits closed")?;
+
+        tokio::task::spawn_blocking(move || strip_metadata(&data))
+            .await
+            .context("Image processing panicked")?
+    }
+}
+
+/// A fixed number of tasks processing queued media one at a time each
+///
+/// The image work itself runs on the blocking thread pool, and each worker
+/// holds one of the queue's permits while it does, so the number of images
+/// decoded at once (uploads included) is bounded by `images.workers`.
+pub struct ImageWorkers {
+    shutdown: watch::Sender<bool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl ImageWorkers {
+    pub fn start(
+        pool: PgPool,
+        storage: Arc<dyn Storage>,
+        config: ImagesConfig,
+    ) -> (ImageQueue, Self) {
+        let (sender, receiver) = mpsc::channel(config.queue_size.max(1));
+        let receiver = Arc::new(Mutex::new(receiver));
+        let (shutdown, _) = watch::channel(false);
+        let workers = config.workers.max(1);
+        let permits = Arc::new(Semaphore::new(workers));
+        let processor = Arc::new(Processor {
+            pool,
+            storage,
+            config,
+            permits: permits.clone(),
+        });
+
+        info!(workers, "Starting image workers");
+
+        let handles = (0..workers)
+            .map(|worker| {
+                let processor = processor.clone();
+                let receiver = receiver.clone();
+                let mut shutdown = shutdown.subscribe();
+
+                tokio::spawn(async move {
+                    loop {
+                        let media_id = tokio::select! {
+                            media_id = async { receiver.lock().await.recv().await } => media_id,
+                            _ = shutdown.changed() => break,
+                        };
+                        let Some(media_id) = media_id else {
+                            break;
+                        };
+
+                        let span = tracing::info_span!("process_image", worker, media_id);
+                        if let Err(e) = processor.run(media_id).instrument(span).await {
+                            error!(media_id, error = ?e, "Failed to process image");
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let queue = ImageQueue {
+            sender: Some(sender),
+            permits,
+        };
+
+        (queue, Self { shutdown, handles })
+    }
+
+    /// Lets the images being processed finish and stops the workers
+    ///
+    /// Anything still queued stays pending in the database.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+
+        for handle in self.handles {
+            if let Err(e) = handle.await {
+                error!(error = ?e, "Image worker panicked");
+            }
+        }
+    }
+}
+
+/// Storage key of a variant; stored next to the original under its hash
+fn variant_key(hash: &str, image: &EncodedImage) -> String {
+    format!(
+        "{}/{}/{}.{}",
+        &hash[..2],
+        hash,
+        image.width,
+        image.format.extension()
+    )
+}
+
+struct Processor {
+    pool: PgPool,
+    storage: Arc<dyn Storage>,
+    config: ImagesConfig,
+    permits: Arc<Semaphore>,
+}
+
+impl Processor {
+    async fn run(&self, media_id: i32) -> anyhow::Result<()> {
+        // 只有抢到该记录的工作线程才会处理，重复入队无影响
+        let claimed = sqlx::query!(
+            r#"UPDATE media SET status = 'processing', processing_started_at = NOW()
+            WHERE id = $1 AND status = 'pending'
+            RETURNING storage_key, content_hash"#,
+            media_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to claim media")?;
+        let Some(claimed) = claimed else {
+            return Ok(());
+        };
+        let (storage_key, content_hash) = (claimed.storage_key, claimed.content_hash);
+
+        // 相同内容已由其他用户上传并处理过时直接复用结果
+        let reused = sqlx::query!(
+            r#"UPDATE media SET status = 'ready', size_bytes = done.size_bytes,
+                width = done.width, height = done.height,
+                blurhash = done.blurhash, variants = done.variants
+            FROM (
+                SELECT size_bytes, width, height, blurhash, variants FROM media
+                WHERE content_hash = $2 AND status = 'ready' AND id <> $1
+                LIMIT 1
+            ) AS done
+            WHERE media.id = $1"#,
+            media_id,
+            content_hash,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to reuse processed media")?;
+        if reused.rows_affected() > 0 {
+            debug!("Reused processed media with the same content");
+            return Ok(());
+        }
+
+        let data = self.storage.get(&storage_key).await?;
+        let config = self.config.clone();
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .context("Image permits closed")?;
+        let processed = tokio::task::spawn_blocking(move || process(&data, &config))
+            .await
+            .context("Image processing panicked")
+            .and_then(|result| result);
+        drop(permit);
+
+        let processed = match processed {
+            Ok(processed) => processed,
+            Err(e) => {
+                warn!(error = ?e, "Could not process image");
+                sqlx::query!(
+                    r#"UPDATE media SET status = 'failed' WHERE id = $1"#,
+                    media_id
+                )
+                .execute(&self.pool)
+                .await
+                .context("Failed to mark media as failed")?;
+                return Ok(());
+            }
+        };
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        // 与上传、删除使用同一把锁，避免为已删除的文件写入变体
+        sqlx::query!(
+            r#"SELECT pg_advisory_xact_lock(hashtext($1)) AS "locked: ()""#,
+            content_hash,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to lock media hash")?;
+
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM media WHERE id = $1) AS "exists!""#,
+            media_id,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to query media")?;
+        if !exists {
+            return Ok(());
+        }
+
+        let Some(processed) = processed else {
+            sqlx::query!(
+                r#"UPDATE media SET status = 'ready' WHERE id = $1"#,
+                media_id
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to update media")?;
+            tx.commit().await.context("Failed to commit transaction")?;
+            return Ok(());
+        };
+
+        let mut variants = Vec::with_capacity(processed.variants.len());
+        for image in processed.variants {
+            let key = variant_key(&content_hash, &image);
+            let size_bytes = image.data.len() as i64;
+            self.storage
+                .put(&key, Bytes::from(image.data), image.format.mime_type())
+                .await?;
+            variants.push(MediaVariant {
+                width: image.width,
+                height: image.height,
+                mime_type: image.format.mime_type().to_string(),
+                storage_key: key,
+                size_bytes,
+                url: String::new(),
+            });
+        }
+
+        sqlx::query!(
+            r#"UPDATE media SET status = 'ready', width = $2, height = $3, blurhash = $4,
+                variants = $5
+            WHERE id = $1"#,
+            media_id,
+            processed.width as i32,
+            processed.height as i32,
+            processed.blurhash,
+            Json(&variants) as _,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update media")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        info!(variants = variants.len(), "Processed image");
+
+        Ok(())
+    }
+}
+
+/// Queues media left pending, e.g. after a full queue or a restart
+///
+/// Media stuck in processing for too long is put back to pending first.
+pub struct ProcessPendingImages {
+    pool: PgPool,
+    queue: ImageQueue,
+    interval: Duration,
+    batch_size: i64,
+}
+
+impl ProcessPendingImages {
+    pub fn new(pool: PgPool, queue: ImageQueue, interval: Duration, batch_size: usize) -> Self {
+        Self {
+            pool,
+            queue,
+            interval,
+            batch_size: batch_size as i64,
+        }
+    }
+}
+
+#[async_trait]
+impl Job for ProcessPendingImages {
+    fn name(&self) -> &'static str {
+        "process_pending_images"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, now: DateTime<Utc>) -> anyhow::Result<()> {
+        let reset = sqlx::query!(
+            r#"UPDATE media SET status = 'pending'
+            WHERE status = 'processing' AND processing_started_at <= $1"#,
+            now - STALE_AFTER,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to reset stale media")?;
+        if reset.rows_affected() > 0 {
+            warn!(
+                count = reset.rows_affected(),
+                "Requeued media stuck in processing"
+            );
+        }
+
+        let pending = sqlx::query_scalar!(
+            r#"SELECT id FROM media WHERE status = 'pending' ORDER BY id LIMIT $1"#,
+            self.batch_size,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to query pending media")?;
+
+        for media_id in pending {
+            self.queue.enqueue(media_id);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::config::ImageFormat;
+
+    #[test]
+    fn test_variant_key() {
+        let hash = "ab".repeat(32);
+        let image = EncodedImage {
+            width: 320,
+            height: 200,
+            format: ImageFormat::Webp,
+            data: Vec::new(),
+        };
+
+        assert_eq!(variant_key(&hash, &image), format!("ab/{}/320.webp", hash));
+    }
+
+    #[tokio::test]
+    async fn test_strip_metadata_waits_for_a_permit() {
+        let queue = ImageQueue::disabled(1);
+        let permit = queue.permits.clone().acquire_owned().await.unwrap();
+
+        let strip = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.strip_metadata(Bytes::from_static(b"GIF89a")).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!strip.is_finished());
+
+        drop(permit);
+        assert!(strip.await.unwrap().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_queue_drops_when_full() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let queue = ImageQueue {
+            sender: Some(sender),
+            permits: Arc::new(Semaphore::new(1)),
+        };
+
+        queue.enqueue(1);
+        queue.enqueue(2);
+        assert_eq!(receiver.recv().await, Some(1));
+        assert!(receiver.try_recv().is_err());
+
+        // 关闭后入队同样不会出错
+        ImageQueue::disabled(1).enqueue(3);
+    }
+}
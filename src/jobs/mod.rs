@@ -0,0 +1,233 @@
+mod publish_scheduled;
+mod purge_refresh_tokens;
+mod sync_revocations;
+
+pub use publish_scheduled::PublishScheduledPosts;
+pub use purge_refresh_tokens::PurgeRefreshTokens;
+pub use sync_revocations::SyncRevocations;
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::{sync::watch, task::JoinHandle, time::MissedTickBehavior};
+use tracing::{Instrument, debug, error, info};
+use uuid::Uuid;
+
+/// Source of the current time for background jobs
+///
+/// Jobs never call `Utc::now()` themselves; the runner reads the clock and
+/// passes the result in, so tests can drive jobs with a fixed time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock backed by the system time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A unit of periodic background work
+#[async_trait]
+pub trait Job: Send + Sync + 'static {
+    /// Name used in logs and tracing spans
+    fn name(&self) -> &'static str;
+
+    /// Delay between two runs; the first run happens right after spawning
+    ///
+    /// Must not be zero, configured values go through
+    /// [`parse_interval`](crate::utils::parse_interval).
+    fn interval(&self) -> Duration;
+
+    async fn run(&self, now: DateTime<Utc>) -> anyhow::Result<()>;
+}
+
+/// Runs background jobs on the tokio runtime next to the HTTP server
+///
+/// Every spawned job gets its own task that ticks on the job's interval.
+/// Calling [`JobRunner::shutdown`] lets in-flight runs finish and then waits
+/// for all tasks to exit.
+pub struct JobRunner {
+    clock: Arc<dyn Clock>,
+    shutdown: watch::Sender<bool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl JobRunner {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        let (shutdown, _) = watch::channel(false);
+
+        Self {
+            clock,
+            shutdown,
+            handles: Vec::new(),
+        }
+    }
+
+    pub fn spawn<J: Job>(&mut self, job: J) {
+        let clock = self.clock.clone();
+        let mut shutdown = self.shutdown.subscribe();
+
+        info!(job = job.name(), interval = ?job.interval(), "Starting background job");
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(job.interval());
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    // 发送端被丢弃时同样视为关闭
+                    _ = shutdown.changed() => break,
+                }
+                if *shutdown.borrow() {
+                    break;
+                }
+
+                run_once(&job, clock.now()).await;
+            }
+
+            info!(job = job.name(), "Background job stopped");
+        });
+
+        self.handles.push(handle);
+    }
+
+    /// Signals every job to stop and waits for them to finish
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+
+        for handle in self.handles {
+            if let Err(e) = handle.await {
+                error!(error = ?e, "Background job panicked");
+            }
+        }
+    }
+}
+
+/// Executes a single run inside a span, mirroring the HTTP trace layer
+async fn run_once<J: Job>(job: &J, now: DateTime<Utc>) {
+    let span = tracing::info_span!(
+        "background_job",
+        run_id = %Uuid::new_v4(),
+        job = job.name(),
+    );
+
+    async {
+        debug!(now = %now, "Job started");
+        let started = Instant::now();
+
+        match job.run(now).await {
+            Ok(()) => debug!(latency = ?started.elapsed(), "Job finished"),
+            Err(error) => error!(error = ?error, latency = ?started.elapsed(), "Job failed"),
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Clock whose time only moves when the test says so
+    struct ManualClock(Mutex<DateTime<Utc>>);
+
+    impl ManualClock {
+        fn advance(&self, by: chrono::Duration) {
+            *self.0.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    type Runs = Arc<Mutex<Vec<DateTime<Utc>>>>;
+
+    struct RecordingJob {
+        runs: Runs,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Job for RecordingJob {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        fn interval(&self) -> Duration {
+            Duration::from_secs(60)
+        }
+
+        async fn run(&self, now: DateTime<Utc>) -> anyhow::Result<()> {
+            self.runs.lock().unwrap().push(now);
+            if self.fail {
+                anyhow::bail!("job failed on purpose");
+            }
+            Ok(())
+        }
+    }
+
+    fn start(fail: bool) -> (JobRunner, Arc<ManualClock>, Runs) {
+        let start = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let clock = Arc::new(ManualClock(Mutex::new(start)));
+        let runs = Arc::new(Mutex::new(Vec::new()));
+
+        let mut runner = JobRunner::new(clock.clone());
+        runner.spawn(RecordingJob {
+            runs: runs.clone(),
+            fail,
+        });
+
+        (runner, clock, runs)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_runner_passes_clock_time_to_jobs() {
+        let (runner, clock, runs) = start(false);
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        clock.advance(chrono::Duration::hours(2));
+        tokio::time::sleep(Duration::from_secs(60)).await;
+
+        let recorded = runs.lock().unwrap().clone();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[1] - recorded[0], chrono::Duration::hours(2));
+
+        runner.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_runner_keeps_going_after_failure() {
+        let (runner, _clock, runs) = start(true);
+
+        tokio::time::sleep(Duration::from_secs(150)).await;
+        assert_eq!(runs.lock().unwrap().len(), 3);
+
+        runner.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_stops_jobs() {
+        let (runner, _clock, runs) = start(false);
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        runner.shutdown().await;
+
+        tokio::time::sleep(Duration::from_secs(600)).await;
+        assert_eq!(runs.lock().unwrap().len(), 1);
+    }
+}
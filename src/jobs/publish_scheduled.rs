@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::info;
+
+use super::Job;
+
+/// Promotes scheduled posts whose `published_at` has passed to published
+pub struct PublishScheduledPosts {
+    pool: PgPool,
+    interval: Duration,
+}
+
+impl PublishScheduledPosts {
+    pub fn new(pool: PgPool, interval: Duration) -> Self {
+        Self { pool, interval }
+    }
+}
+
+#[async_trait]
+impl Job for PublishScheduledPosts {
+    fn name(&self) -> &'static str {
+        "publish_scheduled_posts"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, now: DateTime<Utc>) -> anyhow::Result<()> {
+        let published = sqlx::query_scalar!(
+            r#"UPDATE posts SET status = 'published', updated_at = NOW()
+            WHERE status = 'scheduled' AND published_at <= $1
+            RETURNING id"#,
+            now,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to publish scheduled posts")?;
+
+        if !published.is_empty() {
+            info!(count = published.len(), post_ids = ?published, "Published scheduled posts");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[sqlx::test(migrator = "crate::db::migrations::MIGRATOR")]
+    #[ignore = "needs a PostgreSQL server (DATABASE_URL)"]
+    async fn test_run_publishes_posts_due_at_now(pool: PgPool) {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let author_id: i32 = sqlx::query_scalar(
+            r#"INSERT INTO users (username, email, password_hash)
+            VALUES ('author', 'author@example.com', 'x') RETURNING id"#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let posts = [
+            ("past", "scheduled", now - chrono::Duration::hours(1)),
+            ("due", "scheduled", now),
+            ("future", "scheduled", now + chrono::Duration::seconds(1)),
+            ("draft", "draft", now - chrono::Duration::hours(1)),
+        ];
+        for (slug, status, published_at) in posts {
+            sqlx::query(
+                r#"INSERT INTO posts (title, slug, author_id, status, published_at)
+                VALUES ($1, $1, $2, $3::post_status, $4)"#,
+            )
+            .bind(slug)
+            .bind(author_id)
+            .bind(status)
+            .bind(published_at)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let job = PublishScheduledPosts::new(pool.clone(), Duration::from_secs(60));
+        job.run(now).await.unwrap();
+
+        let statuses: Vec<(String, String)> =
+            sqlx::query_as(r#"SELECT slug, status::text FROM posts ORDER BY id"#)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        let statuses: Vec<(&str, &str)> = statuses
+            .iter()
+            .map(|(slug, status)| (slug.as_str(), status.as_str()))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("past", "published"),
+                ("due", "published"),
+                ("future", "scheduled"),
+                ("draft", "draft"),
+            ]
+        );
+
+        // 时间推进后，之前未到期的文章才会发布
+        job.run(now + chrono::Duration::seconds(1)).await.unwrap();
+        let status: String =
+            sqlx::query_scalar(r#"SELECT status::text FROM posts WHERE slug = 'future'"#)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(status, "published");
+    }
+}
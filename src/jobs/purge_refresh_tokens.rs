@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::info;
+
+use super::Job;
+
+/// Deletes expired refresh tokens, sessions left without any, and expired
+/// revocation records
+///
+/// An expired token is rejected whether or not its row still exists, so
+/// nothing is lost by removing it. Likewise a revocation record outlives
+/// every access token it covers.
+pub struct PurgeRefreshTokens {
+    pool: PgPool,
+    interval: Duration,
+}
+
+impl PurgeRefreshTokens {
+    pub fn new(pool: PgPool, interval: Duration) -> Self {
+        Self { pool, interval }
+    }
+}
+
+#[async_trait]
+impl Job for PurgeRefreshTokens {
+    fn name(&self) -> &'static str {
+        "purge_refresh_tokens"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, now: DateTime<Utc>) -> anyhow::Result<()> {
+        let purged = sqlx::query!(r#"DELETE FROM refresh_tokens WHERE expires_at <= $1"#, now,)
+            .execute(&self.pool)
+            .await
+            .context("Failed to purge refresh tokens")?;
+
+        if purged.rows_affected() > 0 {
+            info!(
+                count = purged.rows_affected(),
+                "Purged expired refresh tokens"
+            );
+        }
+
+        sqlx::query!(
+            r#"DELETE FROM sessions s
+            WHERE NOT EXISTS (SELECT 1 FROM refresh_tokens t WHERE t.family_id = s.id)"#,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to purge sessions")?;
+
+        sqlx::query!(r#"DELETE FROM revoked_tokens WHERE expires_at <= $1"#, now)
+            .execute(&self.pool)
+            .await
+            .context("Failed to purge revoked tokens")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[sqlx::test(migrator = "crate::db::migrations::MIGRATOR")]
+    #[ignore = "needs a PostgreSQL server (DATABASE_URL)"]
+    async fn test_run_purges_records_expired_at_now(pool: PgPool) {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let user_id: i32 = sqlx::query_scalar(
+            r#"INSERT INTO users (username, email, password_hash)
+            VALUES ('reader', 'reader@example.com', 'x') RETURNING id"#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        // 一个会话只剩过期令牌，另一个还有未过期的令牌
+        let expired_session = Uuid::new_v4();
+        let live_session = Uuid::new_v4();
+        let day_ago = now - chrono::Duration::days(1);
+        let tokens = [
+            (expired_session, "expired-1", day_ago),
+            (expired_session, "expired-2", now),
+            (live_session, "expired-3", day_ago),
+            (live_session, "live", now + chrono::Duration::seconds(1)),
+        ];
+        for session in [expired_session, live_session] {
+            sqlx::query(r#"INSERT INTO sessions (id, user_id) VALUES ($1, $2)"#)
+                .bind(session)
+                .bind(user_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        for (session, hash, expires_at) in tokens {
+            sqlx::query(
+                r#"INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+                VALUES ($1, $2, $3, $4)"#,
+            )
+            .bind(user_id)
+            .bind(session)
+            .bind(hash)
+            .bind(expires_at)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let expired_revocation = Uuid::new_v4();
+        let live_revocation = Uuid::new_v4();
+        for (id, expires_at) in [
+            (expired_revocation, now),
+            (live_revocation, now + chrono::Duration::seconds(1)),
+        ] {
+            sqlx::query(r#"INSERT INTO revoked_tokens (id, expires_at) VALUES ($1, $2)"#)
+                .bind(id)
+                .bind(expires_at)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let job = PurgeRefreshTokens::new(pool.clone(), Duration::from_secs(60));
+        job.run(now).await.unwrap();
+
+        let tokens: Vec<String> =
+            sqlx::query_scalar(r#"SELECT token_hash FROM refresh_tokens ORDER BY id"#)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(tokens, ["live"]);
+
+        let sessions: Vec<Uuid> = sqlx::query_scalar(r#"SELECT id FROM sessions"#)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(sessions, [live_session]);
+
+        let revocations: Vec<Uuid> = sqlx::query_scalar(r#"SELECT id FROM revoked_tokens"#)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(revocations, [live_revocation]);
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use super::Job;
+use crate::utils::jwt::RevocationList;
+
+/// Loads the revocations recorded in the database into the in-memory list
+///
+/// Handlers revoke in their own process right away; this picks up the ones
+/// made by other instances and forgets records that have expired.
+pub struct SyncRevocations {
+    pool: PgPool,
+    revocations: RevocationList,
+    interval: Duration,
+}
+
+impl SyncRevocations {
+    pub fn new(pool: PgPool, revocations: RevocationList, interval: Duration) -> Self {
+        Self {
+            pool,
+            revocations,
+            interval,
+        }
+    }
+}
+
+#[async_trait]
+impl Job for SyncRevocations {
+    fn name(&self) -> &'static str {
+        "sync_revocations"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, now: DateTime<Utc>) -> anyhow::Result<()> {
+        let rows = sqlx::query!(
+            r#"SELECT id, expires_at FROM revoked_tokens WHERE expires_at > $1"#,
+            now,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to query revoked tokens")?;
+
+        self.revocations
+            .merge(rows.into_iter().map(|row| (row.id, row.expires_at)), now);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[sqlx::test(migrator = "crate::db::migrations::MIGRATOR")]
+    #[ignore = "needs a PostgreSQL server (DATABASE_URL)"]
+    async fn test_run_loads_revocations_unexpired_at_now(pool: PgPool) {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let expired = Uuid::new_v4();
+        let live = Uuid::new_v4();
+        for (id, expires_at) in [(expired, now), (live, now + chrono::Duration::minutes(5))] {
+            sqlx::query(r#"INSERT INTO revoked_tokens (id, expires_at) VALUES ($1, $2)"#)
+                .bind(id)
+                .bind(expires_at)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        // 本进程刚吊销、尚未写入数据库的记录不会被同步覆盖
+        let revocations = RevocationList::default();
+        let local = Uuid::new_v4();
+        revocations.revoke(local, now + chrono::Duration::minutes(5));
+
+        let job = SyncRevocations::new(pool, revocations.clone(), Duration::from_secs(60));
+        job.run(now).await.unwrap();
+
+        let before = now - chrono::Duration::seconds(1);
+        assert!(!revocations.is_revoked(&expired, before));
+        assert!(revocations.is_revoked(&live, now));
+        assert!(revocations.is_revoked(&local, now));
+        assert!(!revocations.is_revoked(&live, now + chrono::Duration::minutes(5)));
+    }
+}
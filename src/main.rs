@@ -1,27 +1,77 @@
+use std::{net::SocketAddr, sync::Arc};
+
 use anyhow::Context;
-use axum::{Router, extract::State, middleware, routing::get};
+use axum::{Router, middleware, routing::get};
+use clap::Parser;
 use sqlx::PgPool;
-use tracing::{debug, info};
+use tower_http::services::ServeDir;
+use tracing::{debug, info, warn};
 
+use crate::cli::{Cli, Command, MigrateAction};
+use crate::db::{CheckReplicas, Database};
+use crate::images::{ImageQueue, ImageWorkers, ProcessPendingImages};
+use crate::jobs::{
+    JobRunner, PublishScheduledPosts, PurgeRefreshTokens, SyncRevocations, SystemClock,
+};
 use crate::response::{StatusCode, SuccessResponse};
-use crate::utils::{config, init_tracing};
+use crate::spam::SpamFilterChain;
+use crate::state::AppState;
+use crate::utils::{
+    config::{self, StorageBackend},
+    init_tracing, parse_duration, parse_interval,
+};
 
+mod cli;
+mod db;
 mod error;
 mod extractors;
+mod images;
+mod jobs;
 mod middlewares;
 mod models;
+mod permissions;
+mod repositories;
 mod response;
 mod routes;
+mod spam;
+mod state;
+mod storage;
 mod utils;
 
-async fn root(
-    _state: State<(PgPool, crate::utils::jwt::JwtService)>,
-) -> axum::response::Json<SuccessResponse<&'static str>> {
+async fn root() -> axum::response::Json<SuccessResponse<&'static str>> {
     StatusCode::success(Some("RUA")).into()
 }
 
+// 等待 Ctrl+C 或 SIGTERM，用于优雅关闭
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("Shutdown signal received");
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
     init_tracing()?;
 
     // 加载配置
@@ -34,32 +84,151 @@ async fn main() -> anyhow::Result<()> {
     debug!("Git Version: {}", git_version);
 
     // 创建数据库连接池
-    let database_url = format!(
-        "postgres://{}:{}@{}:{}/{}",
-        app_config.postgresql.user,
-        app_config.postgresql.password,
-        app_config.postgresql.host,
-        app_config.postgresql.port,
-        app_config.postgresql.database
-    );
-
-    info!("Connecting databse: {}", app_config.postgresql.host);
-    let pool = PgPool::connect(&database_url)
-        .await
-        .with_context(|| "Failed to connect to database")?;
+    let pool = db::connect(&app_config.postgresql).await?;
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(app_config, pool).await,
+        Command::Migrate { action } => migrate(action, &pool).await,
+    }
+}
+
+async fn migrate(action: MigrateAction, pool: &PgPool) -> anyhow::Result<()> {
+    match action {
+        MigrateAction::Up => db::migrations::run(pool).await,
+        MigrateAction::Down { target } => {
+            let reverted = db::migrations::revert(pool, target).await?;
+            for version in reverted {
+                println!("Reverted {}", version);
+            }
+            Ok(())
+        }
+        MigrateAction::Status => {
+            for status in db::migrations::status(pool).await? {
+                let installed_on = status
+                    .installed_on
+                    .map(|time| time.to_rfc3339())
+                    .unwrap_or_default();
+                println!(
+                    "{:<16} {:<9} {:<32} {}",
+                    status.version,
+                    status.state.label(),
+                    status.description,
+                    installed_on
+                );
+            }
+            Ok(())
+        }
+    }
+}
 
-    info!("Database connection pool created successfully");
+async fn serve(app_config: config::AppConfig, pool: PgPool) -> anyhow::Result<()> {
+    // 数据库结构比当前版本新时拒绝启动，避免旧代码写坏新结构
+    if app_config.migrations.run_on_startup {
+        db::migrations::run(&pool).await?;
+    } else {
+        let pending = db::migrations::check(&pool).await?;
+        if pending > 0 {
+            warn!(
+                pending,
+                "Database has pending migrations, run `blog-axum migrate up` to apply them"
+            );
+        }
+    }
+
+    // 同步全文检索配置，配置变化时重建索引
+    routes::search::apply_text_search_config(&pool, &app_config.search).await?;
 
     // 创建 JWT 服务
     let jwt_service = crate::utils::jwt::JwtService::from_config(&app_config)
         .with_context(|| "Failed to create JWT service")?;
     info!("JWT service initialized successfully");
 
+    // 创建评论垃圾过滤链
+    let spam_filters = SpamFilterChain::from_config(&app_config.spam)
+        .with_context(|| "Failed to create spam filters")?;
+
+    // 创建文件存储
+    let storage =
+        storage::from_config(&app_config.storage).with_context(|| "Failed to create storage")?;
+    info!("Storage backend: {:?}", app_config.storage.backend);
+
+    // 启动后台任务
+    let publish_interval = parse_interval(&app_config.jobs.publish_interval)
+        .with_context(|| "Invalid jobs.publish_interval")?;
+    let mut job_runner = JobRunner::new(Arc::new(SystemClock));
+    job_runner.spawn(PublishScheduledPosts::new(pool.clone(), publish_interval));
+
+    let purge_tokens_interval = parse_interval(&app_config.jobs.purge_tokens_interval)
+        .with_context(|| "Invalid jobs.purge_tokens_interval")?;
+    job_runner.spawn(PurgeRefreshTokens::new(pool.clone(), purge_tokens_interval));
+
+    let revocation_sync_interval = parse_interval(&app_config.jobs.revocation_sync_interval)
+        .with_context(|| "Invalid jobs.revocation_sync_interval")?;
+    job_runner.spawn(SyncRevocations::new(
+        pool.clone(),
+        jwt_service.revocations().clone(),
+        revocation_sync_interval,
+    ));
+
+    // 启动图片处理线程，上传的图片在请求之外生成变体
+    let (image_queue, image_workers) = if app_config.images.enabled {
+        let sweep_interval = parse_interval(&app_config.images.sweep_interval)
+            .with_context(|| "Invalid images.sweep_interval")?;
+        let (queue, workers) =
+            ImageWorkers::start(pool.clone(), storage.clone(), app_config.images.clone());
+        job_runner.spawn(ProcessPendingImages::new(
+            pool.clone(),
+            queue.clone(),
+            sweep_interval,
+            app_config.images.queue_size,
+        ));
+        (queue, Some(workers))
+    } else {
+        (ImageQueue::disabled(app_config.images.workers), None)
+    };
+
+    // 列表等只读请求优先走只读副本，副本不可用或延迟过大时回退到主库
+    let replication = &app_config.replication;
+    let database =
+        Database::with_replicas(pool.clone(), &app_config.postgresql, &replication.replicas)?;
+    if database.has_replicas() {
+        let max_lag =
+            parse_duration(&replication.max_lag).with_context(|| "Invalid replication.max_lag")?;
+        let check_interval = parse_interval(&replication.health_check_interval)
+            .with_context(|| "Invalid replication.health_check_interval")?;
+        info!(
+            replicas = replication.replicas.len(),
+            "Read replicas configured"
+        );
+        job_runner.spawn(CheckReplicas::new(
+            database.clone(),
+            max_lag,
+            check_interval,
+        ));
+    }
+
     // 创建路由
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/api/", get(root))
         .nest("/api", routes::create_routes())
-        .with_state((pool, jwt_service));
+        // sitemap、robots.txt 和 JWKS 按约定位于站点根路径
+        .merge(routes::sitemap::routes())
+        .merge(routes::well_known::routes());
+
+    // 本地存储且 URL 为站内路径时，由本服务直接提供上传的文件
+    let local = &app_config.storage.local;
+    if app_config.storage.backend == StorageBackend::Local && local.base_url.starts_with('/') {
+        app = app.nest_service(&local.base_url, ServeDir::new(&local.root));
+    }
+
+    let app = app.with_state(AppState::new(
+        database,
+        jwt_service,
+        spam_filters,
+        storage,
+        image_queue,
+        app_config,
+    ));
 
     let app = middlewares::build_trace_layer(app)
         .layer(middleware::from_fn(middlewares::request_id_middleware));
@@ -69,9 +238,19 @@ async fn main() -> anyhow::Result<()> {
         .await
         .with_context(|| "Failed to bind TCP listener to 0.0.0.0:8000")?;
     info!("Server running on http://0.0.0.0:8000");
-    axum::serve(listener, app)
-        .await
-        .with_context(|| "Failed to serve HTTP server")?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .with_context(|| "Failed to serve HTTP server")?;
+
+    info!("Waiting for background jobs to stop");
+    job_runner.shutdown().await;
+    if let Some(image_workers) = image_workers {
+        image_workers.shutdown().await;
+    }
 
     Ok(())
 }
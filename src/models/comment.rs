@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "comment_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CommentStatus {
+    Pending,
+    Approved,
+    Spam,
+    Deleted,
+}
+
+/// A comment as seen by moderators
+#[derive(Debug, Clone, Serialize)]
+pub struct Comment {
+    pub id: i32,
+    pub post_id: i32,
+    pub parent_id: Option<i32>,
+    // 匿名评论为 None
+    pub user_id: Option<i32>,
+    pub author_name: String,
+    pub author_email: Option<String>,
+    pub body: String,
+    pub status: CommentStatus,
+    // 垃圾过滤器给出的判定原因
+    pub moderation_reason: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What the submitter sees of their own comment
+///
+/// Spam verdicts are reported as pending and the filter's reason stays in
+/// the moderation queue, so a bot cannot learn which check caught it.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubmittedComment {
+    pub id: i32,
+    pub post_id: i32,
+    pub parent_id: Option<i32>,
+    pub author_name: String,
+    pub body: String,
+    pub state: SubmissionState,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmissionState {
+    Pending,
+    Approved,
+}
+
+impl From<Comment> for SubmittedComment {
+    fn from(comment: Comment) -> Self {
+        let state = match comment.status {
+            CommentStatus::Approved => SubmissionState::Approved,
+            _ => SubmissionState::Pending,
+        };
+
+        SubmittedComment {
+            id: comment.id,
+            post_id: comment.post_id,
+            parent_id: comment.parent_id,
+            author_name: comment.author_name,
+            body: comment.body,
+            state,
+            created_at: comment.created_at,
+        }
+    }
+}
+
+/// Public view of an approved comment and its replies
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentNode {
+    pub id: i32,
+    pub parent_id: Option<i32>,
+    pub user_id: Option<i32>,
+    pub author_name: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub replies: Vec<CommentNode>,
+}
+
+impl CommentNode {
+    /// Builds one thread per root comment
+    ///
+    /// Roots keep the given order and replies keep theirs within each parent.
+    /// Replies whose parent is neither a root nor another reply are dropped,
+    /// so hiding a comment also hides the conversation below it.
+    pub fn build_threads(roots: Vec<Comment>, replies: Vec<Comment>) -> Vec<CommentNode> {
+        let mut by_parent: HashMap<i32, Vec<Comment>> = HashMap::new();
+        for reply in replies {
+            if let Some(parent_id) = reply.parent_id {
+                by_parent.entry(parent_id).or_default().push(reply);
+            }
+        }
+
+        fn attach(comment: Comment, by_parent: &mut HashMap<i32, Vec<Comment>>) -> CommentNode {
+            let replies = by_parent
+                .remove(&comment.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|reply| attach(reply, by_parent))
+                .collect();
+
+            CommentNode {
+                id: comment.id,
+                parent_id: comment.parent_id,
+                user_id: comment.user_id,
+                author_name: comment.author_name,
+                body: comment.body,
+                created_at: comment.created_at,
+                replies,
+            }
+        }
+
+        roots
+            .into_iter()
+            .map(|root| attach(root, &mut by_parent))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: i32, parent_id: Option<i32>) -> Comment {
+        Comment {
+            id,
+            post_id: 1,
+            parent_id,
+            user_id: None,
+            author_name: format!("guest{}", id),
+            author_email: None,
+            body: format!("comment {}", id),
+            status: CommentStatus::Approved,
+            moderation_reason: None,
+            ip_address: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_submitted_comment_hides_spam_verdict() {
+        let mut spam = comment(1, None);
+        spam.status = CommentStatus::Spam;
+        spam.moderation_reason = Some("honeypot field was filled in".to_string());
+        spam.ip_address = Some("203.0.113.7".to_string());
+
+        let submitted = SubmittedComment::from(spam);
+        assert_eq!(submitted.state, SubmissionState::Pending);
+        let json = serde_json::to_value(&submitted).unwrap();
+        assert_eq!(json["state"], "pending");
+        assert!(json.get("moderation_reason").is_none());
+        assert!(json.get("ip_address").is_none());
+        assert!(json.get("status").is_none());
+
+        let approved = SubmittedComment::from(comment(2, None));
+        assert_eq!(approved.state, SubmissionState::Approved);
+    }
+
+    #[test]
+    fn test_build_comment_threads() {
+        let threads = CommentNode::build_threads(
+            vec![comment(1, None), comment(4, None)],
+            vec![
+                comment(2, Some(1)),
+                comment(3, Some(2)),
+                comment(5, Some(1)),
+                // 父评论未通过审核时整条回复链都不展示
+                comment(7, Some(6)),
+            ],
+        );
+
+        let roots: Vec<i32> = threads.iter().map(|n| n.id).collect();
+        assert_eq!(roots, vec![1, 4]);
+        let replies: Vec<i32> = threads[0].replies.iter().map(|n| n.id).collect();
+        assert_eq!(replies, vec![2, 5]);
+        assert_eq!(threads[0].replies[0].replies[0].id, 3);
+        assert!(threads[1].replies.is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "media_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum MediaStatus {
+    Pending,
+    Processing,
+    Ready,
+    Failed,
+}
+
+/// A resized or converted copy of an uploaded image
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaVariant {
+    pub width: u32,
+    pub height: u32,
+    pub mime_type: String,
+    pub storage_key: String,
+    pub size_bytes: i64,
+    /// Public URL, filled in from the storage backend after loading
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub url: String,
+}
+
+/// An uploaded file in a user's media library
+#[derive(Debug, Clone, Serialize)]
+pub struct Media {
+    pub id: i32,
+    pub uploader_id: i32,
+    pub storage_key: String,
+    // 文件内容的 SHA-256，十六进制
+    pub content_hash: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub original_filename: Option<String>,
+    pub status: MediaStatus,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub blurhash: Option<String>,
+    pub variants: Vec<MediaVariant>,
+    /// Public URL, filled in from the storage backend after loading
+    pub url: String,
+    /// Ready-made `srcset` attribute values keyed by MIME type
+    pub srcset: BTreeMap<String, String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Media {
+    /// Groups the variants by format into `srcset` strings, narrowest first
+    ///
+    /// Variant URLs must already be filled in.
+    pub fn build_srcset(variants: &[MediaVariant]) -> BTreeMap<String, String> {
+        let mut by_type: BTreeMap<String, Vec<&MediaVariant>> = BTreeMap::new();
+        for variant in variants {
+            by_type
+                .entry(variant.mime_type.clone())
+                .or_default()
+                .push(variant);
+        }
+
+        by_type
+            .into_iter()
+            .map(|(mime_type, mut variants)| {
+                variants.sort_by_key(|variant| variant.width);
+                let srcset = variants
+                    .iter()
+                    .map(|variant| format!("{} {}w", variant.url, variant.width))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (mime_type, srcset)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(width: u32, mime_type: &str) -> MediaVariant {
+        MediaVariant {
+            width,
+            height: width / 2,
+            mime_type: mime_type.to_string(),
+            storage_key: format!("ab/abc/{}", width),
+            size_bytes: 100,
+            url: format!("/media/{}.{}", width, &mime_type[6..]),
+        }
+    }
+
+    #[test]
+    fn test_build_srcset() {
+        let srcset = Media::build_srcset(&[
+            variant(768, "image/webp"),
+            variant(320, "image/webp"),
+            variant(320, "image/avif"),
+        ]);
+
+        assert_eq!(srcset.len(), 2);
+        assert_eq!(srcset["image/avif"], "/media/320.avif 320w");
+        assert_eq!(
+            srcset["image/webp"],
+            "/media/320.webp 320w, /media/768.webp 768w"
+        );
+    }
+}
@@ -1,14 +1,32 @@
+mod comment;
+mod media;
+mod post;
+mod role;
+mod search;
+mod series;
+mod session;
+mod taxonomy;
+
+pub use comment::{Comment, CommentNode, CommentStatus, SubmittedComment};
+pub use media::{Media, MediaStatus, MediaVariant};
+pub use post::{Post, PostStatus, PostSummary};
+pub use role::Role;
+pub use search::{PostSuggestion, RelatedPost, SearchHit, Suggestions, TagSuggestion};
+pub use series::{Series, SeriesNavigation, SeriesPostLink, SeriesWithCount};
+pub use session::Session;
+pub use taxonomy::{Category, CategoryNode, Tag, TagWithCount};
+
+use chrono::{DateTime, Utc};
 use serde::Serialize;
-use sqlx::FromRow;
 
-#[derive(Debug, FromRow, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct User {
     pub id: i32,
     pub username: String,
     pub email: String,
     pub avatar_url: Option<String>,
     pub bio: Option<String>,
-    pub last_login: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+    pub last_login: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
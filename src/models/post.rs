@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+
+use crate::utils::markdown::TocEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "post_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    Draft,
+    Published,
+    Scheduled,
+    Archived,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Post {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub body_markdown: String,
+    // 渲染后的 HTML 缓存，尚未渲染时为 None
+    pub body_html: Option<String>,
+    pub toc: Json<Vec<TocEntry>>,
+    pub word_count: i32,
+    pub reading_time_minutes: i32,
+    pub excerpt: Option<String>,
+    pub author_id: i32,
+    pub status: PostStatus,
+    pub published_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Post {
+    /// Whether anonymous readers are allowed to see this post
+    pub fn is_public(&self) -> bool {
+        self.status == PostStatus::Published
+    }
+}
+
+/// Post without its body, used by listing endpoints
+#[derive(Debug, Clone, Serialize)]
+pub struct PostSummary {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub excerpt: Option<String>,
+    pub reading_time_minutes: i32,
+    pub author_id: i32,
+    pub status: PostStatus,
+    pub published_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,10 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Role {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    /// Names of the permissions the role grants
+    pub permissions: Vec<String>,
+}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A published post matching a search query
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub excerpt: Option<String>,
+    pub author_id: i32,
+    pub published_at: Option<DateTime<Utc>>,
+    /// Relevance score, only comparable within one search
+    pub rank: f32,
+    /// HTML-escaped title with the matches wrapped in `<mark>`
+    pub title_highlight: String,
+    /// HTML-escaped body fragments with the matches wrapped in `<mark>`
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PostSuggestion {
+    pub title: String,
+    pub slug: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagSuggestion {
+    pub name: String,
+    pub slug: String,
+    pub post_count: i64,
+}
+
+/// Search-as-you-type completions for a partial query
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Suggestions {
+    pub posts: Vec<PostSuggestion>,
+    pub tags: Vec<TagSuggestion>,
+}
+
+/// A published post similar to the one being viewed
+#[derive(Debug, Clone, Serialize)]
+pub struct RelatedPost {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub excerpt: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    /// Shared tags plus text similarity; higher is more related
+    pub score: f32,
+}
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Series {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub author_id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Series together with the number of published posts in it
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesWithCount {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub author_id: i32,
+    pub post_count: i64,
+}
+
+/// Minimal reference to a post inside a series
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeriesPostLink {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+}
+
+/// "Part N of M" navigation shown on a post that belongs to a series
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeriesNavigation {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    /// 1-based position of the current post among the visible parts
+    pub position: usize,
+    pub total: usize,
+    pub previous: Option<SeriesPostLink>,
+    pub next: Option<SeriesPostLink>,
+}
+
+impl SeriesNavigation {
+    /// Builds the navigation for `current_post_id` from the ordered parts
+    ///
+    /// Returns `None` if the current post is not one of the parts.
+    pub fn build(series: &Series, parts: &[SeriesPostLink], current_post_id: i32) -> Option<Self> {
+        let index = parts.iter().position(|p| p.id == current_post_id)?;
+
+        Some(Self {
+            id: series.id,
+            title: series.title.clone(),
+            slug: series.slug.clone(),
+            position: index + 1,
+            total: parts.len(),
+            previous: index.checked_sub(1).map(|i| parts[i].clone()),
+            next: parts.get(index + 1).cloned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(id: i32) -> SeriesPostLink {
+        SeriesPostLink {
+            id,
+            title: format!("Part {}", id),
+            slug: format!("part-{}", id),
+        }
+    }
+
+    #[test]
+    fn test_series_navigation() {
+        let series = Series {
+            id: 1,
+            title: "Tutorial".to_string(),
+            slug: "tutorial".to_string(),
+            description: None,
+            author_id: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let parts = vec![link(10), link(20), link(30)];
+
+        let first = SeriesNavigation::build(&series, &parts, 10).unwrap();
+        assert_eq!(first.position, 1);
+        assert_eq!(first.total, 3);
+        assert_eq!(first.previous, None);
+        assert_eq!(first.next, Some(link(20)));
+
+        let middle = SeriesNavigation::build(&series, &parts, 20).unwrap();
+        assert_eq!(middle.previous, Some(link(10)));
+        assert_eq!(middle.next, Some(link(30)));
+
+        let last = SeriesNavigation::build(&series, &parts, 30).unwrap();
+        assert_eq!(last.position, 3);
+        assert_eq!(last.next, None);
+
+        assert!(SeriesNavigation::build(&series, &parts, 99).is_none());
+    }
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Tag together with the number of published posts using it
+#[derive(Debug, Clone, Serialize)]
+pub struct TagWithCount {
+    pub id: i32,
+    pub name: String,
+    pub slug: String,
+    pub post_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Category {
+    pub id: i32,
+    pub name: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub parent_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Category with its sub-categories, used to return the hierarchy
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Category,
+    pub children: Vec<CategoryNode>,
+}
+
+impl CategoryNode {
+    /// Builds the category forest from a flat list
+    ///
+    /// Categories whose parent is not in the list are treated as roots.
+    pub fn build_tree(categories: Vec<Category>) -> Vec<CategoryNode> {
+        use std::collections::{HashMap, HashSet};
+
+        let ids: HashSet<i32> = categories.iter().map(|c| c.id).collect();
+        let mut by_parent: HashMap<Option<i32>, Vec<Category>> = HashMap::new();
+        for category in categories {
+            let parent = category.parent_id.filter(|id| ids.contains(id));
+            by_parent.entry(parent).or_default().push(category);
+        }
+
+        fn attach(
+            parent: Option<i32>,
+            by_parent: &mut HashMap<Option<i32>, Vec<Category>>,
+        ) -> Vec<CategoryNode> {
+            by_parent
+                .remove(&parent)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|category| {
+                    let children = attach(Some(category.id), by_parent);
+                    CategoryNode { category, children }
+                })
+                .collect()
+        }
+
+        attach(None, &mut by_parent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(id: i32, parent_id: Option<i32>) -> Category {
+        Category {
+            id,
+            name: format!("c{}", id),
+            slug: format!("c{}", id),
+            description: None,
+            parent_id,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_build_category_tree() {
+        let tree = CategoryNode::build_tree(vec![
+            category(1, None),
+            category(2, Some(1)),
+            category(3, Some(2)),
+            category(4, None),
+            category(5, Some(1)),
+            // 父级不在列表中时作为根节点
+            category(6, Some(99)),
+        ]);
+
+        let roots: Vec<i32> = tree.iter().map(|n| n.category.id).collect();
+        assert_eq!(roots, vec![1, 4, 6]);
+        assert_eq!(tree[0].children.len(), 2);
+        assert_eq!(tree[0].children[0].category.id, 2);
+        assert_eq!(tree[0].children[0].children[0].category.id, 3);
+        assert!(tree[1].children.is_empty());
+    }
+}
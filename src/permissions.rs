@@ -0,0 +1,68 @@
+use crate::error::AppResult;
+use crate::repositories::RoleRepository;
+use crate::utils::config::AdminConfig;
+
+/// A capability granted through roles, checked by
+/// [`crate::extractors::RequirePermission`]
+///
+/// `NAME` matches a row of the `permissions` table.
+pub trait Permission: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+/// Create posts and edit one's own
+pub enum WritePosts {}
+
+impl Permission for WritePosts {
+    const NAME: &'static str = "posts.write";
+}
+
+/// Edit, publish and delete posts written by someone else
+pub enum EditAnyPost {}
+
+impl Permission for EditAnyPost {
+    const NAME: &'static str = "posts.edit_any";
+}
+
+pub enum UploadMedia {}
+
+impl Permission for UploadMedia {
+    const NAME: &'static str = "media.upload";
+}
+
+/// Create, rename, merge and delete categories and tags
+pub enum ManageTaxonomy {}
+
+impl Permission for ManageTaxonomy {
+    const NAME: &'static str = "taxonomy.manage";
+}
+
+/// Moderate comments on every post, not only one's own
+pub enum ModerateComments {}
+
+impl Permission for ModerateComments {
+    const NAME: &'static str = "comments.moderate";
+}
+
+pub enum ManageRoles {}
+
+impl Permission for ManageRoles {
+    const NAME: &'static str = "roles.manage";
+}
+
+/// Whether a user holds a permission through one of their roles
+///
+/// Users listed in `admin.user_ids` hold every permission, so there is always
+/// someone who can hand out roles.
+pub async fn has_permission(
+    roles: &dyn RoleRepository,
+    admin: &AdminConfig,
+    user_id: i32,
+    permission: &str,
+) -> AppResult<bool> {
+    if admin.is_admin(user_id) {
+        return Ok(true);
+    }
+
+    roles.has_permission(user_id, permission).await
+}
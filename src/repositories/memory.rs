@@ -0,0 +1,400 @@
+use std::{
+    cmp::Reverse,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::roles::unknown_roles;
+use super::users::UserCredentials;
+use super::{
+    NewSession, NewUser, Refresh, RoleRepository, Rotation, SessionRepository, UserRepository,
+};
+use crate::error::AppResult;
+use crate::models::{Role, Session, User};
+use crate::response::StatusCode;
+
+/// Users kept in memory, for testing handlers without a database
+///
+/// Roles given at creation are assigned in the role repository passed to
+/// [`InMemoryUserRepository::with_roles`].
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    users: Mutex<Vec<UserCredentials>>,
+    roles: Option<Arc<InMemoryRoleRepository>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn with_roles(roles: Arc<InMemoryRoleRepository>) -> Self {
+        Self {
+            users: Mutex::default(),
+            roles: Some(roles),
+        }
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn create(&self, user: NewUser<'_>) -> AppResult<User> {
+        let mut users = self.users.lock().unwrap();
+        if users
+            .iter()
+            .any(|row| row.user.username == user.username || row.user.email == user.email)
+        {
+            return Err(StatusCode::duplicate_resource().into());
+        }
+
+        let id = users.len() as i32 + 1;
+        if let Some(role) = user.role {
+            let roles = self
+                .roles
+                .as_ref()
+                .expect("roles assigned without a role repository");
+            roles.assign(id, role)?;
+        }
+
+        let now = Utc::now();
+        let created = User {
+            id,
+            username: user.username.to_string(),
+            email: user.email.to_string(),
+            avatar_url: None,
+            bio: None,
+            last_login: None,
+            created_at: now,
+            updated_at: now,
+        };
+        users.push(UserCredentials {
+            user: created.clone(),
+            password_hash: user.password_hash.to_string(),
+        });
+
+        Ok(created)
+    }
+
+    async fn list(&self) -> AppResult<Vec<User>> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().rev().map(|row| row.user.clone()).collect())
+    }
+
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<User>> {
+        let users = self.users.lock().unwrap();
+        Ok(users
+            .iter()
+            .find(|row| row.user.id == id)
+            .map(|row| row.user.clone()))
+    }
+
+    async fn find_credentials_by_email(&self, email: &str) -> AppResult<Option<UserCredentials>> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().find(|row| row.user.email == email).cloned())
+    }
+}
+
+struct StoredSession {
+    session: Session,
+    user_id: i32,
+    revoked: bool,
+}
+
+struct StoredRefreshToken {
+    session_id: Uuid,
+    token_hash: String,
+    expires_at: DateTime<Utc>,
+    used: bool,
+}
+
+#[derive(Default)]
+struct SessionStore {
+    sessions: Vec<StoredSession>,
+    tokens: Vec<StoredRefreshToken>,
+}
+
+impl SessionStore {
+    fn session(&mut self, id: Uuid) -> &mut StoredSession {
+        self.sessions
+            .iter_mut()
+            .find(|row| row.session.id == id)
+            .expect("refresh token without session")
+    }
+}
+
+/// Sessions and refresh tokens kept in memory, for testing handlers without
+/// a database
+///
+/// Revoked tokens are refused through their session, so nothing needs to be
+/// recorded for `revoked_until`.
+#[derive(Default)]
+pub struct InMemorySessionRepository {
+    store: Mutex<SessionStore>,
+}
+
+#[async_trait]
+impl SessionRepository for InMemorySessionRepository {
+    async fn create(&self, session: NewSession<'_>) -> AppResult<()> {
+        let mut store = self.store.lock().unwrap();
+        let now = Utc::now();
+        store.sessions.push(StoredSession {
+            session: Session {
+                id: session.id,
+                user_agent: session.user_agent.map(str::to_string),
+                ip_address: session.ip_address.map(str::to_string),
+                created_at: now,
+                last_seen_at: now,
+                current: false,
+            },
+            user_id: session.user_id,
+            revoked: false,
+        });
+        store.tokens.push(StoredRefreshToken {
+            session_id: session.id,
+            token_hash: session.refresh_token_hash.to_string(),
+            expires_at: session.expires_at,
+            used: false,
+        });
+
+        Ok(())
+    }
+
+    async fn rotate(
+        &self,
+        refresh: Refresh<'_>,
+        now: DateTime<Utc>,
+        _revoked_until: DateTime<Utc>,
+    ) -> AppResult<Rotation> {
+        let mut store = self.store.lock().unwrap();
+        let Some(index) = store
+            .tokens
+            .iter()
+            .position(|row| row.token_hash == refresh.token_hash)
+        else {
+            return Ok(Rotation::Invalid);
+        };
+
+        let (session_id, used, expires_at) = {
+            let token = &store.tokens[index];
+            (token.session_id, token.used, token.expires_at)
+        };
+        let session = store.session(session_id);
+        let user_id = session.user_id;
+        if session.revoked {
+            return Ok(Rotation::Invalid);
+        }
+        if used {
+            session.revoked = true;
+            return Ok(Rotation::Reused {
+                user_id,
+                session_id,
+            });
+        }
+        if expires_at <= now {
+            return Ok(Rotation::Invalid);
+        }
+
+        session.session.last_seen_at = now;
+        if let Some(ip_address) = refresh.ip_address {
+            session.session.ip_address = Some(ip_address.to_string());
+        }
+        store.tokens[index].used = true;
+        store.tokens.push(StoredRefreshToken {
+            session_id,
+            token_hash: refresh.replacement_hash.to_string(),
+            expires_at: refresh.expires_at,
+            used: false,
+        });
+
+        Ok(Rotation::Rotated {
+            user_id,
+            session_id,
+        })
+    }
+
+    async fn list_active(
+        &self,
+        user_id: i32,
+        current: Option<Uuid>,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<Session>> {
+        let store = self.store.lock().unwrap();
+        let mut sessions: Vec<Session> = store
+            .sessions
+            .iter()
+            .filter(|row| row.user_id == user_id && !row.revoked)
+            .filter(|row| {
+                store.tokens.iter().any(|token| {
+                    token.session_id == row.session.id && !token.used && token.expires_at > now
+                })
+            })
+            .map(|row| Session {
+                current: current == Some(row.session.id),
+                ..row.session.clone()
+            })
+            .collect();
+        sessions.sort_by_key(|session| Reverse(session.last_seen_at));
+
+        Ok(sessions)
+    }
+
+    async fn revoke(
+        &self,
+        user_id: i32,
+        session_id: Option<Uuid>,
+        _now: DateTime<Utc>,
+        _revoked_until: DateTime<Utc>,
+    ) -> AppResult<Vec<Uuid>> {
+        let mut store = self.store.lock().unwrap();
+        let mut revoked = Vec::new();
+        for row in store.sessions.iter_mut().filter(|row| {
+            row.user_id == user_id
+                && !row.revoked
+                && session_id.is_none_or(|id| id == row.session.id)
+        }) {
+            row.revoked = true;
+            revoked.push(row.session.id);
+        }
+
+        Ok(revoked)
+    }
+
+    async fn revoke_by_refresh_token(
+        &self,
+        token_hash: &str,
+        _now: DateTime<Utc>,
+        _revoked_until: DateTime<Utc>,
+    ) -> AppResult<Option<Uuid>> {
+        let mut store = self.store.lock().unwrap();
+        let Some(session_id) = store
+            .tokens
+            .iter()
+            .find(|row| row.token_hash == token_hash)
+            .map(|row| row.session_id)
+        else {
+            return Ok(None);
+        };
+        store.session(session_id).revoked = true;
+
+        Ok(Some(session_id))
+    }
+}
+
+/// Roles kept in memory, seeded with the roles and permissions of the
+/// migration
+pub struct InMemoryRoleRepository {
+    roles: Vec<Role>,
+    assignments: Mutex<Vec<(i32, String)>>,
+}
+
+impl Default for InMemoryRoleRepository {
+    fn default() -> Self {
+        let role = |id: i32, name: &str, permissions: &[&str]| Role {
+            id,
+            name: name.to_string(),
+            description: String::new(),
+            permissions: permissions.iter().map(|name| name.to_string()).collect(),
+        };
+
+        Self {
+            roles: vec![
+                role(
+                    1,
+                    "admin",
+                    &[
+                        "comments.moderate",
+                        "media.upload",
+                        "posts.edit_any",
+                        "posts.write",
+                        "roles.manage",
+                        "taxonomy.manage",
+                    ],
+                ),
+                role(
+                    2,
+                    "editor",
+                    &[
+                        "comments.moderate",
+                        "media.upload",
+                        "posts.edit_any",
+                        "posts.write",
+                        "taxonomy.manage",
+                    ],
+                ),
+                role(3, "author", &["media.upload", "posts.write"]),
+                role(4, "reader", &[]),
+            ],
+            assignments: Mutex::default(),
+        }
+    }
+}
+
+impl InMemoryRoleRepository {
+    fn assign(&self, user_id: i32, role: &str) -> AppResult<()> {
+        if !self.roles.iter().any(|known| known.name == role) {
+            return Err(anyhow::anyhow!("Role {} does not exist", role).into());
+        }
+
+        self.assignments
+            .lock()
+            .unwrap()
+            .push((user_id, role.to_string()));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RoleRepository for InMemoryRoleRepository {
+    async fn list(&self) -> AppResult<Vec<Role>> {
+        let mut roles = self.roles.clone();
+        roles.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(roles)
+    }
+
+    async fn roles_of(&self, user_id: i32) -> AppResult<Vec<String>> {
+        let mut roles: Vec<String> = self
+            .assignments
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _)| *id == user_id)
+            .map(|(_, role)| role.clone())
+            .collect();
+        roles.sort();
+        Ok(roles)
+    }
+
+    async fn set_roles(
+        &self,
+        user_id: i32,
+        roles: &[String],
+        _granted_by: Option<i32>,
+    ) -> AppResult<()> {
+        let unknown: Vec<&String> = roles
+            .iter()
+            .filter(|name| !self.roles.iter().any(|role| &role.name == *name))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(unknown_roles(unknown).into());
+        }
+
+        let mut assignments = self.assignments.lock().unwrap();
+        assignments.retain(|(id, _)| *id != user_id);
+        for role in roles {
+            if !assignments.contains(&(user_id, role.clone())) {
+                assignments.push((user_id, role.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn has_permission(&self, user_id: i32, permission: &str) -> AppResult<bool> {
+        let assignments = self.assignments.lock().unwrap();
+        Ok(assignments.iter().any(|(id, name)| {
+            *id == user_id
+                && self.roles.iter().any(|role| {
+                    &role.name == name && role.permissions.iter().any(|p| p == permission)
+                })
+        }))
+    }
+}
@@ -0,0 +1,11 @@
+#[cfg(test)]
+mod memory;
+mod roles;
+mod sessions;
+mod users;
+
+#[cfg(test)]
+pub use memory::{InMemoryRoleRepository, InMemorySessionRepository, InMemoryUserRepository};
+pub use roles::{PgRoleRepository, RoleRepository};
+pub use sessions::{NewSession, PgSessionRepository, Refresh, Rotation, SessionRepository};
+pub use users::{NewUser, PgUserRepository, UserRepository};
@@ -0,0 +1,162 @@
+use anyhow::Context;
+use async_trait::async_trait;
+
+use crate::db::Database;
+use crate::error::AppResult;
+use crate::models::Role;
+use crate::response::{ErrorDetail, ErrorResponse, StatusCode};
+
+/// Storage of roles, the permissions they grant and who holds them
+#[async_trait]
+pub trait RoleRepository: Send + Sync {
+    /// All roles with their permissions, by name
+    async fn list(&self) -> AppResult<Vec<Role>>;
+
+    /// Names of the roles a user holds, sorted
+    async fn roles_of(&self, user_id: i32) -> AppResult<Vec<String>>;
+
+    /// Replaces the roles of a user
+    ///
+    /// Roles the user keeps retain their original grant. Unknown role names
+    /// fail with a parameter error and change nothing.
+    async fn set_roles(
+        &self,
+        user_id: i32,
+        roles: &[String],
+        granted_by: Option<i32>,
+    ) -> AppResult<()>;
+
+    async fn has_permission(&self, user_id: i32, permission: &str) -> AppResult<bool>;
+}
+
+/// Parameter error naming the roles that do not exist
+pub(crate) fn unknown_roles<'a>(names: impl IntoIterator<Item = &'a String>) -> ErrorResponse {
+    StatusCode::param_error().with_errors(
+        names
+            .into_iter()
+            .map(|name| ErrorDetail {
+                field: Some("roles".to_string()),
+                message: format!("Unknown role: {}", name),
+            })
+            .collect(),
+    )
+}
+
+pub struct PgRoleRepository {
+    db: Database,
+}
+
+impl PgRoleRepository {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl RoleRepository for PgRoleRepository {
+    async fn list(&self) -> AppResult<Vec<Role>> {
+        let roles = sqlx::query_as!(
+            Role,
+            r#"SELECT r.id, r.name, r.description,
+                COALESCE(
+                    ARRAY_AGG(p.name ORDER BY p.name) FILTER (WHERE p.name IS NOT NULL),
+                    '{}'
+                ) AS "permissions!"
+            FROM roles r
+            LEFT JOIN role_permissions rp ON rp.role_id = r.id
+            LEFT JOIN permissions p ON p.id = rp.permission_id
+            GROUP BY r.id
+            ORDER BY r.name"#,
+        )
+        .fetch_all(self.db.read())
+        .await
+        .context("Failed to query roles")?;
+
+        Ok(roles)
+    }
+
+    async fn roles_of(&self, user_id: i32) -> AppResult<Vec<String>> {
+        let roles = sqlx::query_scalar!(
+            r#"SELECT r.name FROM user_roles ur JOIN roles r ON r.id = ur.role_id
+            WHERE ur.user_id = $1
+            ORDER BY r.name"#,
+            user_id,
+        )
+        .fetch_all(self.db.primary())
+        .await
+        .context("Failed to query user roles")?;
+
+        Ok(roles)
+    }
+
+    async fn set_roles(
+        &self,
+        user_id: i32,
+        roles: &[String],
+        granted_by: Option<i32>,
+    ) -> AppResult<()> {
+        let mut tx = self
+            .db
+            .primary()
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        let found = sqlx::query!(r#"SELECT id, name FROM roles WHERE name = ANY($1)"#, roles,)
+            .fetch_all(&mut *tx)
+            .await
+            .context("Failed to query roles")?;
+
+        let unknown: Vec<&String> = roles
+            .iter()
+            .filter(|name| !found.iter().any(|role| &role.name == *name))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(unknown_roles(unknown).into());
+        }
+
+        let role_ids: Vec<i32> = found.iter().map(|role| role.id).collect();
+        sqlx::query!(
+            r#"DELETE FROM user_roles WHERE user_id = $1 AND role_id <> ALL($2)"#,
+            user_id,
+            &role_ids,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to remove user roles")?;
+
+        sqlx::query!(
+            r#"INSERT INTO user_roles (user_id, role_id, granted_by)
+            SELECT $1, role_id, $3 FROM unnest($2::INTEGER[]) AS role_id
+            ON CONFLICT (user_id, role_id) DO NOTHING"#,
+            user_id,
+            &role_ids,
+            granted_by,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to assign user roles")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(())
+    }
+
+    async fn has_permission(&self, user_id: i32, permission: &str) -> AppResult<bool> {
+        let granted = sqlx::query_scalar!(
+            r#"SELECT EXISTS (
+                SELECT 1 FROM user_roles ur
+                JOIN role_permissions rp ON rp.role_id = ur.role_id
+                JOIN permissions p ON p.id = rp.permission_id
+                WHERE ur.user_id = $1 AND p.name = $2
+            ) AS "exists!""#,
+            user_id,
+            permission,
+        )
+        .fetch_one(self.db.primary())
+        .await
+        .context("Failed to query permissions")?;
+
+        Ok(granted)
+    }
+}
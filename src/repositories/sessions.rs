@@ -0,0 +1,354 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::AppResult;
+use crate::models::Session;
+
+/// Fields needed to start a session with its first refresh token; the token
+/// is already hashed
+pub struct NewSession<'a> {
+    pub id: Uuid,
+    pub user_id: i32,
+    pub user_agent: Option<&'a str>,
+    pub ip_address: Option<&'a str>,
+    pub refresh_token_hash: &'a str,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A refresh token presented for exchange, and the token replacing it
+pub struct Refresh<'a> {
+    pub token_hash: &'a str,
+    pub replacement_hash: &'a str,
+    pub expires_at: DateTime<Utc>,
+    pub ip_address: Option<&'a str>,
+}
+
+/// Outcome of exchanging a refresh token for a new one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// The token was valid and has been replaced
+    Rotated { user_id: i32, session_id: Uuid },
+    /// The token had already been exchanged, so it was stolen or replayed;
+    /// its session has been revoked
+    Reused { user_id: i32, session_id: Uuid },
+    /// Unknown, expired or revoked
+    Invalid,
+}
+
+/// Storage of sign-in sessions and their refresh tokens
+///
+/// Every refresh replaces the presented token with a new one of the same
+/// session (the token's family). Presenting a replaced token again revokes
+/// the session, so a leaked token stops working for both the thief and the
+/// user.
+///
+/// Revoking a session also records its id in `revoked_tokens` until
+/// `revoked_until`, by which time every access token issued for it has
+/// expired.
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    async fn create(&self, session: NewSession<'_>) -> AppResult<()>;
+
+    /// Marks the presented token as used, stores its replacement in the same
+    /// session and records the session as seen
+    async fn rotate(
+        &self,
+        refresh: Refresh<'_>,
+        now: DateTime<Utc>,
+        revoked_until: DateTime<Utc>,
+    ) -> AppResult<Rotation>;
+
+    /// Sessions of a user that can still be refreshed, most recently seen
+    /// first; `current` is flagged
+    async fn list_active(
+        &self,
+        user_id: i32,
+        current: Option<Uuid>,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<Session>>;
+
+    /// Revokes one session of a user, or all of them without `session_id`;
+    /// returns the ids that were revoked
+    async fn revoke(
+        &self,
+        user_id: i32,
+        session_id: Option<Uuid>,
+        now: DateTime<Utc>,
+        revoked_until: DateTime<Utc>,
+    ) -> AppResult<Vec<Uuid>>;
+
+    /// Revokes the session a refresh token belongs to, returning its id
+    async fn revoke_by_refresh_token(
+        &self,
+        token_hash: &str,
+        now: DateTime<Utc>,
+        revoked_until: DateTime<Utc>,
+    ) -> AppResult<Option<Uuid>>;
+}
+
+pub struct PgSessionRepository {
+    db: Database,
+}
+
+impl PgSessionRepository {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+/// Revokes the refresh tokens of sessions and records the sessions as revoked
+async fn revoke_sessions(
+    tx: &mut Transaction<'_, Postgres>,
+    session_ids: &[Uuid],
+    now: DateTime<Utc>,
+    revoked_until: DateTime<Utc>,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"UPDATE sessions SET revoked_at = $2 WHERE id = ANY($1) AND revoked_at IS NULL"#,
+        session_ids,
+        now,
+    )
+    .execute(&mut **tx)
+    .await
+    .context("Failed to revoke sessions")?;
+
+    sqlx::query!(
+        r#"UPDATE refresh_tokens SET revoked_at = $2
+        WHERE family_id = ANY($1) AND revoked_at IS NULL"#,
+        session_ids,
+        now,
+    )
+    .execute(&mut **tx)
+    .await
+    .context("Failed to revoke refresh tokens")?;
+
+    sqlx::query!(
+        r#"INSERT INTO revoked_tokens (id, expires_at)
+        SELECT id, $2 FROM unnest($1::UUID[]) AS id
+        ON CONFLICT (id) DO UPDATE SET expires_at = EXCLUDED.expires_at"#,
+        session_ids,
+        revoked_until,
+    )
+    .execute(&mut **tx)
+    .await
+    .context("Failed to record revoked sessions")?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl SessionRepository for PgSessionRepository {
+    async fn create(&self, session: NewSession<'_>) -> AppResult<()> {
+        let mut tx = self
+            .db
+            .primary()
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        sqlx::query!(
+            r#"INSERT INTO sessions (id, user_id, user_agent, ip_address)
+            VALUES ($1, $2, $3, $4)"#,
+            session.id,
+            session.user_id,
+            session.user_agent,
+            session.ip_address,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to create session")?;
+
+        sqlx::query!(
+            r#"INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4)"#,
+            session.user_id,
+            session.id,
+            session.refresh_token_hash,
+            session.expires_at,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to store refresh token")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(())
+    }
+
+    async fn rotate(
+        &self,
+        refresh: Refresh<'_>,
+        now: DateTime<Utc>,
+        revoked_until: DateTime<Utc>,
+    ) -> AppResult<Rotation> {
+        let mut tx = self
+            .db
+            .primary()
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        // 锁住该行，同一令牌的并发刷新只有一个能成功，其余按重放处理
+        let Some(token) = sqlx::query!(
+            r#"SELECT user_id, family_id, expires_at, used_at, revoked_at
+            FROM refresh_tokens WHERE token_hash = $1
+            FOR UPDATE"#,
+            refresh.token_hash,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to query refresh token")?
+        else {
+            return Ok(Rotation::Invalid);
+        };
+
+        if token.revoked_at.is_some() {
+            return Ok(Rotation::Invalid);
+        }
+
+        if token.used_at.is_some() {
+            revoke_sessions(&mut tx, &[token.family_id], now, revoked_until).await?;
+            tx.commit().await.context("Failed to commit transaction")?;
+
+            return Ok(Rotation::Reused {
+                user_id: token.user_id,
+                session_id: token.family_id,
+            });
+        }
+
+        if token.expires_at <= now {
+            return Ok(Rotation::Invalid);
+        }
+
+        sqlx::query!(
+            r#"UPDATE refresh_tokens SET used_at = $2 WHERE token_hash = $1"#,
+            refresh.token_hash,
+            now,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update refresh token")?;
+
+        sqlx::query!(
+            r#"INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4)"#,
+            token.user_id,
+            token.family_id,
+            refresh.replacement_hash,
+            refresh.expires_at,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to store refresh token")?;
+
+        sqlx::query!(
+            r#"UPDATE sessions SET last_seen_at = $2, ip_address = COALESCE($3, ip_address)
+            WHERE id = $1"#,
+            token.family_id,
+            now,
+            refresh.ip_address,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update session")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(Rotation::Rotated {
+            user_id: token.user_id,
+            session_id: token.family_id,
+        })
+    }
+
+    async fn list_active(
+        &self,
+        user_id: i32,
+        current: Option<Uuid>,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<Session>> {
+        let sessions = sqlx::query_as!(
+            Session,
+            r#"SELECT s.id, s.user_agent, s.ip_address, s.created_at, s.last_seen_at,
+                COALESCE(s.id = $2, FALSE) AS "current!"
+            FROM sessions s
+            WHERE s.user_id = $1 AND s.revoked_at IS NULL
+                AND EXISTS (
+                    SELECT 1 FROM refresh_tokens t
+                    WHERE t.family_id = s.id AND t.used_at IS NULL
+                        AND t.revoked_at IS NULL AND t.expires_at > $3)
+            ORDER BY s.last_seen_at DESC, s.created_at DESC"#,
+            user_id,
+            current,
+            now,
+        )
+        .fetch_all(self.db.primary())
+        .await
+        .context("Failed to query sessions")?;
+
+        Ok(sessions)
+    }
+
+    async fn revoke(
+        &self,
+        user_id: i32,
+        session_id: Option<Uuid>,
+        now: DateTime<Utc>,
+        revoked_until: DateTime<Utc>,
+    ) -> AppResult<Vec<Uuid>> {
+        let mut tx = self
+            .db
+            .primary()
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        let session_ids = sqlx::query_scalar!(
+            r#"SELECT id FROM sessions
+            WHERE user_id = $1 AND ($2::UUID IS NULL OR id = $2) AND revoked_at IS NULL
+            FOR UPDATE"#,
+            user_id,
+            session_id,
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to query sessions")?;
+
+        revoke_sessions(&mut tx, &session_ids, now, revoked_until).await?;
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(session_ids)
+    }
+
+    async fn revoke_by_refresh_token(
+        &self,
+        token_hash: &str,
+        now: DateTime<Utc>,
+        revoked_until: DateTime<Utc>,
+    ) -> AppResult<Option<Uuid>> {
+        let mut tx = self
+            .db
+            .primary()
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        let session_id = sqlx::query_scalar!(
+            r#"SELECT family_id FROM refresh_tokens WHERE token_hash = $1"#,
+            token_hash,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to query refresh token")?;
+
+        if let Some(session_id) = session_id {
+            revoke_sessions(&mut tx, &[session_id], now, revoked_until).await?;
+        }
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(session_id)
+    }
+}
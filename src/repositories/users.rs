@@ -0,0 +1,156 @@
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+
+use crate::db::Database;
+use crate::error::{AppError, AppResult};
+use crate::models::User;
+use crate::response::StatusCode;
+
+/// Fields needed to insert a user; the password is already hashed
+pub struct NewUser<'a> {
+    pub username: &'a str,
+    pub email: &'a str,
+    pub password_hash: &'a str,
+    /// Role granted together with the account, so a user is never left
+    /// without it
+    pub role: Option<&'a str>,
+}
+
+/// A user together with the password hash, for signing in
+#[derive(Debug, Clone)]
+pub struct UserCredentials {
+    pub user: User,
+    pub password_hash: String,
+}
+
+/// Storage of user accounts
+///
+/// A username or email that is already taken fails with a duplicate
+/// resource error. Creating a user with a role that does not exist fails
+/// without creating the user.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn create(&self, user: NewUser<'_>) -> AppResult<User>;
+
+    /// All users, newest first
+    async fn list(&self) -> AppResult<Vec<User>>;
+
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<User>>;
+
+    async fn find_credentials_by_email(&self, email: &str) -> AppResult<Option<UserCredentials>>;
+}
+
+pub struct PgUserRepository {
+    db: Database,
+}
+
+impl PgUserRepository {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl UserRepository for PgUserRepository {
+    async fn create(&self, user: NewUser<'_>) -> AppResult<User> {
+        let mut tx = self
+            .db
+            .primary()
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        let created = sqlx::query_as!(
+            User,
+            r#"INSERT INTO users (username, email, avatar_url, bio, password_hash)
+            VALUES ($1, $2, NULL, NULL, $3)
+            RETURNING id, username, email, avatar_url, bio, last_login, created_at, updated_at"#,
+            user.username,
+            user.email,
+            user.password_hash,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            // 用户名或邮箱已被占用，与内存实现返回同样的错误
+            Some(db_err) if db_err.is_unique_violation() => {
+                AppError::from(StatusCode::duplicate_resource())
+            }
+            _ => anyhow::Error::new(e)
+                .context("Failed to create user")
+                .into(),
+        })?;
+
+        if let Some(role) = user.role {
+            let assigned = sqlx::query!(
+                r#"INSERT INTO user_roles (user_id, role_id)
+                SELECT $1, id FROM roles WHERE name = $2"#,
+                created.id,
+                role,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to assign user role")?;
+            if assigned.rows_affected() == 0 {
+                return Err(anyhow!("Role {} does not exist", role).into());
+            }
+        }
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(created)
+    }
+
+    async fn list(&self) -> AppResult<Vec<User>> {
+        let users = sqlx::query_as!(
+            User,
+            r#"SELECT id, username, email, avatar_url, bio, last_login, created_at, updated_at
+            FROM users ORDER BY created_at DESC"#
+        )
+        .fetch_all(self.db.read())
+        .await
+        .context("Failed to query users")?;
+
+        Ok(users)
+    }
+
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            r#"SELECT id, username, email, avatar_url, bio, last_login, created_at, updated_at
+            FROM users WHERE id = $1"#,
+            id,
+        )
+        .fetch_optional(self.db.primary())
+        .await
+        .context("Failed to query user")?;
+
+        Ok(user)
+    }
+
+    async fn find_credentials_by_email(&self, email: &str) -> AppResult<Option<UserCredentials>> {
+        let row = sqlx::query!(
+            r#"SELECT id, username, email, avatar_url, bio, last_login, created_at, updated_at,
+                password_hash
+            FROM users WHERE email = $1"#,
+            email,
+        )
+        .fetch_optional(self.db.primary())
+        .await
+        .context("Failed to query user")?;
+
+        Ok(row.map(|row| UserCredentials {
+            user: User {
+                id: row.id,
+                username: row.username,
+                email: row.email,
+                avatar_url: row.avatar_url,
+                bio: row.bio,
+                last_login: row.last_login,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            },
+            password_hash: row.password_hash,
+        }))
+    }
+}
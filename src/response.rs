@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
 use axum::response::IntoResponse;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 // 状态码定义
@@ -43,6 +43,10 @@ pub enum StatusCode {
     Conflict = 40900,
     DuplicateResource = 40901,
 
+    // 请求体错误（413xx/415xx）
+    PayloadTooLarge = 41300,
+    UnsupportedMediaType = 41500,
+
     // 系统错误（500xx）
     InternalError = 50000,
     ServiceUnavailable = 50001,
@@ -121,6 +125,15 @@ pub struct PaginationInfo {
     pub total_pages: u32,
 }
 
+// 分页查询参数，配合 `Query<PaginationQuery>` 使用
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct PaginationQuery {
+    #[serde(default = "PaginationQuery::default_page")]
+    pub page: u32,
+    #[serde(default = "PaginationQuery::default_page_size")]
+    pub page_size: u32,
+}
+
 // 分页响应结构体
 #[derive(Debug, Serialize)]
 pub struct PaginationResponse<T> {
@@ -210,6 +223,69 @@ impl ErrorResponse {
     }
 }
 
+impl Default for PaginationQuery {
+    fn default() -> Self {
+        Self {
+            page: Self::default_page(),
+            page_size: Self::default_page_size(),
+        }
+    }
+}
+
+impl PaginationQuery {
+    pub const MAX_PAGE_SIZE: u32 = 100;
+
+    fn default_page() -> u32 {
+        1
+    }
+
+    fn default_page_size() -> u32 {
+        20
+    }
+
+    // 页码从 1 开始
+    pub fn page(&self) -> u32 {
+        self.page.max(1)
+    }
+
+    // 每页数量限制在 1..=MAX_PAGE_SIZE
+    pub fn page_size(&self) -> u32 {
+        self.page_size.clamp(1, Self::MAX_PAGE_SIZE)
+    }
+
+    // SQL LIMIT
+    pub fn limit(&self) -> i64 {
+        self.page_size() as i64
+    }
+
+    // SQL OFFSET
+    pub fn offset(&self) -> i64 {
+        (self.page() as i64 - 1) * self.page_size() as i64
+    }
+
+    // 根据总数生成分页信息
+    pub fn info(&self, total: u64) -> PaginationInfo {
+        PaginationInfo::new(self.page(), self.page_size(), total)
+    }
+}
+
+impl PaginationInfo {
+    pub fn new(page: u32, page_size: u32, total: u64) -> Self {
+        let total_pages = if page_size == 0 {
+            0
+        } else {
+            total.div_ceil(page_size as u64) as u32
+        };
+
+        Self {
+            page,
+            page_size,
+            total,
+            total_pages,
+        }
+    }
+}
+
 impl<T> PaginationResponse<T> {
     // 创建分页响应
     pub fn new(
@@ -302,6 +378,14 @@ impl StatusCode {
         ErrorResponse::new(StatusCode::DuplicateResource, "Duplicate Resource")
     }
 
+    pub fn payload_too_large() -> ErrorResponse {
+        ErrorResponse::new(StatusCode::PayloadTooLarge, "Payload Too Large")
+    }
+
+    pub fn unsupported_media_type() -> ErrorResponse {
+        ErrorResponse::new(StatusCode::UnsupportedMediaType, "Unsupported Media Type")
+    }
+
     pub fn internal_error() -> ErrorResponse {
         ErrorResponse::new(StatusCode::InternalError, "Internal Server Error")
     }
@@ -338,6 +422,8 @@ impl IntoResponse for ErrorResponse {
             StatusCode::Forbidden | StatusCode::AccessDenied => AxumStatusCode::FORBIDDEN,
             StatusCode::NotFound | StatusCode::ResourceNotFound => AxumStatusCode::NOT_FOUND,
             StatusCode::Conflict | StatusCode::DuplicateResource => AxumStatusCode::CONFLICT,
+            StatusCode::PayloadTooLarge => AxumStatusCode::PAYLOAD_TOO_LARGE,
+            StatusCode::UnsupportedMediaType => AxumStatusCode::UNSUPPORTED_MEDIA_TYPE,
             StatusCode::InternalError
             | StatusCode::ServiceUnavailable
             | StatusCode::DatabaseError => AxumStatusCode::INTERNAL_SERVER_ERROR,
@@ -519,6 +605,40 @@ mod tests {
         assert_eq!(response.version.unwrap(), version);
     }
 
+    #[test]
+    fn test_pagination_query_bounds() {
+        let query = PaginationQuery {
+            page: 0,
+            page_size: 1000,
+        };
+        assert_eq!(query.page(), 1);
+        assert_eq!(query.page_size(), PaginationQuery::MAX_PAGE_SIZE);
+        assert_eq!(query.offset(), 0);
+
+        let query = PaginationQuery {
+            page: 3,
+            page_size: 10,
+        };
+        assert_eq!(query.limit(), 10);
+        assert_eq!(query.offset(), 20);
+
+        let info = query.info(25);
+        assert_eq!(info.page, 3);
+        assert_eq!(info.total, 25);
+        assert_eq!(info.total_pages, 3);
+
+        assert_eq!(PaginationInfo::new(1, 10, 0).total_pages, 0);
+        assert_eq!(PaginationInfo::new(1, 10, 30).total_pages, 3);
+    }
+
+    #[test]
+    fn test_pagination_query_defaults() {
+        let query: PaginationQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(query, PaginationQuery::default());
+        assert_eq!(query.page(), 1);
+        assert_eq!(query.page_size(), 20);
+    }
+
     #[test]
     fn test_response_serialization() {
         let data = "test data";
@@ -560,6 +680,8 @@ mod tests {
         let _ = StatusCode::resource_not_found();
         let _ = StatusCode::conflict();
         let _ = StatusCode::duplicate_resource();
+        let _ = StatusCode::payload_too_large();
+        let _ = StatusCode::unsupported_media_type();
         let _ = StatusCode::internal_error();
         let _ = StatusCode::service_unavailable();
         let _ = StatusCode::database_error();
@@ -0,0 +1,519 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::{get, post, put},
+};
+use serde::Deserialize;
+use sqlx::{PgExecutor, PgPool};
+use validator::Validate;
+
+use crate::db::ReadPool;
+use crate::error::AppResult;
+use crate::extractors::{RequirePermission, ValidatedJson};
+use crate::models::{Category, CategoryNode, PostStatus, PostSummary};
+use crate::permissions::{ManageTaxonomy, WritePosts};
+use crate::repositories::RoleRepository;
+use crate::response::{
+    ErrorDetail, PaginationQuery, PaginationResponse, StatusCode, SuccessResponse,
+};
+use crate::routes::{
+    double_option, posts::fetch_owned_post, slug_for, tags::MergeRequest, validate_slug,
+};
+use crate::state::AppState;
+use crate::utils::config::AppConfig;
+
+// 删除冲突时最多列出的文章数量
+const CONFLICT_DETAIL_LIMIT: i64 = 20;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/categories/list", get(list_categories))
+        .route("/categories/create", post(create_category))
+        .route(
+            "/categories/id/{id}",
+            put(update_category).delete(delete_category),
+        )
+        .route("/categories/id/{id}/merge", post(merge_category))
+        .route("/categories/{slug}/posts", get(list_category_posts))
+        .route("/posts/id/{id}/categories", put(set_post_categories))
+}
+
+/// Categories attached to a post, ordered by name
+pub(crate) async fn categories_for_post(pool: &PgPool, post_id: i32) -> AppResult<Vec<Category>> {
+    let categories = sqlx::query_as!(
+        Category,
+        r#"SELECT c.id, c.name, c.slug, c.description, c.parent_id, c.created_at, c.updated_at
+        FROM categories c JOIN post_categories pc ON pc.category_id = c.id
+        WHERE pc.post_id = $1
+        ORDER BY c.name"#,
+        post_id,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query post categories")?;
+
+    Ok(categories)
+}
+
+/// Whether `ancestor` is `category` itself or one of its ancestors
+async fn is_self_or_ancestor<'e>(
+    executor: impl PgExecutor<'e>,
+    category: i32,
+    ancestor: i32,
+) -> AppResult<bool> {
+    let found: bool = sqlx::query_scalar!(
+        r#"WITH RECURSIVE ancestors AS (
+            SELECT id, parent_id FROM categories WHERE id = $1
+            UNION
+            SELECT c.id, c.parent_id FROM categories c JOIN ancestors a ON c.id = a.parent_id
+        )
+        SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = $2) AS "exists!""#,
+        category,
+        ancestor,
+    )
+    .fetch_one(executor)
+    .await
+    .context("Failed to query category ancestors")?;
+
+    Ok(found)
+}
+
+/// Serialises moves within the category tree for the rest of the transaction
+///
+/// The cycle check reads the whole ancestor chain, so two concurrent moves
+/// (A below B and B below A) could otherwise both pass it.
+async fn lock_category_tree<'e>(executor: impl PgExecutor<'e>) -> AppResult<()> {
+    sqlx::query!(r#"SELECT pg_advisory_xact_lock(hashtext('categories.tree')) AS "locked: ()""#)
+        .execute(executor)
+        .await
+        .context("Failed to lock category tree")?;
+
+    Ok(())
+}
+
+async fn ensure_parent_exists<'e>(executor: impl PgExecutor<'e>, parent_id: i32) -> AppResult<()> {
+    let exists: bool = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM categories WHERE id = $1) AS "exists!""#,
+        parent_id,
+    )
+    .fetch_one(executor)
+    .await
+    .context("Failed to query parent category")?;
+
+    if !exists {
+        return Err(StatusCode::param_error()
+            .with_errors(vec![ErrorDetail {
+                field: Some("parent_id".to_string()),
+                message: format!("Category {} does not exist", parent_id),
+            }])
+            .into());
+    }
+
+    Ok(())
+}
+
+async fn list_categories(
+    State(ReadPool(pool)): State<ReadPool>,
+) -> AppResult<Json<SuccessResponse<Vec<CategoryNode>>>> {
+    let categories = sqlx::query_as!(
+        Category,
+        r#"SELECT id, name, slug, description, parent_id, created_at, updated_at
+        FROM categories
+        ORDER BY name"#,
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query categories")?;
+
+    Ok(StatusCode::success(Some(CategoryNode::build_tree(categories))).into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateCategoryRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+
+    #[validate(custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+
+    pub parent_id: Option<i32>,
+}
+
+async fn create_category(
+    State(pool): State<PgPool>,
+    _auth: RequirePermission<ManageTaxonomy>,
+    ValidatedJson(payload): ValidatedJson<CreateCategoryRequest>,
+) -> AppResult<Json<SuccessResponse<Category>>> {
+    let slug = slug_for(&payload.name, payload.slug.as_deref())?;
+    if let Some(parent_id) = payload.parent_id {
+        ensure_parent_exists(&pool, parent_id).await?;
+    }
+
+    let category = sqlx::query_as!(
+        Category,
+        r#"INSERT INTO categories (name, slug, description, parent_id) VALUES ($1, $2, $3, $4)
+        RETURNING id, name, slug, description, parent_id, created_at, updated_at"#,
+        &payload.name,
+        &slug,
+        payload.description,
+        payload.parent_id,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to create category")?;
+
+    Ok(StatusCode::created(Some(category)).into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateCategoryRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+
+    #[validate(custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+
+    /// Absent keeps the current parent, `null` moves the category to the top
+    #[serde(default, deserialize_with = "double_option")]
+    pub parent_id: Option<Option<i32>>,
+}
+
+async fn update_category(
+    State(pool): State<PgPool>,
+    _auth: RequirePermission<ManageTaxonomy>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<UpdateCategoryRequest>,
+) -> AppResult<Json<SuccessResponse<Category>>> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    if let Some(Some(parent_id)) = payload.parent_id {
+        lock_category_tree(&mut *tx).await?;
+        ensure_parent_exists(&mut *tx, parent_id).await?;
+
+        // 新的父级不能是自己或自己的子孙，否则会形成环
+        if is_self_or_ancestor(&mut *tx, parent_id, id).await? {
+            return Err(StatusCode::param_error()
+                .with_errors(vec![ErrorDetail {
+                    field: Some("parent_id".to_string()),
+                    message: "A category cannot be moved below itself".to_string(),
+                }])
+                .into());
+        }
+    }
+
+    let category = sqlx::query_as!(
+        Category,
+        r#"UPDATE categories SET
+            name = COALESCE($2, name),
+            slug = COALESCE($3, slug),
+            description = COALESCE($4, description),
+            parent_id = CASE WHEN $5 THEN $6 ELSE parent_id END,
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, name, slug, description, parent_id, created_at, updated_at"#,
+        id,
+        payload.name,
+        payload.slug,
+        payload.description,
+        payload.parent_id.is_some(),
+        payload.parent_id.flatten(),
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .context("Failed to update category")?
+    .ok_or_else(StatusCode::resource_not_found)?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    Ok(StatusCode::success(Some(category)).into())
+}
+
+async fn delete_category(
+    State(pool): State<PgPool>,
+    _auth: RequirePermission<ManageTaxonomy>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<()>>> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    sqlx::query_scalar!(r#"SELECT id FROM categories WHERE id = $1 FOR UPDATE"#, id)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to query category")?
+        .ok_or_else(StatusCode::resource_not_found)?;
+
+    let usage: i64 = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM post_categories WHERE category_id = $1"#,
+        id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to count category usage")?;
+
+    let children: Vec<String> = sqlx::query_scalar!(
+        r#"SELECT slug FROM categories WHERE parent_id = $1 ORDER BY id"#,
+        id,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("Failed to query sub-categories")?;
+
+    if usage > 0 || !children.is_empty() {
+        let mut details = Vec::new();
+
+        if usage > 0 {
+            let slugs: Vec<String> = sqlx::query_scalar!(
+                r#"SELECT p.slug FROM post_categories pc JOIN posts p ON p.id = pc.post_id
+                WHERE pc.category_id = $1
+                ORDER BY p.id
+                LIMIT $2"#,
+                id,
+                CONFLICT_DETAIL_LIMIT,
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .context("Failed to query categorised posts")?;
+
+            details.push(ErrorDetail {
+                field: Some("category".to_string()),
+                message: format!("Category is used by {} post(s)", usage),
+            });
+            details.extend(slugs.into_iter().map(|slug| ErrorDetail {
+                field: Some("posts".to_string()),
+                message: slug,
+            }));
+        }
+
+        if !children.is_empty() {
+            details.push(ErrorDetail {
+                field: Some("category".to_string()),
+                message: format!("Category has {} sub-category(ies)", children.len()),
+            });
+            details.extend(children.into_iter().map(|slug| ErrorDetail {
+                field: Some("children".to_string()),
+                message: slug,
+            }));
+        }
+
+        return Err(StatusCode::conflict().with_errors(details).into());
+    }
+
+    sqlx::query!(r#"DELETE FROM categories WHERE id = $1"#, id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete category")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    Ok(StatusCode::success(None).into())
+}
+
+/// Moves posts and sub-categories into another category and deletes the source
+async fn merge_category(
+    State(pool): State<PgPool>,
+    _auth: RequirePermission<ManageTaxonomy>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<MergeRequest>,
+) -> AppResult<Json<SuccessResponse<Category>>> {
+    if payload.into_id == id {
+        return Err(StatusCode::param_error()
+            .with_debug("Cannot merge a category into itself")
+            .into());
+    }
+
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    // 子分类会被移到目标下，与移动分类互斥
+    lock_category_tree(&mut *tx).await?;
+
+    let locked: Vec<i32> = sqlx::query_scalar!(
+        r#"SELECT id FROM categories WHERE id = ANY($1) FOR UPDATE"#,
+        &[id, payload.into_id],
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("Failed to query categories")?;
+    if locked.len() != 2 {
+        return Err(StatusCode::resource_not_found().into());
+    }
+
+    // 目标不能是源分类的子孙
+    if is_self_or_ancestor(&mut *tx, payload.into_id, id).await? {
+        return Err(StatusCode::param_error()
+            .with_debug("Cannot merge a category into one of its sub-categories")
+            .into());
+    }
+
+    sqlx::query!(
+        r#"INSERT INTO post_categories (post_id, category_id)
+        SELECT post_id, $2 FROM post_categories WHERE category_id = $1
+        ON CONFLICT DO NOTHING"#,
+        id,
+        payload.into_id,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to move categorised posts")?;
+
+    sqlx::query!(
+        r#"UPDATE categories SET parent_id = $2, updated_at = NOW() WHERE parent_id = $1"#,
+        id,
+        payload.into_id,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to move sub-categories")?;
+
+    sqlx::query!(r#"DELETE FROM categories WHERE id = $1"#, id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete merged category")?;
+
+    let category = sqlx::query_as!(
+        Category,
+        r#"UPDATE categories SET updated_at = NOW() WHERE id = $1
+        RETURNING id, name, slug, description, parent_id, created_at, updated_at"#,
+        payload.into_id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to query merged category")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    Ok(StatusCode::success(Some(category)).into())
+}
+
+/// Lists published posts in a category or any of its sub-categories
+async fn list_category_posts(
+    State(ReadPool(pool)): State<ReadPool>,
+    Path(slug): Path<String>,
+    Query(pagination): Query<PaginationQuery>,
+) -> AppResult<Json<PaginationResponse<PostSummary>>> {
+    let category_id: i32 =
+        sqlx::query_scalar!(r#"SELECT id FROM categories WHERE slug = $1"#, &slug)
+            .fetch_optional(&pool)
+            .await
+            .context("Failed to query category")?
+            .ok_or_else(StatusCode::resource_not_found)?;
+
+    let total: i64 = sqlx::query_scalar!(
+        r#"WITH RECURSIVE tree AS (
+            SELECT id FROM categories WHERE id = $1
+            UNION
+            SELECT c.id FROM categories c JOIN tree t ON c.parent_id = t.id
+        )
+        SELECT COUNT(*) AS "count!" FROM posts p
+        WHERE p.status = 'published' AND EXISTS (
+            SELECT 1 FROM post_categories pc
+            WHERE pc.post_id = p.id AND pc.category_id IN (SELECT id FROM tree)
+        )"#,
+        category_id,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to count posts")?;
+
+    let posts = sqlx::query_as!(
+        PostSummary,
+        r#"WITH RECURSIVE tree AS (
+            SELECT id FROM categories WHERE id = $1
+            UNION
+            SELECT c.id FROM categories c JOIN tree t ON c.parent_id = t.id
+        )
+        SELECT p.id, p.title, p.slug, p.excerpt, p.reading_time_minutes, p.author_id,
+        p.status AS "status: PostStatus", p.published_at, p.created_at, p.updated_at
+        FROM posts p
+        WHERE p.status = 'published' AND EXISTS (
+            SELECT 1 FROM post_categories pc
+            WHERE pc.post_id = p.id AND pc.category_id IN (SELECT id FROM tree)
+        )
+        ORDER BY p.published_at DESC, p.id DESC
+        LIMIT $2 OFFSET $3"#,
+        category_id,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query posts")?;
+
+    Ok(PaginationResponse::new(
+        StatusCode::Success,
+        "Success",
+        posts,
+        pagination.info(total as u64),
+    )
+    .into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetPostCategoriesRequest {
+    #[validate(length(max = 20))]
+    pub category_ids: Vec<i32>,
+}
+
+/// Replaces the categories attached to a post
+async fn set_post_categories(
+    State(pool): State<PgPool>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    State(config): State<Arc<AppConfig>>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(post_id): Path<i32>,
+    ValidatedJson(mut payload): ValidatedJson<SetPostCategoriesRequest>,
+) -> AppResult<Json<SuccessResponse<Vec<Category>>>> {
+    fetch_owned_post(&pool, roles.as_ref(), &config, post_id, &auth).await?;
+
+    payload.category_ids.sort_unstable();
+    payload.category_ids.dedup();
+
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    let existing: Vec<i32> = sqlx::query_scalar!(
+        r#"SELECT id FROM categories WHERE id = ANY($1) FOR KEY SHARE"#,
+        &payload.category_ids,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("Failed to query categories")?;
+    let missing: Vec<ErrorDetail> = payload
+        .category_ids
+        .iter()
+        .filter(|id| !existing.contains(id))
+        .map(|id| ErrorDetail {
+            field: Some("category_ids".to_string()),
+            message: format!("Category {} does not exist", id),
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(StatusCode::param_error().with_errors(missing).into());
+    }
+
+    sqlx::query!(r#"DELETE FROM post_categories WHERE post_id = $1"#, post_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to detach categories")?;
+
+    sqlx::query!(
+        r#"INSERT INTO post_categories (post_id, category_id)
+        SELECT $1, UNNEST($2::INTEGER[])"#,
+        post_id,
+        &payload.category_ids,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to attach categories")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    let categories = categories_for_post(&pool, post_id).await?;
+
+    Ok(StatusCode::success(Some(categories)).into())
+}
@@ -0,0 +1,540 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::{delete, get, post},
+};
+use chrono::Utc;
+use serde::Deserialize;
+use sqlx::PgPool;
+use validator::Validate;
+
+use crate::db::ReadPool;
+use crate::error::AppResult;
+use crate::extractors::{Auth, ClientIp, OptionalAuth, ValidatedJson};
+use crate::models::{Comment, CommentNode, CommentStatus, SubmittedComment};
+use crate::permissions::{ModerateComments, Permission, has_permission};
+use crate::repositories::RoleRepository;
+use crate::response::{
+    ErrorDetail, PaginationQuery, PaginationResponse, StatusCode, SuccessResponse,
+};
+use crate::spam::{CommentSubmission, SpamFilterChain, Verdict};
+use crate::state::AppState;
+use crate::utils::config::AppConfig;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/posts/{slug}/comments",
+            get(list_post_comments).post(create_comment),
+        )
+        .route("/comments/moderation", get(list_moderation_queue))
+        .route("/comments/moderate", post(bulk_moderate))
+        .route("/comments/id/{id}", delete(delete_comment))
+        .route("/comments/id/{id}/approve", post(approve_comment))
+        .route("/comments/id/{id}/reject", post(reject_comment))
+        .route("/comments/id/{id}/spam", post(mark_comment_spam))
+}
+
+/// Looks up a published post by slug, returning its id and author
+async fn published_post(pool: &PgPool, slug: &str) -> AppResult<(i32, i32)> {
+    let post = sqlx::query!(
+        r#"SELECT id, author_id FROM posts WHERE slug = $1 AND status = 'published'"#,
+        slug,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query post")?
+    .ok_or_else(StatusCode::resource_not_found)?;
+
+    Ok((post.id, post.author_id))
+}
+
+async fn list_post_comments(
+    State(ReadPool(pool)): State<ReadPool>,
+    Path(slug): Path<String>,
+    Query(pagination): Query<PaginationQuery>,
+) -> AppResult<Json<PaginationResponse<CommentNode>>> {
+    let (post_id, _) = published_post(&pool, &slug).await?;
+
+    // 分页只针对顶层评论，每条顶层评论带上完整的回复树
+    let total: i64 = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM comments
+        WHERE post_id = $1 AND parent_id IS NULL AND status = 'approved'"#,
+        post_id,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to count comments")?;
+
+    let roots = sqlx::query_as!(
+        Comment,
+        r#"SELECT id, post_id, parent_id, user_id, author_name, author_email, body,
+            status AS "status: CommentStatus", moderation_reason, ip_address, created_at, updated_at FROM comments
+        WHERE post_id = $1 AND parent_id IS NULL AND status = 'approved'
+        ORDER BY created_at, id
+        LIMIT $2 OFFSET $3"#,
+        post_id,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query comments")?;
+
+    let root_ids: Vec<i32> = roots.iter().map(|c| c.id).collect();
+    let replies = sqlx::query_as!(
+        Comment,
+        r#"WITH RECURSIVE thread AS (
+            SELECT id, post_id, parent_id, user_id, author_name, author_email, body, status,
+                moderation_reason, ip_address, created_at, updated_at
+            FROM comments
+            WHERE parent_id = ANY($1) AND status = 'approved'
+            UNION ALL
+            SELECT c.id, c.post_id, c.parent_id, c.user_id, c.author_name, c.author_email,
+                c.body, c.status, c.moderation_reason, c.ip_address, c.created_at, c.updated_at
+            FROM comments c JOIN thread t ON c.parent_id = t.id
+            WHERE c.status = 'approved'
+        )
+        SELECT id AS "id!", post_id AS "post_id!", parent_id, user_id, author_name AS "author_name!",
+            author_email, body AS "body!", status AS "status!: CommentStatus", moderation_reason,
+            ip_address, created_at AS "created_at!", updated_at AS "updated_at!"
+        FROM thread ORDER BY created_at, id"#,
+        &root_ids,
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query comment replies")?;
+
+    Ok(PaginationResponse::new(
+        StatusCode::Success,
+        "Success",
+        CommentNode::build_threads(roots, replies),
+        pagination.info(total as u64),
+    )
+    .into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateCommentRequest {
+    #[validate(length(min = 1, max = 5000))]
+    pub body: String,
+
+    pub parent_id: Option<i32>,
+
+    /// Required for anonymous comments, ignored for signed-in users
+    #[validate(length(min = 1, max = 100))]
+    pub author_name: Option<String>,
+
+    #[validate(email)]
+    pub author_email: Option<String>,
+
+    /// Honeypot: hidden in the comment form, so only bots fill it in
+    pub website: Option<String>,
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn create_comment(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<AppConfig>>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    State(spam): State<Arc<SpamFilterChain>>,
+    OptionalAuth(claims): OptionalAuth,
+    ClientIp(ip): ClientIp,
+    Path(slug): Path<String>,
+    ValidatedJson(payload): ValidatedJson<CreateCommentRequest>,
+) -> AppResult<Json<SuccessResponse<SubmittedComment>>> {
+    let (post_id, post_author_id) = published_post(&pool, &slug).await?;
+
+    if let Some(parent_id) = payload.parent_id {
+        let parent_ok: bool = sqlx::query_scalar!(
+            r#"SELECT EXISTS (
+                SELECT 1 FROM comments WHERE id = $1 AND post_id = $2 AND status = 'approved'
+            ) AS "exists!""#,
+            parent_id,
+            post_id,
+        )
+        .fetch_one(&pool)
+        .await
+        .context("Failed to query parent comment")?;
+        if !parent_ok {
+            return Err(StatusCode::param_error()
+                .with_errors(vec![ErrorDetail {
+                    field: Some("parent_id".to_string()),
+                    message: "Parent comment does not exist on this post".to_string(),
+                }])
+                .into());
+        }
+    }
+
+    let (user_id, author_name, author_email, trusted) = match claims {
+        Some(claims) => {
+            let user_id = claims.user_id()?;
+            let user = sqlx::query!(
+                r#"SELECT username, email FROM users WHERE id = $1"#,
+                user_id,
+            )
+            .fetch_optional(&pool)
+            .await
+            .context("Failed to query user")?
+            .ok_or_else(StatusCode::unauthorized)?;
+
+            let trusted =
+                user_id == post_author_id || is_moderator(roles.as_ref(), &config, user_id).await?;
+            (Some(user_id), user.username, Some(user.email), trusted)
+        }
+        None => {
+            let author_name = payload.author_name.ok_or_else(|| {
+                StatusCode::param_error().with_errors(vec![ErrorDetail {
+                    field: Some("author_name".to_string()),
+                    message: "Anonymous comments need an author name".to_string(),
+                }])
+            })?;
+            (None, author_name, payload.author_email, false)
+        }
+    };
+
+    // 文章作者和版主的评论无需审核；其余评论先经过垃圾过滤，
+    // 登录用户通过过滤后直接展示，匿名评论仍需人工审核
+    let (status, moderation_reason) = if trusted {
+        (CommentStatus::Approved, None)
+    } else {
+        let submission = CommentSubmission {
+            body: &payload.body,
+            author_name: &author_name,
+            author_email: author_email.as_deref(),
+            honeypot: payload.website.as_deref(),
+            ip,
+            submitted_at: Utc::now(),
+        };
+        match spam.check(&submission).await {
+            Verdict::Spam(reason) => (CommentStatus::Spam, Some(reason)),
+            Verdict::Hold(reason) => (CommentStatus::Pending, Some(reason)),
+            Verdict::Pass if user_id.is_some() => (CommentStatus::Approved, None),
+            Verdict::Pass => (CommentStatus::Pending, None),
+        }
+    };
+
+    let comment = sqlx::query_as!(
+        Comment,
+        r#"INSERT INTO comments (post_id, parent_id, user_id, author_name, author_email, body,
+            status, moderation_reason, ip_address)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id, post_id, parent_id, user_id, author_name, author_email, body,
+            status AS "status: CommentStatus", moderation_reason, ip_address, created_at, updated_at"#,
+        post_id,
+        payload.parent_id,
+        user_id,
+        &author_name,
+        author_email.as_deref(),
+        &payload.body,
+        status as CommentStatus,
+        moderation_reason.as_deref(),
+        ip.map(|ip| ip.to_string()),
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to create comment")?;
+
+    Ok(StatusCode::created(Some(SubmittedComment::from(comment))).into())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModerationQueueQuery {
+    #[serde(default = "default_queue_status")]
+    pub status: CommentStatus,
+    pub post_id: Option<i32>,
+}
+
+fn default_queue_status() -> CommentStatus {
+    CommentStatus::Pending
+}
+
+/// Comments awaiting a decision, limited to the caller's posts unless they are a moderator
+async fn list_moderation_queue(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<AppConfig>>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    auth: Auth,
+    Query(pagination): Query<PaginationQuery>,
+    Query(query): Query<ModerationQueueQuery>,
+) -> AppResult<Json<PaginationResponse<Comment>>> {
+    let user_id = auth.0.user_id()?;
+    let is_moderator = is_moderator(roles.as_ref(), &config, user_id).await?;
+
+    let total: i64 = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM comments c JOIN posts p ON p.id = c.post_id
+        WHERE c.status = $1
+            AND ($2::INTEGER IS NULL OR c.post_id = $2)
+            AND ($3 OR p.author_id = $4)"#,
+        query.status as CommentStatus,
+        query.post_id,
+        is_moderator,
+        user_id,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to count comments")?;
+
+    let comments = sqlx::query_as!(
+        Comment,
+        r#"SELECT c.id, c.post_id, c.parent_id, c.user_id, c.author_name, c.author_email,
+            c.body, c.status AS "status: CommentStatus", c.moderation_reason, c.ip_address,
+            c.created_at, c.updated_at
+        FROM comments c JOIN posts p ON p.id = c.post_id
+        WHERE c.status = $1
+            AND ($2::INTEGER IS NULL OR c.post_id = $2)
+            AND ($3 OR p.author_id = $4)
+        ORDER BY c.created_at, c.id
+        LIMIT $5 OFFSET $6"#,
+        query.status as CommentStatus,
+        query.post_id,
+        is_moderator,
+        user_id,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query comments")?;
+
+    Ok(PaginationResponse::new(
+        StatusCode::Success,
+        "Success",
+        comments,
+        pagination.info(total as u64),
+    )
+    .into())
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationAction {
+    Approve,
+    Reject,
+    Spam,
+}
+
+impl ModerationAction {
+    fn status(self) -> CommentStatus {
+        match self {
+            Self::Approve => CommentStatus::Approved,
+            Self::Reject => CommentStatus::Deleted,
+            Self::Spam => CommentStatus::Spam,
+        }
+    }
+}
+
+/// Whether a user may moderate comments on every post, not only their own
+async fn is_moderator(
+    roles: &dyn RoleRepository,
+    config: &AppConfig,
+    user_id: i32,
+) -> AppResult<bool> {
+    has_permission(roles, &config.admin, user_id, ModerateComments::NAME).await
+}
+
+/// Checks that the caller may moderate a comment: the post's author or a moderator
+async fn ensure_can_moderate(
+    pool: &PgPool,
+    roles: &dyn RoleRepository,
+    config: &AppConfig,
+    id: i32,
+    auth: &Auth,
+) -> AppResult<()> {
+    let post_author_id: i32 = sqlx::query_scalar!(
+        r#"SELECT p.author_id FROM comments c JOIN posts p ON p.id = c.post_id WHERE c.id = $1"#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query comment")?
+    .ok_or_else(StatusCode::resource_not_found)?;
+
+    let user_id = auth.0.user_id()?;
+    if post_author_id != user_id && !is_moderator(roles, config, user_id).await? {
+        return Err(StatusCode::access_denied().into());
+    }
+
+    Ok(())
+}
+
+async fn set_comment_status(pool: &PgPool, id: i32, status: CommentStatus) -> AppResult<Comment> {
+    let comment = sqlx::query_as!(
+        Comment,
+        r#"UPDATE comments SET status = $2, updated_at = NOW() WHERE id = $1
+        RETURNING id, post_id, parent_id, user_id, author_name, author_email, body,
+            status AS "status: CommentStatus", moderation_reason, ip_address, created_at, updated_at"#,
+        id,
+        status as CommentStatus,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to update comment status")?
+    .ok_or_else(StatusCode::resource_not_found)?;
+
+    Ok(comment)
+}
+
+async fn moderate_comment(
+    pool: &PgPool,
+    roles: &dyn RoleRepository,
+    config: &AppConfig,
+    id: i32,
+    auth: &Auth,
+    action: ModerationAction,
+) -> AppResult<Json<SuccessResponse<Comment>>> {
+    ensure_can_moderate(pool, roles, config, id, auth).await?;
+    let comment = set_comment_status(pool, id, action.status()).await?;
+
+    Ok(StatusCode::success(Some(comment)).into())
+}
+
+async fn approve_comment(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<AppConfig>>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    auth: Auth,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<Comment>>> {
+    moderate_comment(
+        &pool,
+        roles.as_ref(),
+        &config,
+        id,
+        &auth,
+        ModerationAction::Approve,
+    )
+    .await
+}
+
+async fn reject_comment(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<AppConfig>>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    auth: Auth,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<Comment>>> {
+    moderate_comment(
+        &pool,
+        roles.as_ref(),
+        &config,
+        id,
+        &auth,
+        ModerationAction::Reject,
+    )
+    .await
+}
+
+async fn mark_comment_spam(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<AppConfig>>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    auth: Auth,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<Comment>>> {
+    moderate_comment(
+        &pool,
+        roles.as_ref(),
+        &config,
+        id,
+        &auth,
+        ModerationAction::Spam,
+    )
+    .await
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct BulkModerateRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub comment_ids: Vec<i32>,
+    pub action: ModerationAction,
+}
+
+/// Applies one moderation action to many comments at once
+///
+/// Either every comment is updated or none: ids that do not exist or that the
+/// caller may not moderate are reported back as parameter errors.
+async fn bulk_moderate(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<AppConfig>>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    auth: Auth,
+    ValidatedJson(mut payload): ValidatedJson<BulkModerateRequest>,
+) -> AppResult<Json<SuccessResponse<Vec<Comment>>>> {
+    let user_id = auth.0.user_id()?;
+    payload.comment_ids.sort_unstable();
+    payload.comment_ids.dedup();
+
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    let allowed: Vec<i32> = sqlx::query_scalar!(
+        r#"SELECT c.id FROM comments c JOIN posts p ON p.id = c.post_id
+        WHERE c.id = ANY($1) AND ($2 OR p.author_id = $3)
+        FOR UPDATE OF c"#,
+        &payload.comment_ids,
+        is_moderator(roles.as_ref(), &config, user_id).await?,
+        user_id,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("Failed to query comments")?;
+    let rejected: Vec<ErrorDetail> = payload
+        .comment_ids
+        .iter()
+        .filter(|id| !allowed.contains(id))
+        .map(|id| ErrorDetail {
+            field: Some("comment_ids".to_string()),
+            message: format!(
+                "Comment {} does not exist or cannot be moderated by you",
+                id
+            ),
+        })
+        .collect();
+    if !rejected.is_empty() {
+        return Err(StatusCode::param_error().with_errors(rejected).into());
+    }
+
+    let comments = sqlx::query_as!(
+        Comment,
+        r#"UPDATE comments SET status = $2, updated_at = NOW() WHERE id = ANY($1)
+        RETURNING id, post_id, parent_id, user_id, author_name, author_email, body,
+            status AS "status: CommentStatus", moderation_reason, ip_address, created_at, updated_at"#,
+        &payload.comment_ids,
+        payload.action.status() as CommentStatus,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("Failed to update comment status")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    Ok(StatusCode::success(Some(comments)).into())
+}
+
+/// Soft-deletes a comment; allowed for its author as well as moderators
+async fn delete_comment(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<AppConfig>>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    auth: Auth,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<()>>> {
+    let comment_user_id: Option<i32> =
+        sqlx::query_scalar!(r#"SELECT user_id FROM comments WHERE id = $1"#, id)
+            .fetch_optional(&pool)
+            .await
+            .context("Failed to query comment")?
+            .ok_or_else(StatusCode::resource_not_found)?;
+
+    if comment_user_id != Some(auth.0.user_id()?) {
+        ensure_can_moderate(&pool, roles.as_ref(), &config, id, &auth).await?;
+    }
+
+    // 保留记录以便审计，回复随父评论一起隐藏
+    set_comment_status(&pool, id, CommentStatus::Deleted).await?;
+
+    Ok(StatusCode::success(None).into())
+}
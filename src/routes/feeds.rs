@@ -0,0 +1,657 @@
+use std::{collections::HashMap, sync::Arc, time::SystemTime};
+
+use anyhow::Context;
+use axum::{
+    Router,
+    extract::{Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode as HttpStatus, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use chrono::{DateTime, Utc};
+use httpdate::HttpDate;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, types::Json as SqlJson};
+
+use crate::error::AppResult;
+use crate::models::{Post, PostStatus};
+use crate::response::StatusCode;
+use crate::routes::posts::ensure_rendered;
+use crate::state::AppState;
+use crate::utils::config::{AppConfig, FeedContent, SiteConfig};
+use crate::utils::markdown::{self, TocEntry};
+
+const JSON_FEED_VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+// 文章没有摘要时，摘要模式下从正文截取的纯文本长度（字符）
+const SUMMARY_CHARS: usize = 280;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/feed.xml", get(rss_feed))
+        .route("/atom.xml", get(atom_feed))
+        .route("/feed.json", get(json_feed))
+}
+
+/// Restricts a feed to one tag or one author
+#[derive(Debug, Default, Deserialize)]
+pub struct FeedQuery {
+    pub tag: Option<String>,
+    pub author_id: Option<i32>,
+}
+
+impl FeedQuery {
+    /// Query string reproducing this filter, used for the feed's own URL
+    fn query_string(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(tag) = &self.tag {
+            params.push(format!("tag={}", tag));
+        }
+        if let Some(author_id) = self.author_id {
+            params.push(format!("author_id={}", author_id));
+        }
+
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", params.join("&"))
+        }
+    }
+}
+
+/// A feed independent of the format it is served in
+struct Feed {
+    title: String,
+    description: String,
+    language: String,
+    /// Page the feed belongs to (home, tag or author page)
+    home_page_url: String,
+    /// URL of the feed itself, without the file name
+    feed_base_url: String,
+    query_string: String,
+    /// Most recent change to the feed's content; unlike the entries' own
+    /// timestamps this never moves backwards when a post is removed
+    updated: Option<DateTime<Utc>>,
+    entries: Vec<FeedEntry>,
+}
+
+impl Feed {
+    fn feed_url(&self, file_name: &str) -> String {
+        format!("{}/{}{}", self.feed_base_url, file_name, self.query_string)
+    }
+
+    fn updated_or_epoch(&self) -> DateTime<Utc> {
+        self.updated.unwrap_or(DateTime::UNIX_EPOCH)
+    }
+}
+
+struct FeedEntry {
+    url: String,
+    title: String,
+    author: String,
+    author_url: String,
+    summary: Option<String>,
+    /// Full post HTML; only set when feeds carry the full content
+    content_html: Option<String>,
+    tags: Vec<String>,
+    published: DateTime<Utc>,
+    updated: DateTime<Utc>,
+}
+
+/// Loads the latest published posts matching the filter
+async fn load_feed(pool: &PgPool, config: &AppConfig, query: &FeedQuery) -> AppResult<Feed> {
+    let site = &config.site;
+    let mut title = site.title.clone();
+    let mut home_page_url = site.url("/");
+
+    let tag_id = match &query.tag {
+        Some(slug) => {
+            let tag = sqlx::query!(r#"SELECT id, name FROM tags WHERE slug = $1"#, slug)
+                .fetch_optional(pool)
+                .await
+                .context("Failed to query tag")?
+                .ok_or_else(StatusCode::resource_not_found)?;
+            title = format!("{} - {}", title, tag.name);
+            home_page_url = site.url(&format!("tags/{}", slug));
+            Some(tag.id)
+        }
+        None => None,
+    };
+
+    if let Some(author_id) = query.author_id {
+        let username: String =
+            sqlx::query_scalar!(r#"SELECT username FROM users WHERE id = $1"#, author_id)
+                .fetch_optional(pool)
+                .await
+                .context("Failed to query author")?
+                .ok_or_else(StatusCode::resource_not_found)?;
+        title = format!("{} - {}", title, username);
+        home_page_url = author_url(site, author_id);
+    }
+
+    // 先于文章读取，中间有写入时 Last-Modified 只会偏旧，客户端下次仍会重新获取
+    let changed_at =
+        sqlx::query_scalar!(r#"SELECT changed_at FROM content_versions WHERE name = 'feed'"#,)
+            .fetch_one(pool)
+            .await
+            .context("Failed to query feed version")?;
+
+    let posts = sqlx::query_as!(
+        Post,
+        r#"SELECT id, title, slug, body_markdown, body_html,
+        toc AS "toc: SqlJson<Vec<TocEntry>>", word_count, reading_time_minutes, excerpt, author_id,
+        status AS "status: PostStatus", published_at, created_at, updated_at
+        FROM posts
+        WHERE status = 'published'
+            AND ($1::INTEGER IS NULL OR author_id = $1)
+            AND ($2::INTEGER IS NULL
+                OR EXISTS (SELECT 1 FROM post_tags WHERE post_id = posts.id AND tag_id = $2))
+        ORDER BY published_at DESC, id DESC
+        LIMIT $3"#,
+        query.author_id,
+        tag_id,
+        config.feed.limit,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query posts")?;
+
+    let post_ids: Vec<i32> = posts.iter().map(|post| post.id).collect();
+    let author_ids: Vec<i32> = posts.iter().map(|post| post.author_id).collect();
+
+    let authors: HashMap<i32, String> = sqlx::query!(
+        r#"SELECT id, username FROM users WHERE id = ANY($1)"#,
+        &author_ids,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query authors")?
+    .into_iter()
+    .map(|row| (row.id, row.username))
+    .collect();
+
+    let mut tags: HashMap<i32, Vec<String>> = HashMap::new();
+    let rows = sqlx::query!(
+        r#"SELECT pt.post_id, t.name
+        FROM post_tags pt JOIN tags t ON t.id = pt.tag_id
+        WHERE pt.post_id = ANY($1)
+        ORDER BY t.name"#,
+        &post_ids,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query post tags")?;
+    for row in rows {
+        tags.entry(row.post_id).or_default().push(row.name);
+    }
+
+    let mut entries = Vec::with_capacity(posts.len());
+    for post in posts {
+        let (content_html, summary) = match config.feed.content {
+            FeedContent::Full => (
+                ensure_rendered(pool, post.clone()).await?.body_html,
+                post.excerpt,
+            ),
+            FeedContent::Excerpt => (
+                None,
+                post.excerpt.or_else(|| {
+                    Some(markdown::summary(&post.body_markdown, SUMMARY_CHARS))
+                        .filter(|summary| !summary.is_empty())
+                }),
+            ),
+        };
+
+        entries.push(FeedEntry {
+            url: site.url(&format!("posts/{}", post.slug)),
+            author: authors.get(&post.author_id).cloned().unwrap_or_default(),
+            author_url: author_url(site, post.author_id),
+            summary,
+            content_html,
+            tags: tags.remove(&post.id).unwrap_or_default(),
+            published: post.published_at.unwrap_or(post.created_at),
+            updated: post.updated_at,
+            title: post.title,
+        });
+    }
+
+    Ok(Feed {
+        title,
+        description: site.description.clone(),
+        language: site.language.clone(),
+        home_page_url,
+        feed_base_url: site.url("api"),
+        query_string: query.query_string(),
+        updated: entries
+            .iter()
+            .map(|entry| entry.updated)
+            .chain([changed_at])
+            .max(),
+        entries,
+    })
+}
+
+fn author_url(site: &SiteConfig, author_id: i32) -> String {
+    site.url(&format!("authors/{}", author_id))
+}
+
+fn render_rss(feed: &Feed) -> String {
+    use rss::{
+        Category, ChannelBuilder, Guid, ItemBuilder, extension::dublincore::DublinCoreExtension,
+    };
+
+    let items: Vec<rss::Item> = feed
+        .entries
+        .iter()
+        .map(|entry| {
+            ItemBuilder::default()
+                .title(entry.title.clone())
+                .link(entry.url.clone())
+                .guid(Guid {
+                    value: entry.url.clone(),
+                    permalink: true,
+                })
+                .pub_date(entry.published.to_rfc2822())
+                .description(entry.summary.clone())
+                .content(entry.content_html.clone())
+                .categories(
+                    entry
+                        .tags
+                        .iter()
+                        .map(|name| Category {
+                            name: name.clone(),
+                            domain: None,
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                // RSS 的 author 字段要求是邮箱，作者名放在 dc:creator 中
+                .dublin_core_ext(DublinCoreExtension {
+                    creators: vec![entry.author.clone()],
+                    ..Default::default()
+                })
+                .build()
+        })
+        .collect();
+
+    ChannelBuilder::default()
+        .title(feed.title.clone())
+        .link(feed.home_page_url.clone())
+        .description(feed.description.clone())
+        .language(Some(feed.language.clone()))
+        .last_build_date(feed.updated.map(|updated| updated.to_rfc2822()))
+        .items(items)
+        .build()
+        .to_string()
+}
+
+fn render_atom(feed: &Feed) -> String {
+    use atom_syndication::{Category, Content, Entry, Feed as AtomFeed, Link, Person, Text};
+
+    let entries: Vec<Entry> = feed
+        .entries
+        .iter()
+        .map(|entry| Entry {
+            id: entry.url.clone(),
+            title: Text::plain(entry.title.clone()),
+            updated: entry.updated.fixed_offset(),
+            published: Some(entry.published.fixed_offset()),
+            authors: vec![Person {
+                name: entry.author.clone(),
+                uri: Some(entry.author_url.clone()),
+                ..Default::default()
+            }],
+            links: vec![Link {
+                href: entry.url.clone(),
+                rel: "alternate".to_string(),
+                ..Default::default()
+            }],
+            summary: entry.summary.clone().map(Text::plain),
+            content: entry.content_html.clone().map(|html| Content {
+                value: Some(html),
+                content_type: Some("html".to_string()),
+                ..Default::default()
+            }),
+            categories: entry
+                .tags
+                .iter()
+                .map(|name| Category {
+                    term: name.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        })
+        .collect();
+
+    AtomFeed {
+        id: feed.feed_url("atom.xml"),
+        title: Text::plain(feed.title.clone()),
+        subtitle: (!feed.description.is_empty()).then(|| Text::plain(feed.description.clone())),
+        updated: feed.updated_or_epoch().fixed_offset(),
+        lang: Some(feed.language.clone()),
+        links: vec![
+            Link {
+                href: feed.home_page_url.clone(),
+                rel: "alternate".to_string(),
+                ..Default::default()
+            },
+            Link {
+                href: feed.feed_url("atom.xml"),
+                rel: "self".to_string(),
+                mime_type: Some("application/atom+xml".to_string()),
+                ..Default::default()
+            },
+        ],
+        entries,
+        ..Default::default()
+    }
+    .to_string()
+}
+
+/// JSON Feed 1.1, see <https://www.jsonfeed.org/version/1.1/>
+#[derive(Debug, Serialize)]
+struct JsonFeed {
+    version: &'static str,
+    title: String,
+    home_page_url: String,
+    feed_url: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    description: String,
+    language: String,
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonFeedItem {
+    id: String,
+    url: String,
+    title: String,
+    // 每个条目必须有 content_html 或 content_text 之一
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_html: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    date_published: DateTime<Utc>,
+    date_modified: DateTime<Utc>,
+    authors: Vec<JsonFeedAuthor>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonFeedAuthor {
+    name: String,
+    url: String,
+}
+
+fn render_json(feed: &Feed) -> String {
+    let items = feed
+        .entries
+        .iter()
+        .map(|entry| JsonFeedItem {
+            id: entry.url.clone(),
+            url: entry.url.clone(),
+            title: entry.title.clone(),
+            content_text: match entry.content_html {
+                Some(_) => None,
+                None => Some(entry.summary.clone().unwrap_or_default()),
+            },
+            content_html: entry.content_html.clone(),
+            summary: entry.summary.clone(),
+            date_published: entry.published,
+            date_modified: entry.updated,
+            authors: vec![JsonFeedAuthor {
+                name: entry.author.clone(),
+                url: entry.author_url.clone(),
+            }],
+            tags: entry.tags.clone(),
+        })
+        .collect();
+
+    let json_feed = JsonFeed {
+        version: JSON_FEED_VERSION,
+        title: feed.title.clone(),
+        home_page_url: feed.home_page_url.clone(),
+        feed_url: feed.feed_url("feed.json"),
+        description: feed.description.clone(),
+        language: feed.language.clone(),
+        items,
+    };
+
+    serde_json::to_string(&json_feed).expect("JSON feed is always serializable")
+}
+
+/// Whether the client's cached copy is still current
+///
+/// `If-None-Match` takes precedence over `If-Modified-Since`, as in RFC 9110.
+fn is_not_modified(headers: &HeaderMap, etag: &str, last_modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+        let Ok(if_none_match) = if_none_match.to_str() else {
+            return false;
+        };
+        return if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+    }
+
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| httpdate::parse_http_date(value).ok());
+    match (since, last_modified) {
+        // HTTP 日期只精确到秒，比较前先截断
+        (Some(since), Some(last_modified)) => {
+            HttpDate::from(last_modified) <= HttpDate::from(since)
+        }
+        _ => false,
+    }
+}
+
+/// Serves a rendered feed with validators, answering 304 when unchanged
+fn feed_response(
+    headers: &HeaderMap,
+    content_type: &'static str,
+    body: String,
+    updated: Option<DateTime<Utc>>,
+) -> Response {
+    let etag = format!("\"{}\"", &hex::encode(Sha256::digest(&body))[..32]);
+    let last_modified = updated.map(SystemTime::from);
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(
+        header::ETAG,
+        HeaderValue::from_str(&etag).expect("ETag is valid ASCII"),
+    );
+    if let Some(last_modified) = last_modified {
+        response_headers.insert(
+            header::LAST_MODIFIED,
+            HeaderValue::from_str(&httpdate::fmt_http_date(last_modified))
+                .expect("HTTP date is valid ASCII"),
+        );
+    }
+
+    if is_not_modified(headers, &etag, last_modified) {
+        return (HttpStatus::NOT_MODIFIED, response_headers).into_response();
+    }
+
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    (response_headers, body).into_response()
+}
+
+async fn rss_feed(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<AppConfig>>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let feed = load_feed(&pool, &config, &query).await?;
+
+    Ok(feed_response(
+        &headers,
+        "application/rss+xml; charset=utf-8",
+        render_rss(&feed),
+        feed.updated,
+    ))
+}
+
+async fn atom_feed(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<AppConfig>>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let feed = load_feed(&pool, &config, &query).await?;
+
+    Ok(feed_response(
+        &headers,
+        "application/atom+xml; charset=utf-8",
+        render_atom(&feed),
+        feed.updated,
+    ))
+}
+
+async fn json_feed(
+    State(pool): State<PgPool>,
+    State(config): State<Arc<AppConfig>>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let feed = load_feed(&pool, &config, &query).await?;
+
+    Ok(feed_response(
+        &headers,
+        "application/feed+json; charset=utf-8",
+        render_json(&feed),
+        feed.updated,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn feed(full_content: bool) -> Feed {
+        Feed {
+            title: "Blog - Rust".to_string(),
+            description: "Notes".to_string(),
+            language: "en".to_string(),
+            home_page_url: "https://blog.example.com/tags/rust".to_string(),
+            feed_base_url: "https://blog.example.com/api".to_string(),
+            query_string: "?tag=rust".to_string(),
+            updated: Some(at("2026-01-02T00:00:00Z")),
+            entries: vec![FeedEntry {
+                url: "https://blog.example.com/posts/hello".to_string(),
+                title: "Hello & welcome".to_string(),
+                author: "alice".to_string(),
+                author_url: "https://blog.example.com/authors/1".to_string(),
+                summary: Some("A short summary".to_string()),
+                content_html: full_content.then(|| "<p>Hello <em>world</em></p>".to_string()),
+                tags: vec!["Rust".to_string()],
+                published: at("2026-01-01T00:00:00Z"),
+                updated: at("2026-01-02T00:00:00Z"),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_feed_query_string() {
+        assert_eq!(FeedQuery::default().query_string(), "");
+        let query = FeedQuery {
+            tag: Some("rust".to_string()),
+            author_id: Some(2),
+        };
+        assert_eq!(query.query_string(), "?tag=rust&author_id=2");
+    }
+
+    #[test]
+    fn test_render_rss() {
+        let xml = render_rss(&feed(true));
+        let channel: rss::Channel = xml.parse().unwrap();
+
+        assert_eq!(channel.title(), "Blog - Rust");
+        let item = &channel.items()[0];
+        assert_eq!(item.title(), Some("Hello & welcome"));
+        assert_eq!(item.link(), Some("https://blog.example.com/posts/hello"));
+        assert_eq!(item.content(), Some("<p>Hello <em>world</em></p>"));
+        assert_eq!(item.categories()[0].name(), "Rust");
+        assert_eq!(
+            item.dublin_core_ext().unwrap().creators(),
+            &["alice".to_string()]
+        );
+        assert_eq!(item.pub_date(), Some("Thu, 1 Jan 2026 00:00:00 +0000"));
+    }
+
+    #[test]
+    fn test_render_atom() {
+        let xml = render_atom(&feed(false));
+        let atom: atom_syndication::Feed = xml.parse().unwrap();
+
+        assert_eq!(atom.id(), "https://blog.example.com/api/atom.xml?tag=rust");
+        assert_eq!(atom.updated().to_rfc3339(), "2026-01-02T00:00:00+00:00");
+        let entry = &atom.entries()[0];
+        assert_eq!(entry.authors()[0].name(), "alice");
+        assert_eq!(entry.summary().unwrap().as_str(), "A short summary");
+        // 摘要模式下不输出全文
+        assert!(entry.content().is_none());
+    }
+
+    #[test]
+    fn test_render_json_feed() {
+        let json: serde_json::Value = serde_json::from_str(&render_json(&feed(true))).unwrap();
+        assert_eq!(json["version"], JSON_FEED_VERSION);
+        assert_eq!(
+            json["feed_url"],
+            "https://blog.example.com/api/feed.json?tag=rust"
+        );
+        let item = &json["items"][0];
+        assert_eq!(item["content_html"], "<p>Hello <em>world</em></p>");
+        assert!(item.get("content_text").is_none());
+        assert_eq!(item["date_published"], "2026-01-01T00:00:00Z");
+        assert_eq!(item["tags"][0], "Rust");
+
+        let json: serde_json::Value = serde_json::from_str(&render_json(&feed(false))).unwrap();
+        assert_eq!(json["items"][0]["content_text"], "A short summary");
+    }
+
+    #[test]
+    fn test_is_not_modified() {
+        let etag = "\"abc\"";
+        let modified = SystemTime::from(at("2026-01-02T00:00:00Z"));
+        let headers = |name, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+
+        assert!(!is_not_modified(&HeaderMap::new(), etag, Some(modified)));
+        assert!(is_not_modified(
+            &headers(header::IF_NONE_MATCH, "\"xyz\", W/\"abc\""),
+            etag,
+            None
+        ));
+        assert!(!is_not_modified(
+            &headers(header::IF_NONE_MATCH, "\"xyz\""),
+            etag,
+            Some(modified)
+        ));
+        assert!(is_not_modified(
+            &headers(header::IF_MODIFIED_SINCE, "Fri, 02 Jan 2026 00:00:00 GMT"),
+            etag,
+            Some(modified)
+        ));
+        assert!(!is_not_modified(
+            &headers(header::IF_MODIFIED_SINCE, "Thu, 01 Jan 2026 00:00:00 GMT"),
+            etag,
+            Some(modified)
+        ));
+    }
+}
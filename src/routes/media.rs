@@ -0,0 +1,524 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::{
+    Json, Router,
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State, multipart::MultipartError},
+    routing::{delete, get, post},
+};
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, types::Json as SqlJson};
+use tracing::warn;
+
+use crate::error::AppResult;
+use crate::extractors::{Auth, RequirePermission};
+use crate::images::ImageQueue;
+use crate::models::{Media, MediaStatus, MediaVariant};
+use crate::permissions::UploadMedia;
+use crate::response::{
+    ErrorDetail, ErrorResponse, PaginationQuery, PaginationResponse, StatusCode, SuccessResponse,
+};
+use crate::state::AppState;
+use crate::storage::Storage;
+use crate::utils::config::{AppConfig, StorageConfig};
+
+// multipart 表单中文件字段的名称
+const FILE_FIELD: &str = "file";
+
+pub fn routes() -> Router<AppState> {
+    // 上传大小在读取时按 storage.max_upload_size 限制，这里关闭默认的 2MB 限制
+    Router::new()
+        .route(
+            "/media/upload",
+            post(upload_media).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/media/list", get(list_media))
+        .route("/media/id/{id}", delete(delete_media))
+        .route(
+            "/users/me/avatar",
+            post(upload_avatar).layer(DefaultBodyLimit::disable()),
+        )
+}
+
+/// A file read from a multipart request
+struct Upload {
+    data: Bytes,
+    filename: Option<String>,
+}
+
+fn bad_multipart(error: MultipartError) -> ErrorResponse {
+    StatusCode::bad_request().with_debug(error.body_text())
+}
+
+/// Reads the `file` field, rejecting it as soon as it exceeds `max_size`
+async fn read_upload(multipart: &mut Multipart, max_size: usize) -> AppResult<Upload> {
+    while let Some(mut field) = multipart.next_field().await.map_err(bad_multipart)? {
+        if field.name() != Some(FILE_FIELD) {
+            continue;
+        }
+
+        let filename = field
+            .file_name()
+            .map(|name| name.chars().take(255).collect());
+        let mut data = BytesMut::new();
+        while let Some(chunk) = field.chunk().await.map_err(bad_multipart)? {
+            if data.len() + chunk.len() > max_size {
+                return Err(StatusCode::payload_too_large()
+                    .with_debug(format!("Files may be at most {} bytes", max_size))
+                    .into());
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        return Ok(Upload {
+            data: data.freeze(),
+            filename,
+        });
+    }
+
+    Err(StatusCode::param_error()
+        .with_errors(vec![ErrorDetail {
+            field: Some(FILE_FIELD.to_string()),
+            message: "Missing file".to_string(),
+        }])
+        .into())
+}
+
+/// Detects the file type from its content and checks it against the allow list
+///
+/// The Content-Type sent by the client is ignored. Returns the MIME type and
+/// the file extension to store the file under.
+fn detect_mime(data: &[u8], allowed: &[String]) -> AppResult<(&'static str, &'static str)> {
+    let kind = infer::get(data)
+        .ok_or_else(|| StatusCode::unsupported_media_type().with_debug("Unrecognised file type"))?;
+
+    if !allowed.iter().any(|mime| mime == kind.mime_type()) {
+        return Err(StatusCode::unsupported_media_type()
+            .with_debug(format!("{} files are not allowed", kind.mime_type()))
+            .into());
+    }
+
+    Ok((kind.mime_type(), kind.extension()))
+}
+
+/// Re-encodes an upload without its EXIF/GPS metadata before anything is
+/// stored or returned
+///
+/// Runs under the image queue's permits, so a burst of uploads decodes no
+/// more images at once than the workers do. Uploads that cannot be
+/// re-encoded are rejected, as their metadata could not be removed, and so
+/// are uploads that grow past `max_size` when re-encoded.
+async fn strip_metadata(images: &ImageQueue, data: Bytes, max_size: usize) -> AppResult<Bytes> {
+    let stripped = match images.strip_metadata(data.clone()).await {
+        Ok(Some(image)) => Bytes::from(image.data),
+        Ok(None) => data,
+        Err(e) => {
+            return Err(StatusCode::unsupported_media_type()
+                .with_debug(format!("{:#}", e))
+                .into());
+        }
+    };
+
+    if stripped.len() > max_size {
+        return Err(StatusCode::payload_too_large()
+            .with_debug(format!(
+                "Files may be at most {} bytes after re-encoding",
+                max_size
+            ))
+            .into());
+    }
+
+    Ok(stripped)
+}
+
+/// Storage key for a file; identical content always maps to the same key
+fn media_key(hash: &str, extension: &str) -> String {
+    format!("{}/{}.{}", &hash[..2], hash, extension)
+}
+
+/// A row of the `media` table; the public URLs are added by
+/// [`MediaRow::into_media`]
+struct MediaRow {
+    id: i32,
+    uploader_id: i32,
+    storage_key: String,
+    content_hash: String,
+    mime_type: String,
+    size_bytes: i64,
+    original_filename: Option<String>,
+    status: MediaStatus,
+    width: Option<i32>,
+    height: Option<i32>,
+    blurhash: Option<String>,
+    variants: SqlJson<Vec<MediaVariant>>,
+    created_at: DateTime<Utc>,
+}
+
+impl MediaRow {
+    fn into_media(self, storage: &dyn Storage) -> Media {
+        let SqlJson(mut variants) = self.variants;
+        for variant in variants.iter_mut() {
+            variant.url = storage.url(&variant.storage_key);
+        }
+
+        Media {
+            id: self.id,
+            uploader_id: self.uploader_id,
+            url: storage.url(&self.storage_key),
+            storage_key: self.storage_key,
+            content_hash: self.content_hash,
+            mime_type: self.mime_type,
+            size_bytes: self.size_bytes,
+            original_filename: self.original_filename,
+            status: self.status,
+            width: self.width,
+            height: self.height,
+            blurhash: self.blurhash,
+            srcset: Media::build_srcset(&variants),
+            variants,
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// Stores an upload and records it in the uploader's library
+///
+/// Metadata is stripped first, so only the re-encoded file is ever stored.
+/// Returns the media record and whether it was newly created. Uploading a
+/// file the user already has returns the existing record, and content that
+/// is already stored (by anyone) is not written to storage again. New
+/// records are queued for image processing.
+async fn store_media(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    images: &ImageQueue,
+    config: &StorageConfig,
+    uploader_id: i32,
+    upload: Upload,
+) -> AppResult<(Media, bool)> {
+    let (mime_type, extension) = detect_mime(&upload.data, &config.allowed_mime_types)?;
+    let data = strip_metadata(images, upload.data, config.max_upload_size).await?;
+    let hash = hex::encode(Sha256::digest(&data));
+    let key = media_key(&hash, extension);
+
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    // 按内容哈希加锁，避免并发的上传和删除互相干扰
+    sqlx::query!(
+        r#"SELECT pg_advisory_xact_lock(hashtext($1)) AS "locked: ()""#,
+        &hash
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to lock media hash")?;
+
+    let existing = sqlx::query_as!(
+        MediaRow,
+        r#"SELECT id, uploader_id, storage_key, content_hash, mime_type, size_bytes, original_filename,
+            status AS "status: MediaStatus", width, height, blurhash,
+            variants AS "variants: SqlJson<Vec<MediaVariant>>", created_at
+        FROM media WHERE uploader_id = $1 AND content_hash = $2"#,
+        uploader_id,
+        &hash,
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .context("Failed to query media")?;
+    if let Some(row) = existing {
+        return Ok((row.into_media(storage), false));
+    }
+
+    if !storage.exists(&key).await? {
+        storage.put(&key, data.clone(), mime_type).await?;
+    }
+
+    // 未开启图片处理时直接视为处理完成
+    let status = if images.is_enabled() {
+        MediaStatus::Pending
+    } else {
+        MediaStatus::Ready
+    };
+
+    let row = sqlx::query_as!(
+        MediaRow,
+        r#"INSERT INTO media (uploader_id, storage_key, content_hash, mime_type, size_bytes,
+            original_filename, status)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, uploader_id, storage_key, content_hash, mime_type, size_bytes, original_filename,
+            status AS "status: MediaStatus", width, height, blurhash,
+            variants AS "variants: SqlJson<Vec<MediaVariant>>", created_at"#,
+        uploader_id,
+        &key,
+        &hash,
+        mime_type,
+        data.len() as i64,
+        upload.filename.as_deref(),
+        status as MediaStatus,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to create media")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    images.enqueue(row.id);
+
+    Ok((row.into_media(storage), true))
+}
+
+async fn upload_media(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn Storage>>,
+    State(images): State<ImageQueue>,
+    State(config): State<Arc<AppConfig>>,
+    RequirePermission { auth, .. }: RequirePermission<UploadMedia>,
+    mut multipart: Multipart,
+) -> AppResult<Json<SuccessResponse<Media>>> {
+    let upload = read_upload(&mut multipart, config.storage.max_upload_size).await?;
+    let (media, created) = store_media(
+        &pool,
+        storage.as_ref(),
+        &images,
+        &config.storage,
+        auth.0.user_id()?,
+        upload,
+    )
+    .await?;
+
+    if created {
+        Ok(StatusCode::created(Some(media)).into())
+    } else {
+        Ok(StatusCode::success(Some(media)).into())
+    }
+}
+
+/// Uploads an image and makes it the caller's avatar
+///
+/// Open to every signed-in user; only the media library upload needs
+/// `media.upload`.
+async fn upload_avatar(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn Storage>>,
+    State(images): State<ImageQueue>,
+    State(config): State<Arc<AppConfig>>,
+    auth: Auth,
+    mut multipart: Multipart,
+) -> AppResult<Json<SuccessResponse<Media>>> {
+    let user_id = auth.0.user_id()?;
+    let upload = read_upload(&mut multipart, config.storage.max_upload_size).await?;
+    let (media, _) = store_media(
+        &pool,
+        storage.as_ref(),
+        &images,
+        &config.storage,
+        user_id,
+        upload,
+    )
+    .await?;
+
+    sqlx::query!(
+        r#"UPDATE users SET avatar_url = $2, updated_at = NOW() WHERE id = $1"#,
+        user_id,
+        &media.url,
+    )
+    .execute(&pool)
+    .await
+    .context("Failed to update avatar")?;
+
+    Ok(StatusCode::success(Some(media)).into())
+}
+
+/// The caller's media library, newest first
+///
+/// Needs no permission, so users who lost `media.upload` still see what they
+/// uploaded.
+async fn list_media(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn Storage>>,
+    auth: Auth,
+    Query(pagination): Query<PaginationQuery>,
+) -> AppResult<Json<PaginationResponse<Media>>> {
+    let user_id = auth.0.user_id()?;
+
+    let total: i64 = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM media WHERE uploader_id = $1"#,
+        user_id,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to count media")?;
+
+    let rows = sqlx::query_as!(
+        MediaRow,
+        r#"SELECT id, uploader_id, storage_key, content_hash, mime_type, size_bytes, original_filename,
+            status AS "status: MediaStatus", width, height, blurhash,
+            variants AS "variants: SqlJson<Vec<MediaVariant>>", created_at
+        FROM media
+        WHERE uploader_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2 OFFSET $3"#,
+        user_id,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query media")?;
+
+    let media = rows
+        .into_iter()
+        .map(|row| row.into_media(storage.as_ref()))
+        .collect();
+
+    Ok(PaginationResponse::new(
+        StatusCode::Success,
+        "Success",
+        media,
+        pagination.info(total as u64),
+    )
+    .into())
+}
+
+/// Deletes a stored object and its variants unless a library still uses it
+///
+/// Takes the same lock as uploads, so an upload of the same content either
+/// finishes first and keeps the object, or stores it again afterwards.
+async fn delete_unused_objects(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    content_hash: &str,
+    storage_key: &str,
+    variants: &[MediaVariant],
+) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    sqlx::query!(
+        r#"SELECT pg_advisory_xact_lock(hashtext($1)) AS "locked: ()""#,
+        content_hash,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to lock media hash")?;
+
+    let still_used: bool = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM media WHERE storage_key = $1) AS "exists!""#,
+        storage_key,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to query media usage")?;
+    if still_used {
+        return Ok(());
+    }
+
+    storage.delete(storage_key).await?;
+    for variant in variants {
+        storage.delete(&variant.storage_key).await?;
+    }
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    Ok(())
+}
+
+/// Removes a file from the caller's library
+///
+/// Like listing, this needs no permission beyond owning the file.
+/// The stored object and its variants are only deleted once no library
+/// references it anymore, and only after the record is gone. Failing to
+/// delete them leaves unreferenced files behind, which is logged.
+async fn delete_media(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn Storage>>,
+    auth: Auth,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<()>>> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    let media = sqlx::query!(
+        r#"SELECT uploader_id, storage_key, content_hash FROM media WHERE id = $1"#,
+        id,
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .context("Failed to query media")?
+    .ok_or_else(StatusCode::resource_not_found)?;
+
+    if media.uploader_id != auth.0.user_id()? {
+        return Err(StatusCode::access_denied().into());
+    }
+
+    sqlx::query!(
+        r#"SELECT pg_advisory_xact_lock(hashtext($1)) AS "locked: ()""#,
+        &media.content_hash,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to lock media hash")?;
+
+    let SqlJson(variants) = sqlx::query_scalar!(
+        r#"DELETE FROM media WHERE id = $1
+        RETURNING variants AS "variants: SqlJson<Vec<MediaVariant>>""#,
+        id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to delete media")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    if let Err(e) = delete_unused_objects(
+        &pool,
+        storage.as_ref(),
+        &media.content_hash,
+        &media.storage_key,
+        &variants,
+    )
+    .await
+    {
+        warn!(
+            media_id = id,
+            storage_key = %media.storage_key,
+            error = ?e,
+            "Failed to delete stored media files"
+        );
+    }
+
+    Ok(StatusCode::success(None).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn test_detect_mime_uses_content() {
+        let allowed = vec!["image/png".to_string()];
+
+        assert_eq!(
+            detect_mime(PNG_HEADER, &allowed).unwrap(),
+            ("image/png", "png")
+        );
+
+        for data in [&b"GIF89a...."[..], b"<?php echo 1; ?>"] {
+            let Err(AppError::Response(err)) = detect_mime(data, &allowed) else {
+                panic!("expected an error response");
+            };
+            assert_eq!(err.code, StatusCode::UnsupportedMediaType);
+        }
+    }
+
+    #[test]
+    fn test_media_key_is_content_addressed() {
+        let hash = hex::encode(Sha256::digest(PNG_HEADER));
+        let key = media_key(&hash, "png");
+
+        assert_eq!(key, format!("{}/{}.png", &hash[..2], hash));
+        assert_eq!(key, media_key(&hash, "png"));
+    }
+}
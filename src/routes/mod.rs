@@ -1,8 +1,110 @@
+pub mod categories;
+pub mod comments;
+pub mod feeds;
+pub mod media;
+pub mod posts;
+pub mod roles;
+pub mod search;
+pub mod series;
+pub mod sitemap;
+pub mod tags;
 pub mod users;
+pub mod well_known;
 
 use axum::Router;
-use sqlx::PgPool;
+use serde::{Deserialize, Deserializer};
+use validator::ValidationError;
 
-pub fn create_routes() -> Router<(PgPool, crate::utils::jwt::JwtService)> {
-    Router::new().merge(users::routes())
+use crate::error::AppResult;
+use crate::response::{ErrorDetail, StatusCode};
+use crate::state::AppState;
+
+// 与静态路由冲突的 slug
+const RESERVED_SLUGS: &[&str] = &["list", "create", "mine", "id"];
+
+pub fn create_routes() -> Router<AppState> {
+    Router::new()
+        .merge(users::routes())
+        .merge(roles::routes())
+        .merge(posts::routes())
+        .merge(tags::routes())
+        .merge(categories::routes())
+        .merge(series::routes())
+        .merge(comments::routes())
+        .merge(media::routes())
+        .merge(feeds::routes())
+        .merge(search::routes())
+}
+
+/// Distinguishes a missing field from an explicit `null`
+///
+/// Use with `#[serde(default, deserialize_with = "double_option")]`.
+pub(crate) fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+pub(crate) fn validate_slug(slug: &str) -> Result<(), ValidationError> {
+    let well_formed = !slug.is_empty()
+        && slug.len() <= 255
+        && slug.split('-').all(|part| {
+            !part.is_empty()
+                && part
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        });
+
+    if !well_formed {
+        return Err(ValidationError::new("slug").with_message(
+            "Slug may only contain lowercase letters, digits and single dashes".into(),
+        ));
+    }
+    if RESERVED_SLUGS.contains(&slug) {
+        return Err(ValidationError::new("slug").with_message("Slug is reserved".into()));
+    }
+
+    Ok(())
+}
+
+/// Generates a slug from the title when the client did not provide one
+pub(crate) fn slug_for(title: &str, slug: Option<&str>) -> AppResult<String> {
+    let slug = match slug {
+        Some(slug) => slug.to_string(),
+        None => slug::slugify(title),
+    };
+
+    validate_slug(&slug).map_err(|_| {
+        StatusCode::validation_error().with_errors(vec![ErrorDetail {
+            field: Some("slug".to_string()),
+            message: "Unable to derive a valid slug from the title, please provide one".into(),
+        }])
+    })?;
+
+    Ok(slug)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_slug() {
+        assert!(validate_slug("hello-world").is_ok());
+        assert!(validate_slug("rust-2024").is_ok());
+        assert!(validate_slug("").is_err());
+        assert!(validate_slug("Hello").is_err());
+        assert!(validate_slug("double--dash").is_err());
+        assert!(validate_slug("-leading").is_err());
+        assert!(validate_slug("list").is_err());
+    }
+
+    #[test]
+    fn test_slug_for() {
+        assert_eq!(slug_for("Hello, World!", None).unwrap(), "hello-world");
+        assert_eq!(slug_for("ignored", Some("custom")).unwrap(), "custom");
+        assert!(slug_for("!!!", None).is_err());
+    }
 }
@@ -0,0 +1,597 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::{get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, types::Json as SqlJson};
+use validator::Validate;
+
+use crate::db::ReadPool;
+use crate::error::AppResult;
+use crate::extractors::{Auth, OptionalAuth, RequirePermission, ValidatedJson};
+use crate::models::{Category, Post, PostStatus, PostSummary, RelatedPost, SeriesNavigation, Tag};
+use crate::permissions::{EditAnyPost, Permission, WritePosts, has_permission};
+use crate::repositories::RoleRepository;
+use crate::response::{PaginationQuery, PaginationResponse, StatusCode, SuccessResponse};
+use crate::routes::{
+    categories::categories_for_post, double_option, search::related_posts,
+    series::navigation_for_post, slug_for, tags::tags_for_post, validate_slug,
+};
+use crate::state::AppState;
+use crate::utils::config::AppConfig;
+use crate::utils::markdown::{self, RenderedMarkdown, TocEntry};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/posts/list", get(list_posts))
+        .route("/posts/mine", get(list_my_posts))
+        .route("/posts/create", post(create_post))
+        .route("/posts/{slug}", get(get_post))
+        .route(
+            "/posts/id/{id}",
+            axum::routing::put(update_post).delete(delete_post),
+        )
+        .route("/posts/id/{id}/publish", post(publish_post))
+        .route("/posts/id/{id}/unpublish", post(unpublish_post))
+        .route("/posts/id/{id}/archive", post(archive_post))
+}
+
+/// Works out the stored status and `published_at` for a requested status
+///
+/// Publishing with a `published_at` in the future schedules the post instead.
+fn resolve_publication(
+    status: PostStatus,
+    published_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> AppResult<(PostStatus, Option<DateTime<Utc>>)> {
+    match (status, published_at) {
+        (PostStatus::Draft, _) => Ok((PostStatus::Draft, None)),
+        (PostStatus::Archived, at) => Ok((PostStatus::Archived, at)),
+        (PostStatus::Published | PostStatus::Scheduled, Some(at)) if at > now => {
+            Ok((PostStatus::Scheduled, Some(at)))
+        }
+        (PostStatus::Published, at) => Ok((PostStatus::Published, Some(at.unwrap_or(now)))),
+        (PostStatus::Scheduled, _) => Err(StatusCode::param_error()
+            .with_debug("Scheduled posts need a published_at in the future")
+            .into()),
+    }
+}
+
+/// Renders Markdown off the async runtime, syntax highlighting is CPU bound
+async fn render_markdown(markdown: String) -> AppResult<RenderedMarkdown> {
+    let rendered = tokio::task::spawn_blocking(move || markdown::render(&markdown))
+        .await
+        .context("Markdown rendering task failed")?;
+
+    Ok(rendered)
+}
+
+/// Fills in `body_html` for posts that have not been rendered yet and
+/// stores the result so later reads are served from the cache
+pub(crate) async fn ensure_rendered(pool: &PgPool, mut post: Post) -> AppResult<Post> {
+    if post.body_html.is_some() {
+        return Ok(post);
+    }
+
+    let rendered = render_markdown(post.body_markdown.clone()).await?;
+
+    sqlx::query!(
+        r#"UPDATE posts SET body_html = $2, toc = $3, word_count = $4, reading_time_minutes = $5
+        WHERE id = $1"#,
+        post.id,
+        &rendered.html,
+        SqlJson(&rendered.toc) as _,
+        rendered.word_count as i32,
+        rendered.reading_time_minutes as i32,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to store rendered post")?;
+
+    post.body_html = Some(rendered.html);
+    post.toc = SqlJson(rendered.toc);
+    post.word_count = rendered.word_count as i32;
+    post.reading_time_minutes = rendered.reading_time_minutes as i32;
+    Ok(post)
+}
+
+async fn fetch_post_by_id(pool: &PgPool, id: i32) -> AppResult<Post> {
+    sqlx::query_as!(
+        Post,
+        r#"SELECT id, title, slug, body_markdown, body_html,
+        toc AS "toc: SqlJson<Vec<TocEntry>>", word_count, reading_time_minutes, excerpt, author_id,
+        status AS "status: PostStatus", published_at, created_at, updated_at
+        FROM posts WHERE id = $1"#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query post")?
+    .ok_or_else(|| StatusCode::resource_not_found().into())
+}
+
+/// Whether a user may modify a post: its author, or anyone holding
+/// `posts.edit_any`
+async fn can_edit_post(
+    roles: &dyn RoleRepository,
+    config: &AppConfig,
+    author_id: i32,
+    user_id: i32,
+) -> AppResult<bool> {
+    if author_id == user_id {
+        return Ok(true);
+    }
+
+    has_permission(roles, &config.admin, user_id, EditAnyPost::NAME).await
+}
+
+/// Loads a post and checks that the caller is allowed to modify it
+pub(crate) async fn fetch_owned_post(
+    pool: &PgPool,
+    roles: &dyn RoleRepository,
+    config: &AppConfig,
+    id: i32,
+    auth: &Auth,
+) -> AppResult<Post> {
+    let post = fetch_post_by_id(pool, id).await?;
+
+    if !can_edit_post(roles, config, post.author_id, auth.0.user_id()?).await? {
+        return Err(StatusCode::access_denied().into());
+    }
+
+    Ok(post)
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreatePostRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub title: String,
+
+    #[validate(custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+
+    pub body_markdown: String,
+
+    #[validate(length(max = 1000))]
+    pub excerpt: Option<String>,
+
+    pub status: Option<PostStatus>,
+
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+async fn create_post(
+    State(pool): State<PgPool>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    ValidatedJson(payload): ValidatedJson<CreatePostRequest>,
+) -> AppResult<Json<SuccessResponse<Post>>> {
+    let author_id = auth.0.user_id()?;
+    let slug = slug_for(&payload.title, payload.slug.as_deref())?;
+    let (status, published_at) = resolve_publication(
+        payload.status.unwrap_or(PostStatus::Draft),
+        payload.published_at,
+        Utc::now(),
+    )?;
+
+    let rendered = render_markdown(payload.body_markdown.clone()).await?;
+
+    let post = sqlx::query_as!(
+        Post,
+        r#"INSERT INTO posts (title, slug, body_markdown, body_html, toc, word_count,
+        reading_time_minutes, excerpt, author_id, status, published_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING id, title, slug, body_markdown, body_html,
+        toc AS "toc: SqlJson<Vec<TocEntry>>", word_count, reading_time_minutes, excerpt, author_id,
+        status AS "status: PostStatus", published_at, created_at, updated_at"#,
+        &payload.title,
+        &slug,
+        &payload.body_markdown,
+        &rendered.html,
+        SqlJson(&rendered.toc) as _,
+        rendered.word_count as i32,
+        rendered.reading_time_minutes as i32,
+        payload.excerpt.as_deref(),
+        author_id,
+        status as PostStatus,
+        published_at,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to create post")?;
+
+    Ok(StatusCode::created(Some(post)).into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdatePostRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub title: Option<String>,
+
+    #[validate(custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+
+    pub body_markdown: Option<String>,
+
+    /// Absent keeps the current excerpt, `null` removes it
+    #[validate(length(max = 1000))]
+    #[serde(default, deserialize_with = "double_option")]
+    pub excerpt: Option<Option<String>>,
+}
+
+async fn update_post(
+    State(pool): State<PgPool>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    State(config): State<Arc<AppConfig>>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<UpdatePostRequest>,
+) -> AppResult<Json<SuccessResponse<Post>>> {
+    fetch_owned_post(&pool, roles.as_ref(), &config, id, &auth).await?;
+
+    let rendered = match &payload.body_markdown {
+        Some(body) => Some(render_markdown(body.clone()).await?),
+        None => None,
+    };
+
+    let post = sqlx::query_as!(
+        Post,
+        r#"UPDATE posts SET
+            title = COALESCE($2, title),
+            slug = COALESCE($3, slug),
+            body_markdown = COALESCE($4, body_markdown),
+            body_html = COALESCE($5, body_html),
+            toc = COALESCE($6, toc),
+            word_count = COALESCE($7, word_count),
+            reading_time_minutes = COALESCE($8, reading_time_minutes),
+            excerpt = CASE WHEN $9 THEN $10 ELSE excerpt END,
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, title, slug, body_markdown, body_html,
+        toc AS "toc: SqlJson<Vec<TocEntry>>", word_count, reading_time_minutes, excerpt, author_id,
+        status AS "status: PostStatus", published_at, created_at, updated_at"#,
+        id,
+        payload.title.as_deref(),
+        payload.slug.as_deref(),
+        payload.body_markdown.as_deref(),
+        rendered.as_ref().map(|r| &r.html),
+        rendered.as_ref().map(|r| SqlJson(&r.toc)) as _,
+        rendered.as_ref().map(|r| r.word_count as i32),
+        rendered.as_ref().map(|r| r.reading_time_minutes as i32),
+        payload.excerpt.is_some(),
+        payload.excerpt.flatten(),
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to update post")?;
+
+    Ok(StatusCode::success(Some(post)).into())
+}
+
+async fn delete_post(
+    State(pool): State<PgPool>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    State(config): State<Arc<AppConfig>>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<()>>> {
+    fetch_owned_post(&pool, roles.as_ref(), &config, id, &auth).await?;
+
+    sqlx::query!(r#"DELETE FROM posts WHERE id = $1"#, id)
+        .execute(&pool)
+        .await
+        .context("Failed to delete post")?;
+
+    Ok(StatusCode::success(None).into())
+}
+
+async fn set_post_status(
+    pool: &PgPool,
+    id: i32,
+    status: PostStatus,
+    published_at: Option<DateTime<Utc>>,
+) -> AppResult<Post> {
+    let post = sqlx::query_as!(
+        Post,
+        r#"UPDATE posts SET status = $2, published_at = $3, updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, title, slug, body_markdown, body_html,
+        toc AS "toc: SqlJson<Vec<TocEntry>>", word_count, reading_time_minutes, excerpt, author_id,
+        status AS "status: PostStatus", published_at, created_at, updated_at"#,
+        id,
+        status as PostStatus,
+        published_at,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to update post status")?;
+
+    Ok(post)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PublishPostRequest {
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+async fn publish_post(
+    State(pool): State<PgPool>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    State(config): State<Arc<AppConfig>>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(id): Path<i32>,
+    payload: Option<Json<PublishPostRequest>>,
+) -> AppResult<Json<SuccessResponse<Post>>> {
+    fetch_owned_post(&pool, roles.as_ref(), &config, id, &auth).await?;
+
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let (status, published_at) =
+        resolve_publication(PostStatus::Published, payload.published_at, Utc::now())?;
+    let post = set_post_status(&pool, id, status, published_at).await?;
+
+    Ok(StatusCode::success(Some(post)).into())
+}
+
+async fn unpublish_post(
+    State(pool): State<PgPool>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    State(config): State<Arc<AppConfig>>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<Post>>> {
+    fetch_owned_post(&pool, roles.as_ref(), &config, id, &auth).await?;
+
+    let post = set_post_status(&pool, id, PostStatus::Draft, None).await?;
+
+    Ok(StatusCode::success(Some(post)).into())
+}
+
+async fn archive_post(
+    State(pool): State<PgPool>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    State(config): State<Arc<AppConfig>>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<Post>>> {
+    let post = fetch_owned_post(&pool, roles.as_ref(), &config, id, &auth).await?;
+
+    let post = set_post_status(&pool, id, PostStatus::Archived, post.published_at).await?;
+
+    Ok(StatusCode::success(Some(post)).into())
+}
+
+/// Post detail response: the post plus everything a post page needs
+#[derive(Debug, Serialize)]
+pub struct PostDetail {
+    #[serde(flatten)]
+    pub post: Post,
+    pub tags: Vec<Tag>,
+    pub categories: Vec<Category>,
+    /// Previous/next links when the post is part of a series
+    pub series: Option<SeriesNavigation>,
+    pub related: Vec<RelatedPost>,
+}
+
+async fn get_post(
+    State(pool): State<PgPool>,
+    OptionalAuth(claims): OptionalAuth,
+    Path(slug): Path<String>,
+) -> AppResult<Json<SuccessResponse<PostDetail>>> {
+    let post = sqlx::query_as!(
+        Post,
+        r#"SELECT id, title, slug, body_markdown, body_html,
+        toc AS "toc: SqlJson<Vec<TocEntry>>", word_count, reading_time_minutes, excerpt, author_id,
+        status AS "status: PostStatus", published_at, created_at, updated_at
+        FROM posts WHERE slug = $1"#,
+        &slug,
+    )
+    .fetch_optional(&pool)
+    .await
+    .context("Failed to query post")?
+    .ok_or_else(StatusCode::resource_not_found)?;
+
+    // 未发布的文章只对作者可见，对其他人表现为不存在
+    let is_author = claims
+        .as_ref()
+        .and_then(|c| c.user_id().ok())
+        .is_some_and(|user_id| user_id == post.author_id);
+    if !post.is_public() && !is_author {
+        return Err(StatusCode::resource_not_found().into());
+    }
+
+    let post = ensure_rendered(&pool, post).await?;
+    let tags = tags_for_post(&pool, post.id).await?;
+    let categories = categories_for_post(&pool, post.id).await?;
+    let series = navigation_for_post(&pool, post.id).await?;
+    let related = related_posts(&pool, post.id).await?;
+
+    Ok(StatusCode::success(Some(PostDetail {
+        post,
+        tags,
+        categories,
+        series,
+        related,
+    }))
+    .into())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListPostsQuery {
+    pub author_id: Option<i32>,
+}
+
+async fn list_posts(
+    State(ReadPool(pool)): State<ReadPool>,
+    Query(pagination): Query<PaginationQuery>,
+    Query(filter): Query<ListPostsQuery>,
+) -> AppResult<Json<PaginationResponse<PostSummary>>> {
+    let total: i64 = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM posts
+        WHERE status = 'published' AND ($1::INTEGER IS NULL OR author_id = $1)"#,
+        filter.author_id,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to count posts")?;
+
+    let posts = sqlx::query_as!(
+        PostSummary,
+        r#"SELECT id, title, slug, excerpt, reading_time_minutes, author_id,
+        status AS "status: PostStatus", published_at, created_at, updated_at
+        FROM posts
+        WHERE status = 'published' AND ($1::INTEGER IS NULL OR author_id = $1)
+        ORDER BY published_at DESC, id DESC
+        LIMIT $2 OFFSET $3"#,
+        filter.author_id,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query posts")?;
+
+    Ok(PaginationResponse::new(
+        StatusCode::Success,
+        "Success",
+        posts,
+        pagination.info(total as u64),
+    )
+    .into())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListMyPostsQuery {
+    pub status: Option<PostStatus>,
+}
+
+/// The caller's own posts, drafts included
+///
+/// Left open to every signed-in user rather than guarded by `WritePosts`: it
+/// only reads the caller's posts, which stay visible to them after their
+/// writing permission is withdrawn.
+async fn list_my_posts(
+    State(pool): State<PgPool>,
+    auth: Auth,
+    Query(pagination): Query<PaginationQuery>,
+    Query(filter): Query<ListMyPostsQuery>,
+) -> AppResult<Json<PaginationResponse<PostSummary>>> {
+    let author_id = auth.0.user_id()?;
+
+    let total: i64 = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM posts
+        WHERE author_id = $1 AND ($2::post_status IS NULL OR status = $2)"#,
+        author_id,
+        filter.status as Option<PostStatus>,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to count posts")?;
+
+    let posts = sqlx::query_as!(
+        PostSummary,
+        r#"SELECT id, title, slug, excerpt, reading_time_minutes, author_id,
+        status AS "status: PostStatus", published_at, created_at, updated_at
+        FROM posts
+        WHERE author_id = $1 AND ($2::post_status IS NULL OR status = $2)
+        ORDER BY updated_at DESC, id DESC
+        LIMIT $3 OFFSET $4"#,
+        author_id,
+        filter.status as Option<PostStatus>,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query posts")?;
+
+    Ok(PaginationResponse::new(
+        StatusCode::Success,
+        "Success",
+        posts,
+        pagination.info(total as u64),
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::InMemoryRoleRepository;
+    use chrono::Duration;
+
+    #[tokio::test]
+    async fn test_can_edit_post() {
+        let roles = InMemoryRoleRepository::default();
+        roles
+            .set_roles(2, &["author".to_string()], None)
+            .await
+            .unwrap();
+        roles
+            .set_roles(3, &["editor".to_string()], None)
+            .await
+            .unwrap();
+        let mut config = AppConfig::default();
+        config.admin.user_ids = vec![1];
+
+        // 作者只能修改自己的文章，编辑和超级用户可以修改任何人的
+        assert!(can_edit_post(&roles, &config, 2, 2).await.unwrap());
+        assert!(!can_edit_post(&roles, &config, 4, 2).await.unwrap());
+        assert!(can_edit_post(&roles, &config, 4, 3).await.unwrap());
+        assert!(can_edit_post(&roles, &config, 4, 1).await.unwrap());
+        assert!(!can_edit_post(&roles, &config, 4, 5).await.unwrap());
+    }
+
+    #[test]
+    fn test_update_post_request_excerpt() {
+        let parse = |json: &str| serde_json::from_str::<UpdatePostRequest>(json).unwrap();
+
+        assert_eq!(parse("{}").excerpt, None);
+        assert_eq!(parse(r#"{"excerpt": null}"#).excerpt, Some(None));
+        assert_eq!(
+            parse(r#"{"excerpt": "Short"}"#).excerpt,
+            Some(Some("Short".to_string()))
+        );
+
+        let too_long = UpdatePostRequest {
+            title: None,
+            slug: None,
+            body_markdown: None,
+            excerpt: Some(Some("x".repeat(1001))),
+        };
+        assert!(too_long.validate().is_err());
+    }
+
+    #[test]
+    fn test_resolve_publication() {
+        let now = Utc::now();
+        let past = now - Duration::hours(1);
+        let future = now + Duration::hours(1);
+
+        assert_eq!(
+            resolve_publication(PostStatus::Draft, Some(past), now).unwrap(),
+            (PostStatus::Draft, None)
+        );
+        assert_eq!(
+            resolve_publication(PostStatus::Published, None, now).unwrap(),
+            (PostStatus::Published, Some(now))
+        );
+        assert_eq!(
+            resolve_publication(PostStatus::Published, Some(past), now).unwrap(),
+            (PostStatus::Published, Some(past))
+        );
+        assert_eq!(
+            resolve_publication(PostStatus::Published, Some(future), now).unwrap(),
+            (PostStatus::Scheduled, Some(future))
+        );
+        assert_eq!(
+            resolve_publication(PostStatus::Scheduled, Some(future), now).unwrap(),
+            (PostStatus::Scheduled, Some(future))
+        );
+        assert!(resolve_publication(PostStatus::Scheduled, None, now).is_err());
+        assert!(resolve_publication(PostStatus::Scheduled, Some(past), now).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    extract::{Path, State},
+    response::Json,
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::error::AppResult;
+use crate::extractors::{RequirePermission, ValidatedJson};
+use crate::models::Role;
+use crate::permissions::ManageRoles;
+use crate::repositories::{RoleRepository, UserRepository};
+use crate::response::{StatusCode, SuccessResponse};
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/roles/list", get(list_roles)).route(
+        "/users/id/{id}/roles",
+        get(get_user_roles).put(set_user_roles),
+    )
+}
+
+/// Roles a user holds
+#[derive(Debug, Serialize)]
+pub struct UserRoles {
+    pub user_id: i32,
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetRolesRequest {
+    #[validate(length(max = 20))]
+    pub roles: Vec<String>,
+}
+
+async fn list_roles(
+    State(roles): State<Arc<dyn RoleRepository>>,
+    _auth: RequirePermission<ManageRoles>,
+) -> AppResult<Json<SuccessResponse<Vec<Role>>>> {
+    let roles = roles.list().await?;
+
+    Ok(StatusCode::success(Some(roles)).into())
+}
+
+async fn get_user_roles(
+    State(users): State<Arc<dyn UserRepository>>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    _auth: RequirePermission<ManageRoles>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<UserRoles>>> {
+    users
+        .find_by_id(id)
+        .await?
+        .ok_or_else(StatusCode::resource_not_found)?;
+
+    let roles = roles.roles_of(id).await?;
+
+    Ok(StatusCode::success(Some(UserRoles { user_id: id, roles })).into())
+}
+
+/// Replaces the roles of a user
+///
+/// The new roles apply to access tokens issued from now on; the `roles`
+/// claim of tokens already out keeps the old list until they expire, while
+/// permission checks see the change right away.
+async fn set_user_roles(
+    State(users): State<Arc<dyn UserRepository>>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    RequirePermission { auth, .. }: RequirePermission<ManageRoles>,
+    Path(id): Path<i32>,
+    ValidatedJson(mut payload): ValidatedJson<SetRolesRequest>,
+) -> AppResult<Json<SuccessResponse<UserRoles>>> {
+    users
+        .find_by_id(id)
+        .await?
+        .ok_or_else(StatusCode::resource_not_found)?;
+
+    payload.roles.sort();
+    payload.roles.dedup();
+    roles
+        .set_roles(id, &payload.roles, Some(auth.0.user_id()?))
+        .await?;
+    let roles = roles.roles_of(id).await?;
+
+    Ok(StatusCode::success(Some(UserRoles { user_id: id, roles })).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use crate::extractors::Auth;
+    use crate::repositories::{InMemoryRoleRepository, InMemoryUserRepository, NewUser};
+    use crate::utils::config::{AppConfig, JwtConfig};
+    use crate::utils::jwt::JwtService;
+
+    fn admin() -> RequirePermission<ManageRoles> {
+        let config = AppConfig {
+            jwt: JwtConfig {
+                secret: "test-secret".to_string(),
+                expires_in: "1h".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let jwt = JwtService::from_config(&config).unwrap();
+        let token = jwt.generate_token("1", None, Vec::new()).unwrap();
+        RequirePermission::new(Auth(jwt.validate_token(&token).unwrap()))
+    }
+
+    async fn setup() -> (Arc<dyn UserRepository>, Arc<dyn RoleRepository>) {
+        let users: Arc<dyn UserRepository> = Arc::new(InMemoryUserRepository::default());
+        for name in ["carol", "erin"] {
+            users
+                .create(NewUser {
+                    username: name,
+                    email: &format!("{}@example.com", name),
+                    password_hash: "hash",
+                    role: None,
+                })
+                .await
+                .unwrap();
+        }
+        (users, Arc::new(InMemoryRoleRepository::default()))
+    }
+
+    async fn set(
+        users: &Arc<dyn UserRepository>,
+        roles: &Arc<dyn RoleRepository>,
+        id: i32,
+        names: &[&str],
+    ) -> AppResult<UserRoles> {
+        let request = SetRolesRequest {
+            roles: names.iter().map(|name| name.to_string()).collect(),
+        };
+        let response = set_user_roles(
+            State(users.clone()),
+            State(roles.clone()),
+            admin(),
+            Path(id),
+            ValidatedJson(request),
+        )
+        .await?;
+        Ok(response.0.data.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_set_user_roles() {
+        let (users, roles) = setup().await;
+
+        let assigned = set(&users, &roles, 2, &["editor", "author", "editor"])
+            .await
+            .unwrap();
+        assert_eq!(assigned.roles, vec!["author", "editor"]);
+        assert!(roles.has_permission(2, "taxonomy.manage").await.unwrap());
+
+        let assigned = set(&users, &roles, 2, &["author"]).await.unwrap();
+        assert_eq!(assigned.roles, vec!["author"]);
+        assert!(!roles.has_permission(2, "taxonomy.manage").await.unwrap());
+
+        let fetched = get_user_roles(State(users), State(roles), admin(), Path(2))
+            .await
+            .unwrap();
+        assert_eq!(fetched.0.data.unwrap().roles, vec!["author"]);
+    }
+
+    #[tokio::test]
+    async fn test_set_user_roles_rejects_unknown_role_and_user() {
+        let (users, roles) = setup().await;
+        set(&users, &roles, 2, &["author"]).await.unwrap();
+
+        match set(&users, &roles, 2, &["author", "owner"]).await {
+            Err(AppError::Response(err)) => {
+                assert_eq!(err.code, StatusCode::ParamError);
+                assert_eq!(err.errors.unwrap()[0].message, "Unknown role: owner");
+            }
+            other => panic!("expected a parameter error, got {:?}", other),
+        }
+        assert_eq!(roles.roles_of(2).await.unwrap(), vec!["author"]);
+
+        match set(&users, &roles, 99, &["author"]).await {
+            Err(AppError::Response(err)) => assert_eq!(err.code, StatusCode::ResourceNotFound),
+            other => panic!("expected not found, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_roles() {
+        let (_, roles) = setup().await;
+        let listed = list_roles(State(roles), admin())
+            .await
+            .unwrap()
+            .0
+            .data
+            .unwrap();
+        let names: Vec<&str> = listed.iter().map(|role| role.name.as_str()).collect();
+        assert_eq!(names, vec!["admin", "author", "editor", "reader"]);
+    }
+}
@@ -0,0 +1,608 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::get,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::db::ReadPool;
+use crate::error::AppResult;
+use crate::models::{PostSuggestion, RelatedPost, SearchHit, Suggestions, TagSuggestion};
+use crate::response::{
+    ErrorDetail, PaginationQuery, PaginationResponse, StatusCode, SuccessResponse,
+};
+use crate::state::AppState;
+use crate::utils::{
+    config::{AppConfig, SearchConfig},
+    markdown::is_cjk,
+};
+
+// 查询字符串的最大长度（字符数）
+const MAX_QUERY_CHARS: usize = 200;
+
+// 输入联想每类返回的条数，以及查询超时后直接返回空结果的时限
+const SUGGESTION_LIMIT: i64 = 5;
+const SUGGESTION_TIMEOUT: &str = "150ms";
+
+// 文章详情中相关文章的数量
+const RELATED_POSTS_LIMIT: i64 = 5;
+
+// 子串匹配时生成的正文摘要长度（字符数）
+const SNIPPET_CHARS: usize = 160;
+
+// ts_headline 和子串高亮使用控制字符标记命中位置，转义 HTML 后再替换为 <mark>
+const MARK_START: char = '\u{2}';
+const MARK_END: char = '\u{3}';
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/search", get(search_posts))
+        .route("/search/suggest", get(suggest))
+}
+
+/// Writes the configured text search config and reindexes posts when it
+/// changed since the last start
+///
+/// Fails when PostgreSQL does not know the configuration.
+pub async fn apply_text_search_config(pool: &PgPool, config: &SearchConfig) -> anyhow::Result<()> {
+    let changed = sqlx::query!(
+        r#"UPDATE search_settings SET text_search_config = $1::TEXT::REGCONFIG
+        WHERE text_search_config <> $1::TEXT::REGCONFIG"#,
+        &config.text_search_config,
+    )
+    .execute(pool)
+    .await
+    .with_context(|| {
+        format!(
+            "Failed to apply text search config {:?}",
+            config.text_search_config
+        )
+    })?;
+
+    if changed.rows_affected() > 0 {
+        // 触发器会按新配置重新生成 search_vector
+        let reindexed = sqlx::query!(r#"UPDATE posts SET title = title"#)
+            .execute(pool)
+            .await
+            .context("Failed to rebuild search index")?;
+        info!(
+            config = %config.text_search_config,
+            posts = reindexed.rows_affected(),
+            "Rebuilt search index"
+        );
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+    pub tag: Option<String>,
+    pub author_id: Option<i32>,
+    /// Only posts published at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only posts published before this time
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+struct SearchRow {
+    id: i32,
+    title: String,
+    slug: String,
+    excerpt: Option<String>,
+    author_id: i32,
+    published_at: Option<DateTime<Utc>>,
+    rank: f32,
+    title_highlight: String,
+    snippet: String,
+}
+
+impl SearchRow {
+    fn into_hit(self) -> SearchHit {
+        SearchHit {
+            id: self.id,
+            title: self.title,
+            slug: self.slug,
+            excerpt: self.excerpt,
+            author_id: self.author_id,
+            published_at: self.published_at,
+            rank: self.rank,
+            title_highlight: mark_html(&self.title_highlight),
+            snippet: mark_html(&self.snippet),
+        }
+    }
+}
+
+fn contains_cjk(text: &str) -> bool {
+    text.chars().any(is_cjk)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes text containing match markers and turns the markers into `<mark>`
+fn mark_html(text: &str) -> String {
+    escape_html(text)
+        .replace(MARK_START, "<mark>")
+        .replace(MARK_END, "</mark>")
+}
+
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Search terms as `ILIKE` patterns, with wildcards in the terms escaped
+fn like_patterns(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|term| format!("%{}%", escape_like(term)))
+        .collect()
+}
+
+/// Marks every case-insensitive occurrence of the terms in `text`
+///
+/// When `max_chars` is set, only a window of that many characters around
+/// the first match is kept.
+fn highlight_terms(text: &str, query: &str, max_chars: Option<usize>) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let terms: Vec<Vec<char>> = query
+        .split_whitespace()
+        .map(|term| term.chars().flat_map(char::to_lowercase).collect())
+        .collect();
+
+    // 每个位置上命中的最长词的长度
+    let match_at = |i: usize| {
+        terms
+            .iter()
+            .filter(|term| lower[i..].starts_with(term))
+            .map(|term| term.len())
+            .max()
+    };
+
+    let (start, end) = match max_chars {
+        Some(max_chars) if chars.len() > max_chars => {
+            let first = (0..chars.len())
+                .find(|&i| match_at(i).is_some())
+                .unwrap_or(0);
+            let start = first.saturating_sub(max_chars / 4);
+            let start = start.min(chars.len() - max_chars);
+            (start, start + max_chars)
+        }
+        _ => (0, chars.len()),
+    };
+
+    let mut result = String::new();
+    if start > 0 {
+        result.push('…');
+    }
+    let mut i = start;
+    while i < end {
+        match match_at(i) {
+            Some(len) => {
+                let match_end = (i + len).min(end);
+                result.push(MARK_START);
+                result.extend(&chars[i..match_end]);
+                result.push(MARK_END);
+                i = match_end;
+            }
+            None => {
+                result.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    if end < chars.len() {
+        result.push('…');
+    }
+    result
+}
+
+/// Ranked full-text search using the stored `search_vector`
+async fn search_full_text(
+    pool: &PgPool,
+    query: &str,
+    filter: &SearchQuery,
+    pagination: &PaginationQuery,
+) -> AppResult<(i64, Vec<SearchRow>)> {
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM posts p, search_settings s
+        WHERE p.status = 'published'
+            AND ($1::INTEGER IS NULL OR p.author_id = $1)
+            AND ($2::TEXT IS NULL OR EXISTS (
+                SELECT 1 FROM post_tags pt JOIN tags t ON t.id = pt.tag_id
+                WHERE pt.post_id = p.id AND t.slug = $2))
+            AND ($3::TIMESTAMPTZ IS NULL OR p.published_at >= $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR p.published_at < $4)
+            AND p.search_vector @@ websearch_to_tsquery(s.text_search_config, $5)"#,
+        filter.author_id,
+        filter.tag,
+        filter.from,
+        filter.to,
+        query,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to count search results")?;
+
+    if total == 0 {
+        return Ok((0, Vec::new()));
+    }
+
+    let rows = sqlx::query_as!(
+        SearchRow,
+        r#"SELECT p.id, p.title, p.slug, p.excerpt, p.author_id, p.published_at,
+            ts_rank_cd(p.search_vector, q.query) AS "rank!",
+            ts_headline(q.config, p.title, q.query,
+                'HighlightAll=true, StartSel=' || chr(2) || ', StopSel=' || chr(3)) AS "title_highlight!",
+            ts_headline(q.config, p.body_markdown, q.query,
+                'StartSel=' || chr(2) || ', StopSel=' || chr(3)
+                || ', MaxWords=35, MinWords=15, MaxFragments=2, FragmentDelimiter=" … "') AS "snippet!"
+        FROM posts p, (
+            SELECT text_search_config AS config,
+                websearch_to_tsquery(text_search_config, $5) AS query
+            FROM search_settings
+        ) q
+        WHERE p.status = 'published'
+            AND ($1::INTEGER IS NULL OR p.author_id = $1)
+            AND ($2::TEXT IS NULL OR EXISTS (
+                SELECT 1 FROM post_tags pt JOIN tags t ON t.id = pt.tag_id
+                WHERE pt.post_id = p.id AND t.slug = $2))
+            AND ($3::TIMESTAMPTZ IS NULL OR p.published_at >= $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR p.published_at < $4)
+            AND p.search_vector @@ q.query
+        ORDER BY "rank!" DESC, p.published_at DESC, p.id DESC
+        LIMIT $6 OFFSET $7"#,
+        filter.author_id,
+        filter.tag,
+        filter.from,
+        filter.to,
+        query,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to search posts")?;
+
+    Ok((total, rows))
+}
+
+/// Substring search backed by the `pg_trgm` index on `search_text`
+///
+/// Every term must appear; posts are ranked by trigram word similarity,
+/// with title matches first.
+async fn search_trigram(
+    pool: &PgPool,
+    query: &str,
+    filter: &SearchQuery,
+    pagination: &PaginationQuery,
+) -> AppResult<(i64, Vec<SearchRow>)> {
+    let patterns = like_patterns(query);
+
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM posts p
+        WHERE p.status = 'published'
+            AND ($1::INTEGER IS NULL OR p.author_id = $1)
+            AND ($2::TEXT IS NULL OR EXISTS (
+                SELECT 1 FROM post_tags pt JOIN tags t ON t.id = pt.tag_id
+                WHERE pt.post_id = p.id AND t.slug = $2))
+            AND ($3::TIMESTAMPTZ IS NULL OR p.published_at >= $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR p.published_at < $4)
+            AND p.search_text ILIKE ALL($5::TEXT[])"#,
+        filter.author_id,
+        filter.tag,
+        filter.from,
+        filter.to,
+        &patterns,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to count search results")?;
+
+    if total == 0 {
+        return Ok((0, Vec::new()));
+    }
+
+    let rows = sqlx::query_as!(
+        SearchRow,
+        r#"SELECT p.id, p.title, p.slug, p.excerpt, p.author_id, p.published_at,
+            (word_similarity($6, p.search_text)
+                + CASE WHEN p.title ILIKE ALL($5::TEXT[]) THEN 1 ELSE 0 END)::REAL AS "rank!",
+            p.title AS title_highlight,
+            p.body_markdown AS snippet
+        FROM posts p
+        WHERE p.status = 'published'
+            AND ($1::INTEGER IS NULL OR p.author_id = $1)
+            AND ($2::TEXT IS NULL OR EXISTS (
+                SELECT 1 FROM post_tags pt JOIN tags t ON t.id = pt.tag_id
+                WHERE pt.post_id = p.id AND t.slug = $2))
+            AND ($3::TIMESTAMPTZ IS NULL OR p.published_at >= $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR p.published_at < $4)
+            AND p.search_text ILIKE ALL($5::TEXT[])
+        ORDER BY "rank!" DESC, p.published_at DESC, p.id DESC
+        LIMIT $7 OFFSET $8"#,
+        filter.author_id,
+        filter.tag,
+        filter.from,
+        filter.to,
+        &patterns,
+        query,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to search posts")?;
+
+    let rows = rows
+        .into_iter()
+        .map(|row| SearchRow {
+            title_highlight: highlight_terms(&row.title_highlight, query, None),
+            snippet: highlight_terms(&row.snippet, query, Some(SNIPPET_CHARS)),
+            ..row
+        })
+        .collect();
+
+    Ok((total, rows))
+}
+
+/// Searches published posts by title, body and tags
+async fn search_posts(
+    State(ReadPool(pool)): State<ReadPool>,
+    State(config): State<Arc<AppConfig>>,
+    Query(pagination): Query<PaginationQuery>,
+    Query(filter): Query<SearchQuery>,
+) -> AppResult<Json<PaginationResponse<SearchHit>>> {
+    let query = filter.q.as_deref().map(str::trim).unwrap_or_default();
+    if query.is_empty() || query.chars().count() > MAX_QUERY_CHARS {
+        return Err(StatusCode::param_error()
+            .with_errors(vec![ErrorDetail {
+                field: Some("q".to_string()),
+                message: format!("Query must be 1 to {} characters", MAX_QUERY_CHARS),
+            }])
+            .into());
+    }
+
+    // 中文等不以空格分词的文字无法被全文检索切分，直接使用子串匹配
+    let fallback = config.search.trigram_fallback;
+    let (total, rows) = if fallback && contains_cjk(query) {
+        search_trigram(&pool, query, &filter, &pagination).await?
+    } else {
+        let (total, rows) = search_full_text(&pool, query, &filter, &pagination).await?;
+        if total == 0 && fallback {
+            search_trigram(&pool, query, &filter, &pagination).await?
+        } else {
+            (total, rows)
+        }
+    };
+
+    let hits = rows.into_iter().map(SearchRow::into_hit).collect();
+
+    Ok(PaginationResponse::new(
+        StatusCode::Success,
+        "Success",
+        hits,
+        pagination.info(total as u64),
+    )
+    .into())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestQuery {
+    pub q: Option<String>,
+}
+
+fn is_query_canceled(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .and_then(|e| e.code())
+        .is_some_and(|code| code == "57014")
+}
+
+/// Titles and tags completing a partial query
+///
+/// Prefix matches come first, then trigram word similarity. The queries run
+/// under a short statement timeout and return nothing rather than keep a
+/// typing user waiting.
+async fn suggest(
+    State(ReadPool(pool)): State<ReadPool>,
+    Query(query): Query<SuggestQuery>,
+) -> AppResult<Json<SuccessResponse<Suggestions>>> {
+    let query: String = query
+        .q
+        .as_deref()
+        .unwrap_or_default()
+        .trim()
+        .chars()
+        .take(MAX_QUERY_CHARS)
+        .collect();
+    if query.is_empty() {
+        return Ok(StatusCode::success(Some(Suggestions::default())).into());
+    }
+
+    let prefix = format!("{}%", escape_like(&query));
+    let contains = format!("%{}%", escape_like(&query));
+
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    // 等同于 SET LOCAL，仅对本事务生效
+    sqlx::query_scalar!(
+        r#"SELECT set_config('statement_timeout', $1, TRUE) AS "timeout!""#,
+        SUGGESTION_TIMEOUT,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to set statement timeout")?;
+
+    let posts = sqlx::query_as!(
+        PostSuggestion,
+        r#"SELECT title, slug FROM posts
+        WHERE status = 'published'
+            AND (title ILIKE $2 OR $3 <% title)
+        ORDER BY title ILIKE $1 DESC, word_similarity($3, title) DESC, published_at DESC
+        LIMIT $4"#,
+        &prefix,
+        &contains,
+        &query,
+        SUGGESTION_LIMIT,
+    )
+    .fetch_all(&mut *tx)
+    .await;
+
+    let tags = sqlx::query_as!(
+        TagSuggestion,
+        r#"SELECT t.name, t.slug, COUNT(p.id) AS "post_count!"
+        FROM tags t
+        LEFT JOIN post_tags pt ON pt.tag_id = t.id
+        LEFT JOIN posts p ON p.id = pt.post_id AND p.status = 'published'
+        WHERE t.name ILIKE $2 OR $3 <% t.name
+        GROUP BY t.id
+        ORDER BY t.name ILIKE $1 DESC, word_similarity($3, t.name) DESC, "post_count!" DESC
+        LIMIT $4"#,
+        &prefix,
+        &contains,
+        &query,
+        SUGGESTION_LIMIT,
+    );
+
+    // 超时的查询不返回建议，已查到的文章建议仍然返回
+    let suggestions = match posts {
+        Ok(posts) => match tags.fetch_all(&mut *tx).await {
+            Ok(tags) => Suggestions { posts, tags },
+            Err(e) if is_query_canceled(&e) => Suggestions {
+                posts,
+                tags: Vec::new(),
+            },
+            Err(e) => {
+                return Err(anyhow::Error::from(e)
+                    .context("Failed to query tag suggestions")
+                    .into());
+            }
+        },
+        Err(e) if is_query_canceled(&e) => Suggestions::default(),
+        Err(e) => {
+            return Err(anyhow::Error::from(e)
+                .context("Failed to query post suggestions")
+                .into());
+        }
+    };
+
+    Ok(StatusCode::success(Some(suggestions)).into())
+}
+
+/// Published posts related to a post, by shared tags and text similarity
+///
+/// Text similarity compares the candidates against the post's title and tag
+/// lexemes, plus trigram similarity of the titles for text the full-text
+/// configuration does not split (such as Chinese).
+pub(crate) async fn related_posts(pool: &PgPool, post_id: i32) -> AppResult<Vec<RelatedPost>> {
+    let related = sqlx::query_as!(
+        RelatedPost,
+        r#"WITH source AS (
+            SELECT p.id, p.title,
+                (SELECT to_tsquery('simple', string_agg(quote_literal(l.lexeme), ' | '))
+                FROM unnest(ts_filter(p.search_vector, '{a,b}')) AS l) AS query
+            FROM posts p WHERE p.id = $1
+        ),
+        source_tags AS (
+            SELECT tag_id FROM post_tags WHERE post_id = $1
+        ),
+        candidates AS (
+            SELECT c.id, c.title, c.slug, c.excerpt, c.published_at,
+                (SELECT COUNT(*) FROM post_tags pt
+                WHERE pt.post_id = c.id AND pt.tag_id IN (SELECT tag_id FROM source_tags)) AS shared_tags,
+                COALESCE(ts_rank(c.search_vector, s.query), 0) AS text_rank,
+                similarity(c.title, s.title) AS title_similarity
+            FROM posts c, source s
+            WHERE c.status = 'published' AND c.id <> s.id
+                AND (EXISTS (SELECT 1 FROM post_tags pt
+                        WHERE pt.post_id = c.id AND pt.tag_id IN (SELECT tag_id FROM source_tags))
+                    OR c.search_vector @@ s.query
+                    OR c.title % s.title)
+        )
+        SELECT id, title, slug, excerpt, published_at,
+            (shared_tags + text_rank * 2 + title_similarity)::REAL AS "score!"
+        FROM candidates
+        ORDER BY "score!" DESC, published_at DESC, id DESC
+        LIMIT $2"#,
+        post_id,
+        RELATED_POSTS_LIMIT,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query related posts")?;
+
+    Ok(related)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_html_escapes_content() {
+        assert_eq!(
+            mark_html("<script>\u{2}alert\u{3}</script> & co"),
+            "&lt;script&gt;<mark>alert</mark>&lt;/script&gt; &amp; co"
+        );
+    }
+
+    #[test]
+    fn test_like_patterns() {
+        assert_eq!(
+            like_patterns(" rust  100%_done "),
+            vec!["%rust%", "%100\\%\\_done%"]
+        );
+    }
+
+    #[test]
+    fn test_highlight_terms() {
+        assert_eq!(
+            mark_html(&highlight_terms("Rust 与 rust 异步", "rust 异步", None)),
+            "<mark>Rust</mark> 与 <mark>rust</mark> <mark>异步</mark>"
+        );
+
+        let text = format!("{}找到我{}", "前".repeat(50), "后".repeat(50));
+        let snippet = highlight_terms(&text, "找到", Some(20));
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("\u{2}找到\u{3}我"));
+        assert_eq!(
+            snippet
+                .chars()
+                .filter(|&c| c != '\u{2}' && c != '\u{3}')
+                .count(),
+            22
+        );
+    }
+
+    #[test]
+    fn test_contains_cjk() {
+        assert!(contains_cjk("rust 异步"));
+        assert!(!contains_cjk("async rust"));
+    }
+}
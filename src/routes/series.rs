@@ -0,0 +1,503 @@
+use anyhow::Context;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::{delete, get, post, put},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use validator::Validate;
+
+use crate::db::ReadPool;
+use crate::error::AppResult;
+use crate::extractors::{Auth, RequirePermission, ValidatedJson};
+use crate::models::{
+    PostStatus, PostSummary, Series, SeriesNavigation, SeriesPostLink, SeriesWithCount,
+};
+use crate::permissions::WritePosts;
+use crate::response::{
+    ErrorDetail, PaginationQuery, PaginationResponse, StatusCode, SuccessResponse,
+};
+use crate::routes::{slug_for, validate_slug};
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/series/list", get(list_series))
+        .route("/series/create", post(create_series))
+        .route("/series/{slug}", get(get_series))
+        .route("/series/id/{id}", put(update_series).delete(delete_series))
+        .route(
+            "/series/id/{id}/posts",
+            put(reorder_series_posts).post(add_series_post),
+        )
+        .route(
+            "/series/id/{id}/posts/{post_id}",
+            delete(remove_series_post),
+        )
+}
+
+/// Builds the previous/next navigation for a post that is part of a series
+///
+/// Only published parts are linked; the current post is always included so
+/// authors previewing a draft still see where it will sit.
+pub(crate) async fn navigation_for_post(
+    pool: &PgPool,
+    post_id: i32,
+) -> AppResult<Option<SeriesNavigation>> {
+    let Some(series) = sqlx::query_as!(
+        Series,
+        r#"SELECT s.id, s.title, s.slug, s.description, s.author_id, s.created_at, s.updated_at
+        FROM series s JOIN series_posts sp ON sp.series_id = s.id
+        WHERE sp.post_id = $1"#,
+        post_id,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query post series")?
+    else {
+        return Ok(None);
+    };
+
+    let parts = sqlx::query_as!(
+        SeriesPostLink,
+        r#"SELECT p.id, p.title, p.slug
+        FROM series_posts sp JOIN posts p ON p.id = sp.post_id
+        WHERE sp.series_id = $1 AND (p.status = 'published' OR p.id = $2)
+        ORDER BY sp.position"#,
+        series.id,
+        post_id,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query series posts")?;
+
+    Ok(SeriesNavigation::build(&series, &parts, post_id))
+}
+
+/// All parts of a series in order, regardless of their status
+async fn series_parts(pool: &PgPool, series_id: i32) -> AppResult<Vec<SeriesPostLink>> {
+    let parts = sqlx::query_as!(
+        SeriesPostLink,
+        r#"SELECT p.id, p.title, p.slug
+        FROM series_posts sp JOIN posts p ON p.id = sp.post_id
+        WHERE sp.series_id = $1
+        ORDER BY sp.position"#,
+        series_id,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query series posts")?;
+
+    Ok(parts)
+}
+
+/// Locks a series for a membership change and checks the caller owns it
+async fn lock_owned_series(
+    tx: &mut Transaction<'_, Postgres>,
+    id: i32,
+    auth: &Auth,
+) -> AppResult<Series> {
+    let series = sqlx::query_as!(
+        Series,
+        r#"SELECT id, title, slug, description, author_id, created_at, updated_at
+        FROM series WHERE id = $1 FOR UPDATE"#,
+        id,
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .context("Failed to query series")?
+    .ok_or_else(StatusCode::resource_not_found)?;
+
+    if series.author_id != auth.0.user_id()? {
+        return Err(StatusCode::access_denied().into());
+    }
+
+    Ok(series)
+}
+
+/// Checks that the posts belong to the caller and are not in another series
+async fn check_series_candidates(
+    tx: &mut Transaction<'_, Postgres>,
+    series_id: i32,
+    post_ids: &[i32],
+    auth: &Auth,
+) -> AppResult<()> {
+    let owned: Vec<i32> = sqlx::query_scalar!(
+        r#"SELECT id FROM posts WHERE id = ANY($1) AND author_id = $2"#,
+        post_ids,
+        auth.0.user_id()?,
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .context("Failed to query posts")?;
+    let missing: Vec<ErrorDetail> = post_ids
+        .iter()
+        .filter(|id| !owned.contains(id))
+        .map(|id| ErrorDetail {
+            field: Some("post_ids".to_string()),
+            message: format!("Post {} does not exist or is not yours", id),
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(StatusCode::param_error().with_errors(missing).into());
+    }
+
+    let taken = sqlx::query!(
+        r#"SELECT sp.post_id, s.slug FROM series_posts sp JOIN series s ON s.id = sp.series_id
+        WHERE sp.post_id = ANY($1) AND sp.series_id <> $2"#,
+        post_ids,
+        series_id,
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .context("Failed to query series membership")?;
+    if !taken.is_empty() {
+        let details = taken
+            .into_iter()
+            .map(|row| ErrorDetail {
+                field: Some("post_ids".to_string()),
+                message: format!(
+                    "Post {} already belongs to series {}",
+                    row.post_id, row.slug
+                ),
+            })
+            .collect();
+        return Err(StatusCode::conflict().with_errors(details).into());
+    }
+
+    Ok(())
+}
+
+async fn list_series(
+    State(ReadPool(pool)): State<ReadPool>,
+    Query(pagination): Query<PaginationQuery>,
+) -> AppResult<Json<PaginationResponse<SeriesWithCount>>> {
+    let total: i64 = sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM series"#)
+        .fetch_one(&pool)
+        .await
+        .context("Failed to count series")?;
+
+    let series = sqlx::query_as!(
+        SeriesWithCount,
+        r#"SELECT s.id, s.title, s.slug, s.description, s.author_id, COUNT(p.id) AS "post_count!"
+        FROM series s
+        LEFT JOIN series_posts sp ON sp.series_id = s.id
+        LEFT JOIN posts p ON p.id = sp.post_id AND p.status = 'published'
+        GROUP BY s.id
+        ORDER BY s.updated_at DESC, s.id DESC
+        LIMIT $1 OFFSET $2"#,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query series")?;
+
+    Ok(PaginationResponse::new(
+        StatusCode::Success,
+        "Success",
+        series,
+        pagination.info(total as u64),
+    )
+    .into())
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesDetail {
+    #[serde(flatten)]
+    pub series: Series,
+    /// Published parts in reading order
+    pub posts: Vec<PostSummary>,
+}
+
+async fn get_series(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+) -> AppResult<Json<SuccessResponse<SeriesDetail>>> {
+    let series = sqlx::query_as!(
+        Series,
+        r#"SELECT id, title, slug, description, author_id, created_at, updated_at
+        FROM series WHERE slug = $1"#,
+        &slug,
+    )
+    .fetch_optional(&pool)
+    .await
+    .context("Failed to query series")?
+    .ok_or_else(StatusCode::resource_not_found)?;
+
+    let posts = sqlx::query_as!(
+        PostSummary,
+        r#"SELECT p.id, p.title, p.slug, p.excerpt, p.reading_time_minutes, p.author_id,
+        p.status AS "status: PostStatus", p.published_at, p.created_at, p.updated_at
+        FROM series_posts sp JOIN posts p ON p.id = sp.post_id
+        WHERE sp.series_id = $1 AND p.status = 'published'
+        ORDER BY sp.position"#,
+        series.id,
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query series posts")?;
+
+    Ok(StatusCode::success(Some(SeriesDetail { series, posts })).into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateSeriesRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub title: String,
+
+    #[validate(custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+}
+
+async fn create_series(
+    State(pool): State<PgPool>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    ValidatedJson(payload): ValidatedJson<CreateSeriesRequest>,
+) -> AppResult<Json<SuccessResponse<Series>>> {
+    let slug = slug_for(&payload.title, payload.slug.as_deref())?;
+
+    let series = sqlx::query_as!(
+        Series,
+        r#"INSERT INTO series (title, slug, description, author_id) VALUES ($1, $2, $3, $4)
+        RETURNING id, title, slug, description, author_id, created_at, updated_at"#,
+        &payload.title,
+        &slug,
+        payload.description,
+        auth.0.user_id()?,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to create series")?;
+
+    Ok(StatusCode::created(Some(series)).into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateSeriesRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub title: Option<String>,
+
+    #[validate(custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+}
+
+async fn update_series(
+    State(pool): State<PgPool>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<UpdateSeriesRequest>,
+) -> AppResult<Json<SuccessResponse<Series>>> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    lock_owned_series(&mut tx, id, &auth).await?;
+
+    let series = sqlx::query_as!(
+        Series,
+        r#"UPDATE series SET
+            title = COALESCE($2, title),
+            slug = COALESCE($3, slug),
+            description = COALESCE($4, description),
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, title, slug, description, author_id, created_at, updated_at"#,
+        id,
+        payload.title,
+        payload.slug,
+        payload.description,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to update series")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    Ok(StatusCode::success(Some(series)).into())
+}
+
+async fn delete_series(
+    State(pool): State<PgPool>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<()>>> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    lock_owned_series(&mut tx, id, &auth).await?;
+
+    // 只删除系列本身，文章保留
+    sqlx::query!(r#"DELETE FROM series WHERE id = $1"#, id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete series")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    Ok(StatusCode::success(None).into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReorderSeriesPostsRequest {
+    /// Every post of the series in reading order; posts left out are removed
+    #[validate(length(max = 200))]
+    pub post_ids: Vec<i32>,
+}
+
+async fn reorder_series_posts(
+    State(pool): State<PgPool>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<ReorderSeriesPostsRequest>,
+) -> AppResult<Json<SuccessResponse<Vec<SeriesPostLink>>>> {
+    let mut unique = payload.post_ids.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    if unique.len() != payload.post_ids.len() {
+        return Err(StatusCode::param_error()
+            .with_debug("post_ids must not contain duplicates")
+            .into());
+    }
+
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    lock_owned_series(&mut tx, id, &auth).await?;
+    check_series_candidates(&mut tx, id, &payload.post_ids, &auth).await?;
+
+    sqlx::query!(r#"DELETE FROM series_posts WHERE series_id = $1"#, id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to clear series posts")?;
+
+    sqlx::query!(
+        r#"INSERT INTO series_posts (series_id, post_id, position)
+        SELECT $1, t.post_id, t.position
+        FROM UNNEST($2::INTEGER[]) WITH ORDINALITY AS t(post_id, position)"#,
+        id,
+        &payload.post_ids,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to store series order")?;
+
+    sqlx::query!(r#"UPDATE series SET updated_at = NOW() WHERE id = $1"#, id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to touch series")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    let parts = series_parts(&pool, id).await?;
+
+    Ok(StatusCode::success(Some(parts)).into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AddSeriesPostRequest {
+    pub post_id: i32,
+
+    /// 1-based position; appended to the end when omitted
+    #[validate(range(min = 1))]
+    pub position: Option<i32>,
+}
+
+async fn add_series_post(
+    State(pool): State<PgPool>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<AddSeriesPostRequest>,
+) -> AppResult<Json<SuccessResponse<Vec<SeriesPostLink>>>> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    lock_owned_series(&mut tx, id, &auth).await?;
+    check_series_candidates(&mut tx, id, &[payload.post_id], &auth).await?;
+
+    let already_in: bool = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM series_posts WHERE series_id = $1 AND post_id = $2) AS "exists!""#,
+        id,
+        payload.post_id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to query series membership")?;
+    if already_in {
+        return Err(StatusCode::duplicate_resource()
+            .with_debug("Post is already part of this series")
+            .into());
+    }
+
+    let count: i64 = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM series_posts WHERE series_id = $1"#,
+        id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to count series posts")?;
+    let position = payload
+        .position
+        .unwrap_or(i32::MAX)
+        .clamp(1, count as i32 + 1);
+
+    sqlx::query!(
+        r#"UPDATE series_posts SET position = position + 1
+        WHERE series_id = $1 AND position >= $2"#,
+        id,
+        position,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to shift series posts")?;
+
+    sqlx::query!(
+        r#"INSERT INTO series_posts (series_id, post_id, position) VALUES ($1, $2, $3)"#,
+        id,
+        payload.post_id,
+        position,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to add post to series")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    let parts = series_parts(&pool, id).await?;
+
+    Ok(StatusCode::success(Some(parts)).into())
+}
+
+async fn remove_series_post(
+    State(pool): State<PgPool>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path((id, post_id)): Path<(i32, i32)>,
+) -> AppResult<Json<SuccessResponse<Vec<SeriesPostLink>>>> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    lock_owned_series(&mut tx, id, &auth).await?;
+
+    let position: i32 = sqlx::query_scalar!(
+        r#"DELETE FROM series_posts WHERE series_id = $1 AND post_id = $2 RETURNING position"#,
+        id,
+        post_id,
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .context("Failed to remove post from series")?
+    .ok_or_else(StatusCode::resource_not_found)?;
+
+    // 保持 position 连续
+    sqlx::query!(
+        r#"UPDATE series_posts SET position = position - 1
+        WHERE series_id = $1 AND position > $2"#,
+        id,
+        position,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to shift series posts")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    let parts = series_parts(&pool, id).await?;
+
+    Ok(StatusCode::success(Some(parts)).into())
+}
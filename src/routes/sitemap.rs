@@ -0,0 +1,340 @@
+use std::{
+    fmt::Write as _,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::Context;
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::header,
+    response::IntoResponse,
+    routing::get,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::PgPool;
+
+use crate::db::ReadPool;
+use crate::error::AppResult;
+use crate::response::StatusCode;
+use crate::state::AppState;
+use crate::utils::config::{AppConfig, RobotsConfig, SiteConfig};
+
+// 单个 sitemap 文件最多包含的 URL 数（协议上限）
+const MAX_URLS_PER_SITEMAP: usize = 50_000;
+
+const XML_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+/// Routes served from the site root rather than below `/api`
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/sitemap.xml", get(sitemap))
+        .route("/sitemap-{page}", get(sitemap_page))
+        .route("/robots.txt", get(robots))
+}
+
+struct SitemapUrl {
+    loc: String,
+    lastmod: Option<DateTime<Utc>>,
+}
+
+/// Rendered sitemap files
+pub struct Sitemap {
+    /// Served at `/sitemap.xml`; an index once there is more than one page
+    root: String,
+    /// Pages served at `/sitemap-{n}.xml` (1-based) when split
+    pages: Vec<String>,
+}
+
+/// Last rendered sitemap, kept until the content version changes
+#[derive(Default)]
+pub struct SitemapCache {
+    cached: RwLock<Option<(i64, Arc<Sitemap>)>>,
+}
+
+impl SitemapCache {
+    fn get(&self, version: i64) -> Option<Arc<Sitemap>> {
+        let cached = self.cached.read().unwrap_or_else(|e| e.into_inner());
+        cached
+            .as_ref()
+            .filter(|(cached_version, _)| *cached_version == version)
+            .map(|(_, sitemap)| sitemap.clone())
+    }
+
+    fn set(&self, version: i64, sitemap: Arc<Sitemap>) {
+        *self.cached.write().unwrap_or_else(|e| e.into_inner()) = Some((version, sitemap));
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn w3c_datetime(value: DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn render_urlset(urls: &[SitemapUrl]) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?><urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+    );
+    for url in urls {
+        let _ = write!(xml, "<url><loc>{}</loc>", escape_xml(&url.loc));
+        if let Some(lastmod) = url.lastmod {
+            let _ = write!(xml, "<lastmod>{}</lastmod>", w3c_datetime(lastmod));
+        }
+        xml.push_str("</url>");
+    }
+    xml.push_str("</urlset>");
+    xml
+}
+
+fn render_index(sitemaps: &[SitemapUrl]) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?><sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+    );
+    for sitemap in sitemaps {
+        let _ = write!(xml, "<sitemap><loc>{}</loc>", escape_xml(&sitemap.loc));
+        if let Some(lastmod) = sitemap.lastmod {
+            let _ = write!(xml, "<lastmod>{}</lastmod>", w3c_datetime(lastmod));
+        }
+        xml.push_str("</sitemap>");
+    }
+    xml.push_str("</sitemapindex>");
+    xml
+}
+
+/// Renders the URLs as a single sitemap, or as an index plus pages of at
+/// most `max_urls` URLs each
+fn build_sitemap(site: &SiteConfig, urls: &[SitemapUrl], max_urls: usize) -> Sitemap {
+    if urls.len() <= max_urls {
+        return Sitemap {
+            root: render_urlset(urls),
+            pages: Vec::new(),
+        };
+    }
+
+    let chunks: Vec<&[SitemapUrl]> = urls.chunks(max_urls).collect();
+    let index: Vec<SitemapUrl> = chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| SitemapUrl {
+            loc: site.url(&format!("sitemap-{}.xml", i + 1)),
+            lastmod: chunk.iter().filter_map(|url| url.lastmod).max(),
+        })
+        .collect();
+
+    Sitemap {
+        root: render_index(&index),
+        pages: chunks.into_iter().map(render_urlset).collect(),
+    }
+}
+
+/// Version of everything the sitemap is built from
+///
+/// Triggers on posts, tags, categories and their link tables bump it on
+/// every write, so checking it is a single primary key lookup.
+async fn content_version(pool: &PgPool) -> AppResult<i64> {
+    let version =
+        sqlx::query_scalar!(r#"SELECT version FROM content_versions WHERE name = 'sitemap'"#,)
+            .fetch_one(pool)
+            .await
+            .context("Failed to query sitemap version")?;
+
+    Ok(version)
+}
+
+/// Every public page: home, published posts, and the tag, category and
+/// author pages that list at least one published post
+async fn load_urls(pool: &PgPool, site: &SiteConfig) -> AppResult<Vec<SitemapUrl>> {
+    let rows = sqlx::query!(
+        r#"SELECT path AS "path!", lastmod FROM (
+            SELECT '' AS path, MAX(updated_at) AS lastmod, 0 AS kind, '' AS sort
+            FROM posts WHERE status = 'published'
+            UNION ALL
+            SELECT 'posts/' || slug, updated_at, 1, slug
+            FROM posts WHERE status = 'published'
+            UNION ALL
+            SELECT 'tags/' || t.slug, GREATEST(t.updated_at, MAX(p.updated_at)), 2, t.slug
+            FROM tags t
+            JOIN post_tags pt ON pt.tag_id = t.id
+            JOIN posts p ON p.id = pt.post_id AND p.status = 'published'
+            GROUP BY t.id
+            UNION ALL
+            SELECT 'categories/' || c.slug, GREATEST(c.updated_at, MAX(p.updated_at)), 3, c.slug
+            FROM categories c
+            JOIN post_categories pc ON pc.category_id = c.id
+            JOIN posts p ON p.id = pc.post_id AND p.status = 'published'
+            GROUP BY c.id
+            UNION ALL
+            SELECT 'authors/' || author_id, MAX(updated_at), 4, LPAD(author_id::TEXT, 10, '0')
+            FROM posts WHERE status = 'published'
+            GROUP BY author_id
+        ) urls
+        ORDER BY kind, sort"#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query sitemap URLs")?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| SitemapUrl {
+            loc: site.url(&row.path),
+            lastmod: row.lastmod,
+        })
+        .collect())
+}
+
+async fn load_sitemap(
+    pool: &PgPool,
+    cache: &SitemapCache,
+    site: &SiteConfig,
+) -> AppResult<Arc<Sitemap>> {
+    let version = content_version(pool).await?;
+    if let Some(sitemap) = cache.get(version) {
+        return Ok(sitemap);
+    }
+
+    let urls = load_urls(pool, site).await?;
+    let sitemap = Arc::new(build_sitemap(site, &urls, MAX_URLS_PER_SITEMAP));
+    cache.set(version, sitemap.clone());
+
+    Ok(sitemap)
+}
+
+async fn sitemap(
+    State(ReadPool(pool)): State<ReadPool>,
+    State(cache): State<Arc<SitemapCache>>,
+    State(config): State<Arc<AppConfig>>,
+) -> AppResult<impl IntoResponse> {
+    let sitemap = load_sitemap(&pool, &cache, &config.site).await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, XML_CONTENT_TYPE)],
+        sitemap.root.clone(),
+    ))
+}
+
+async fn sitemap_page(
+    State(ReadPool(pool)): State<ReadPool>,
+    State(cache): State<Arc<SitemapCache>>,
+    State(config): State<Arc<AppConfig>>,
+    Path(page): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let page: usize = page
+        .strip_suffix(".xml")
+        .and_then(|page| page.parse().ok())
+        .ok_or_else(StatusCode::resource_not_found)?;
+
+    let sitemap = load_sitemap(&pool, &cache, &config.site).await?;
+    let body = page
+        .checked_sub(1)
+        .and_then(|i| sitemap.pages.get(i))
+        .ok_or_else(StatusCode::resource_not_found)?;
+
+    Ok(([(header::CONTENT_TYPE, XML_CONTENT_TYPE)], body.clone()))
+}
+
+fn render_robots(robots: &RobotsConfig, site: &SiteConfig) -> String {
+    let mut txt = String::from("User-agent: *\n");
+    for path in &robots.allow {
+        let _ = writeln!(txt, "Allow: {}", path);
+    }
+    for path in &robots.disallow {
+        let _ = writeln!(txt, "Disallow: {}", path);
+    }
+    if let Some(delay) = robots.crawl_delay {
+        let _ = writeln!(txt, "Crawl-delay: {}", delay);
+    }
+    let _ = write!(txt, "\nSitemap: {}\n", site.url("sitemap.xml"));
+    txt
+}
+
+async fn robots(State(config): State<Arc<AppConfig>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        render_robots(&config.robots, &config.site),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site() -> SiteConfig {
+        SiteConfig {
+            base_url: "https://blog.example.com/".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn urls(count: usize) -> Vec<SitemapUrl> {
+        (0..count)
+            .map(|i| SitemapUrl {
+                loc: site().url(&format!("posts/p{}", i)),
+                lastmod: DateTime::from_timestamp(1_700_000_000 + i as i64, 0),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_single_sitemap() {
+        let sitemap = build_sitemap(&site(), &urls(2), 3);
+
+        assert!(sitemap.pages.is_empty());
+        assert!(sitemap.root.contains("<urlset"));
+        assert!(sitemap.root.contains(
+            "<url><loc>https://blog.example.com/posts/p1</loc><lastmod>2023-11-14T22:13:21Z</lastmod></url>"
+        ));
+    }
+
+    #[test]
+    fn test_sitemap_index_split() {
+        let sitemap = build_sitemap(&site(), &urls(7), 3);
+
+        assert_eq!(sitemap.pages.len(), 3);
+        assert!(sitemap.root.contains("<sitemapindex"));
+        assert_eq!(sitemap.root.matches("<sitemap>").count(), 3);
+        assert!(
+            sitemap
+                .root
+                .contains("<loc>https://blog.example.com/sitemap-3.xml</loc>")
+        );
+        assert_eq!(sitemap.pages[2].matches("<url>").count(), 1);
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml("/a?b=1&c=<2>"), "/a?b=1&amp;c=&lt;2&gt;");
+    }
+
+    #[test]
+    fn test_render_robots() {
+        let robots = RobotsConfig {
+            allow: vec!["/api/feed.xml".to_string()],
+            disallow: vec!["/api/".to_string()],
+            crawl_delay: Some(5),
+        };
+
+        assert_eq!(
+            render_robots(&robots, &site()),
+            "User-agent: *\nAllow: /api/feed.xml\nDisallow: /api/\nCrawl-delay: 5\n\nSitemap: https://blog.example.com/sitemap.xml\n"
+        );
+    }
+
+    #[test]
+    fn test_sitemap_cache() {
+        let cache = SitemapCache::default();
+        assert!(cache.get(1).is_none());
+
+        cache.set(1, Arc::new(build_sitemap(&site(), &[], 1)));
+        assert!(cache.get(1).is_some());
+        assert!(cache.get(2).is_none());
+    }
+}
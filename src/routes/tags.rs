@@ -0,0 +1,366 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::{get, post, put},
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use validator::Validate;
+
+use crate::db::ReadPool;
+use crate::error::AppResult;
+use crate::extractors::{RequirePermission, ValidatedJson};
+use crate::models::{PostStatus, PostSummary, Tag, TagWithCount};
+use crate::permissions::{ManageTaxonomy, WritePosts};
+use crate::repositories::RoleRepository;
+use crate::response::{
+    ErrorDetail, PaginationQuery, PaginationResponse, StatusCode, SuccessResponse,
+};
+use crate::routes::{posts::fetch_owned_post, slug_for, validate_slug};
+use crate::state::AppState;
+use crate::utils::config::AppConfig;
+
+// 删除冲突时最多列出的文章数量
+const CONFLICT_DETAIL_LIMIT: i64 = 20;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/tags/list", get(list_tags))
+        .route("/tags/create", post(create_tag))
+        .route("/tags/id/{id}", put(rename_tag).delete(delete_tag))
+        .route("/tags/id/{id}/merge", post(merge_tag))
+        .route("/tags/{slug}/posts", get(list_tag_posts))
+        .route("/posts/id/{id}/tags", put(set_post_tags))
+}
+
+/// Tags attached to a post, ordered by name
+pub(crate) async fn tags_for_post(pool: &PgPool, post_id: i32) -> AppResult<Vec<Tag>> {
+    let tags = sqlx::query_as!(
+        Tag,
+        r#"SELECT t.id, t.name, t.slug, t.created_at, t.updated_at
+        FROM tags t JOIN post_tags pt ON pt.tag_id = t.id
+        WHERE pt.post_id = $1
+        ORDER BY t.name"#,
+        post_id,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query post tags")?;
+
+    Ok(tags)
+}
+
+async fn list_tags(
+    State(ReadPool(pool)): State<ReadPool>,
+) -> AppResult<Json<SuccessResponse<Vec<TagWithCount>>>> {
+    let tags = sqlx::query_as!(
+        TagWithCount,
+        r#"SELECT t.id, t.name, t.slug, COUNT(p.id) AS "post_count!"
+        FROM tags t
+        LEFT JOIN post_tags pt ON pt.tag_id = t.id
+        LEFT JOIN posts p ON p.id = pt.post_id AND p.status = 'published'
+        GROUP BY t.id
+        ORDER BY t.name"#,
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query tags")?;
+
+    Ok(StatusCode::success(Some(tags)).into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateTagRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+
+    #[validate(custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+}
+
+async fn create_tag(
+    State(pool): State<PgPool>,
+    _auth: RequirePermission<ManageTaxonomy>,
+    ValidatedJson(payload): ValidatedJson<CreateTagRequest>,
+) -> AppResult<Json<SuccessResponse<Tag>>> {
+    let slug = slug_for(&payload.name, payload.slug.as_deref())?;
+
+    let tag = sqlx::query_as!(
+        Tag,
+        r#"INSERT INTO tags (name, slug) VALUES ($1, $2)
+        RETURNING id, name, slug, created_at, updated_at"#,
+        payload.name,
+        slug,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to create tag")?;
+
+    Ok(StatusCode::created(Some(tag)).into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RenameTagRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+
+    #[validate(custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+}
+
+async fn rename_tag(
+    State(pool): State<PgPool>,
+    _auth: RequirePermission<ManageTaxonomy>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<RenameTagRequest>,
+) -> AppResult<Json<SuccessResponse<Tag>>> {
+    let tag = sqlx::query_as!(
+        Tag,
+        r#"UPDATE tags SET name = $2, slug = COALESCE($3, slug), updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, name, slug, created_at, updated_at"#,
+        id,
+        payload.name,
+        payload.slug,
+    )
+    .fetch_optional(&pool)
+    .await
+    .context("Failed to rename tag")?
+    .ok_or_else(StatusCode::resource_not_found)?;
+
+    Ok(StatusCode::success(Some(tag)).into())
+}
+
+async fn delete_tag(
+    State(pool): State<PgPool>,
+    _auth: RequirePermission<ManageTaxonomy>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<SuccessResponse<()>>> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    // 锁住标签行，避免检查期间有文章关联到该标签
+    sqlx::query_scalar!(r#"SELECT id FROM tags WHERE id = $1 FOR UPDATE"#, id)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to query tag")?
+        .ok_or_else(StatusCode::resource_not_found)?;
+
+    let usage: i64 = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM post_tags WHERE tag_id = $1"#,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to count tag usage")?;
+
+    if usage > 0 {
+        let slugs: Vec<String> = sqlx::query_scalar!(
+            r#"SELECT p.slug FROM post_tags pt JOIN posts p ON p.id = pt.post_id
+            WHERE pt.tag_id = $1
+            ORDER BY p.id
+            LIMIT $2"#,
+            id,
+            CONFLICT_DETAIL_LIMIT,
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to query tagged posts")?;
+
+        let mut details = vec![ErrorDetail {
+            field: Some("tag".to_string()),
+            message: format!(
+                "Tag is used by {} post(s); detach it or merge it into another tag first",
+                usage
+            ),
+        }];
+        details.extend(slugs.into_iter().map(|slug| ErrorDetail {
+            field: Some("posts".to_string()),
+            message: slug,
+        }));
+
+        return Err(StatusCode::conflict().with_errors(details).into());
+    }
+
+    sqlx::query!(r#"DELETE FROM tags WHERE id = $1"#, id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete tag")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    Ok(StatusCode::success(None).into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MergeRequest {
+    pub into_id: i32,
+}
+
+/// Moves every post from one tag to another and deletes the source tag
+async fn merge_tag(
+    State(pool): State<PgPool>,
+    _auth: RequirePermission<ManageTaxonomy>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<MergeRequest>,
+) -> AppResult<Json<SuccessResponse<Tag>>> {
+    if payload.into_id == id {
+        return Err(StatusCode::param_error()
+            .with_debug("Cannot merge a tag into itself")
+            .into());
+    }
+
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    let locked: Vec<i32> = sqlx::query_scalar!(
+        r#"SELECT id FROM tags WHERE id = ANY($1) FOR UPDATE"#,
+        &[id, payload.into_id],
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("Failed to query tags")?;
+    if locked.len() != 2 {
+        return Err(StatusCode::resource_not_found().into());
+    }
+
+    sqlx::query!(
+        r#"INSERT INTO post_tags (post_id, tag_id)
+        SELECT post_id, $2 FROM post_tags WHERE tag_id = $1
+        ON CONFLICT DO NOTHING"#,
+        id,
+        payload.into_id,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to move tagged posts")?;
+
+    sqlx::query!(r#"DELETE FROM tags WHERE id = $1"#, id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete merged tag")?;
+
+    let tag = sqlx::query_as!(
+        Tag,
+        r#"UPDATE tags SET updated_at = NOW() WHERE id = $1
+        RETURNING id, name, slug, created_at, updated_at"#,
+        payload.into_id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to query merged tag")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    Ok(StatusCode::success(Some(tag)).into())
+}
+
+async fn list_tag_posts(
+    State(ReadPool(pool)): State<ReadPool>,
+    Path(slug): Path<String>,
+    Query(pagination): Query<PaginationQuery>,
+) -> AppResult<Json<PaginationResponse<PostSummary>>> {
+    let tag_id: i32 = sqlx::query_scalar!(r#"SELECT id FROM tags WHERE slug = $1"#, slug)
+        .fetch_optional(&pool)
+        .await
+        .context("Failed to query tag")?
+        .ok_or_else(StatusCode::resource_not_found)?;
+
+    let total: i64 = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM posts p JOIN post_tags pt ON pt.post_id = p.id
+        WHERE pt.tag_id = $1 AND p.status = 'published'"#,
+        tag_id,
+    )
+    .fetch_one(&pool)
+    .await
+    .context("Failed to count posts")?;
+
+    let posts = sqlx::query_as!(
+        PostSummary,
+        r#"SELECT p.id, p.title, p.slug, p.excerpt, p.reading_time_minutes, p.author_id,
+        p.status AS "status: PostStatus", p.published_at, p.created_at, p.updated_at
+        FROM posts p JOIN post_tags pt ON pt.post_id = p.id
+        WHERE pt.tag_id = $1 AND p.status = 'published'
+        ORDER BY p.published_at DESC, p.id DESC
+        LIMIT $2 OFFSET $3"#,
+        tag_id,
+        pagination.limit(),
+        pagination.offset(),
+    )
+    .fetch_all(&pool)
+    .await
+    .context("Failed to query posts")?;
+
+    Ok(PaginationResponse::new(
+        StatusCode::Success,
+        "Success",
+        posts,
+        pagination.info(total as u64),
+    )
+    .into())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetPostTagsRequest {
+    #[validate(length(max = 50))]
+    pub tag_ids: Vec<i32>,
+}
+
+/// Replaces the tags attached to a post
+async fn set_post_tags(
+    State(pool): State<PgPool>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    State(config): State<Arc<AppConfig>>,
+    RequirePermission { auth, .. }: RequirePermission<WritePosts>,
+    Path(post_id): Path<i32>,
+    ValidatedJson(mut payload): ValidatedJson<SetPostTagsRequest>,
+) -> AppResult<Json<SuccessResponse<Vec<Tag>>>> {
+    fetch_owned_post(&pool, roles.as_ref(), &config, post_id, &auth).await?;
+
+    payload.tag_ids.sort_unstable();
+    payload.tag_ids.dedup();
+
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    let existing: Vec<i32> = sqlx::query_scalar!(
+        r#"SELECT id FROM tags WHERE id = ANY($1) FOR KEY SHARE"#,
+        &payload.tag_ids,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("Failed to query tags")?;
+    let missing: Vec<ErrorDetail> = payload
+        .tag_ids
+        .iter()
+        .filter(|id| !existing.contains(id))
+        .map(|id| ErrorDetail {
+            field: Some("tag_ids".to_string()),
+            message: format!("Tag {} does not exist", id),
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(StatusCode::param_error().with_errors(missing).into());
+    }
+
+    sqlx::query!(r#"DELETE FROM post_tags WHERE post_id = $1"#, post_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to detach tags")?;
+
+    sqlx::query!(
+        r#"INSERT INTO post_tags (post_id, tag_id)
+        SELECT $1, UNNEST($2::INTEGER[])"#,
+        post_id,
+        &payload.tag_ids,
+    )
+    .execute(&mut *tx)
+    .await
+    .context("Failed to attach tags")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    let tags = tags_for_post(&pool, post_id).await?;
+
+    Ok(StatusCode::success(Some(tags)).into())
+}
@@ -1,24 +1,42 @@
-use anyhow::Context;
+use std::sync::Arc;
+
 use axum::{
     Router,
-    extract::State,
-    routing::{get, post},
+    extract::{Path, State},
+    http::{HeaderMap, header},
+    routing::{delete, get, post},
 };
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
 use validator::Validate;
 
 use crate::error::AppResult;
-use crate::extractors::ValidatedJson;
-use crate::models::User;
-use crate::response::{StatusCode, SuccessResponse};
+use crate::extractors::{Auth, ClientIp, ValidatedJson};
+use crate::models::{Session, User};
+use crate::repositories::{
+    NewSession, NewUser, Refresh, RoleRepository, Rotation, SessionRepository, UserRepository,
+};
+use crate::response::{ErrorResponse, StatusCode, SuccessResponse};
+use crate::state::AppState;
+use crate::utils::config::AppConfig;
+use crate::utils::jwt::{JwtService, hash_refresh_token};
 use crate::utils::password;
 
-pub fn routes() -> Router<(PgPool, crate::utils::jwt::JwtService)> {
+pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/users/list", get(get_users_list))
         .route("/users/login", post(login))
+        .route("/users/refresh", post(refresh))
+        .route("/users/logout", post(logout))
         .route("/users/create", post(create_user))
+        .route("/users/me", get(get_current_user))
+        .route(
+            "/users/sessions",
+            get(list_sessions).delete(revoke_all_sessions),
+        )
+        .route("/users/sessions/{id}", delete(revoke_session))
 }
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
@@ -34,36 +52,28 @@ pub struct CreateUserRequest {
 }
 
 async fn create_user(
-    State((pool, _jwt_service)): State<(PgPool, crate::utils::jwt::JwtService)>,
+    State(users): State<Arc<dyn UserRepository>>,
+    State(config): State<Arc<AppConfig>>,
     ValidatedJson(payload): ValidatedJson<CreateUserRequest>,
 ) -> AppResult<axum::response::Json<SuccessResponse<User>>> {
     let password_hash = password::hash_password(&payload.password)?;
 
-    let user = sqlx::query_as::<_, User>(
-        r#"INSERT INTO users (username, email, avatar_url, bio, password_hash)
-        VALUES ($1, $2, NULL, NULL, $3)
-        RETURNING id, username, email, avatar_url, bio, last_login, created_at, updated_at"#,
-    )
-    .bind(&payload.username)
-    .bind(&payload.email)
-    .bind(&password_hash)
-    .fetch_one(&pool)
-    .await
-    .context("Failed to create user")?;
+    let user = users
+        .create(NewUser {
+            username: &payload.username,
+            email: &payload.email,
+            password_hash: &password_hash,
+            role: Some(config.admin.default_role.as_str()).filter(|role| !role.is_empty()),
+        })
+        .await?;
 
     Ok(StatusCode::created(Some(user)).into())
 }
 
 async fn get_users_list(
-    State((pool, _jwt_service)): State<(PgPool, crate::utils::jwt::JwtService)>,
+    State(users): State<Arc<dyn UserRepository>>,
 ) -> AppResult<axum::response::Json<SuccessResponse<Vec<User>>>> {
-    let users = sqlx::query_as::<_, User>(
-r#"SELECT id, username, email, avatar_url, bio, last_login, created_at, updated_at FROM users
-ORDER BY created_at DESC"#
-    )
-        .fetch_all(&pool)
-        .await
-        .context("Failed to query users")?;
+    let users = users.list().await?;
 
     Ok(StatusCode::success(Some(users)).into())
 }
@@ -77,35 +87,835 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+/// A short-lived access token and the refresh token to renew it
+#[derive(Debug, Serialize)]
+pub struct AuthTokens {
+    pub token: String,
+    pub refresh_token: String,
+    /// Seconds until `token` expires
+    pub expires_in: u64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct LoginResponse {
     pub user: User,
-    pub token: String,
+    #[serde(flatten)]
+    pub tokens: AuthTokens,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct RefreshRequest {
+    #[validate(length(min = 1))]
+    pub refresh_token: String,
+}
+
+fn invalid_credentials() -> ErrorResponse {
+    ErrorResponse::new(StatusCode::Unauthorized, "Invalid identifier or password")
 }
 
+/// Longest User-Agent stored with a session, matching the column
+const MAX_USER_AGENT_LEN: usize = 512;
+
+/// The User-Agent header, cut to fit the sessions table
+fn user_agent(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::USER_AGENT)?.to_str().ok()?;
+    let end = value
+        .char_indices()
+        .nth(MAX_USER_AGENT_LEN)
+        .map_or(value.len(), |(index, _)| index);
+    Some(&value[..end])
+}
+
+/// Roles put in the `roles` claim, for services that only see the token
+///
+/// Superusers from `admin.user_ids` always carry `admin`, even without the
+/// role assigned.
+async fn roles_for(
+    roles: &dyn RoleRepository,
+    config: &AppConfig,
+    user_id: i32,
+) -> AppResult<Vec<String>> {
+    let mut names = roles.roles_of(user_id).await?;
+    if config.admin.is_admin(user_id) && !names.iter().any(|name| name == "admin") {
+        names.push("admin".to_string());
+        names.sort();
+    }
+    Ok(names)
+}
+
+/// Signs an access token and starts a new session with its first refresh token
+async fn issue_tokens(
+    jwt_service: &JwtService,
+    sessions: &dyn SessionRepository,
+    roles: Vec<String>,
+    user_id: i32,
+    user_agent: Option<&str>,
+    ip_address: Option<&str>,
+) -> AppResult<AuthTokens> {
+    let session_id = Uuid::new_v4();
+    let refresh_token = jwt_service.generate_refresh_token(Utc::now());
+    sessions
+        .create(NewSession {
+            id: session_id,
+            user_id,
+            user_agent,
+            ip_address,
+            refresh_token_hash: &refresh_token.hash,
+            expires_at: refresh_token.expires_at,
+        })
+        .await?;
+
+    Ok(AuthTokens {
+        token: jwt_service.generate_token(&user_id.to_string(), Some(session_id), roles)?,
+        refresh_token: refresh_token.token,
+        expires_in: jwt_service.expires_in(),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn login(
-    State((pool, jwt_service)): State<(PgPool, crate::utils::jwt::JwtService)>,
+    State(users): State<Arc<dyn UserRepository>>,
+    State(sessions): State<Arc<dyn SessionRepository>>,
+    State(jwt_service): State<JwtService>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    State(config): State<Arc<AppConfig>>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
     ValidatedJson(payload): ValidatedJson<LoginRequest>,
 ) -> AppResult<axum::response::Json<SuccessResponse<LoginResponse>>> {
-    let user: User = sqlx::query_as(
-        r#"SELECT id, username, email, avatar_url, bio, last_login, created_at, updated_at
-        FROM users WHERE email = $1"#,
+    // 用户不存在和密码错误返回相同的错误，避免泄露账号是否存在
+    let credentials = users
+        .find_credentials_by_email(&payload.identifier)
+        .await?
+        .ok_or_else(invalid_credentials)?;
+
+    password::verify_password(&payload.password, &credentials.password_hash)
+        .map_err(|_| invalid_credentials())?;
+
+    let user = credentials.user;
+    let tokens = issue_tokens(
+        &jwt_service,
+        sessions.as_ref(),
+        roles_for(roles.as_ref(), &config, user.id).await?,
+        user.id,
+        user_agent(&headers),
+        ip.map(|ip| ip.to_string()).as_deref(),
     )
-    .bind(&payload.identifier)
-    .fetch_one(&pool)
-    .await
-    .context("Invalid identifier or password")?;
-
-    let password_hash: String =
-        sqlx::query_scalar(r#"SELECT password_hash FROM users WHERE email = $1"#)
-            .bind(&payload.identifier)
-            .fetch_one(&pool)
+    .await?;
+
+    Ok(StatusCode::success(Some(LoginResponse { user, tokens })).into())
+}
+
+/// Exchanges a refresh token for a new access token and refresh token
+///
+/// Each refresh token works once. Presenting one that was already exchanged
+/// means it has leaked, so the whole session is revoked.
+async fn refresh(
+    State(sessions): State<Arc<dyn SessionRepository>>,
+    State(jwt_service): State<JwtService>,
+    State(roles): State<Arc<dyn RoleRepository>>,
+    State(config): State<Arc<AppConfig>>,
+    ClientIp(ip): ClientIp,
+    ValidatedJson(payload): ValidatedJson<RefreshRequest>,
+) -> AppResult<axum::response::Json<SuccessResponse<AuthTokens>>> {
+    let now = Utc::now();
+    let revoked_until = jwt_service.access_tokens_expire_by(now);
+    let replacement = jwt_service.generate_refresh_token(now);
+    let ip = ip.map(|ip| ip.to_string());
+
+    let rotation = sessions
+        .rotate(
+            Refresh {
+                token_hash: &hash_refresh_token(&payload.refresh_token),
+                replacement_hash: &replacement.hash,
+                expires_at: replacement.expires_at,
+                ip_address: ip.as_deref(),
+            },
+            now,
+            revoked_until,
+        )
+        .await?;
+
+    let (user_id, session_id) = match rotation {
+        Rotation::Rotated {
+            user_id,
+            session_id,
+        } => (user_id, session_id),
+        Rotation::Reused {
+            user_id,
+            session_id,
+        } => {
+            warn!(user_id, %session_id, "Refresh token reused, revoking the session");
+            jwt_service.revocations().revoke(session_id, revoked_until);
+            return Err(StatusCode::token_invalid().into());
+        }
+        Rotation::Invalid => return Err(StatusCode::token_invalid().into()),
+    };
+
+    let tokens = AuthTokens {
+        token: jwt_service.generate_token(
+            &user_id.to_string(),
+            Some(session_id),
+            roles_for(roles.as_ref(), &config, user_id).await?,
+        )?,
+        refresh_token: replacement.token,
+        expires_in: jwt_service.expires_in(),
+    };
+
+    Ok(StatusCode::success(Some(tokens)).into())
+}
+
+/// Revokes the session of a refresh token, together with the access tokens
+/// issued for it
+async fn logout(
+    State(sessions): State<Arc<dyn SessionRepository>>,
+    State(jwt_service): State<JwtService>,
+    ValidatedJson(payload): ValidatedJson<RefreshRequest>,
+) -> AppResult<axum::response::Json<SuccessResponse<()>>> {
+    let now = Utc::now();
+    let revoked_until = jwt_service.access_tokens_expire_by(now);
+
+    // 未知的令牌同样返回成功，登出是幂等的
+    let session_id = sessions
+        .revoke_by_refresh_token(
+            &hash_refresh_token(&payload.refresh_token),
+            now,
+            revoked_until,
+        )
+        .await?;
+    if let Some(session_id) = session_id {
+        jwt_service.revocations().revoke(session_id, revoked_until);
+    }
+
+    Ok(StatusCode::success(None).into())
+}
+
+/// Sessions of the current user that can still be refreshed
+async fn list_sessions(
+    State(sessions): State<Arc<dyn SessionRepository>>,
+    Auth(claims): Auth,
+) -> AppResult<axum::response::Json<SuccessResponse<Vec<Session>>>> {
+    let user_id = claims.user_id()?;
+    let sessions = sessions
+        .list_active(user_id, claims.sid, Utc::now())
+        .await?;
+
+    Ok(StatusCode::success(Some(sessions)).into())
+}
+
+/// Signs out one session of the current user, such as a lost device
+async fn revoke_session(
+    State(sessions): State<Arc<dyn SessionRepository>>,
+    State(jwt_service): State<JwtService>,
+    Auth(claims): Auth,
+    Path(id): Path<Uuid>,
+) -> AppResult<axum::response::Json<SuccessResponse<()>>> {
+    let user_id = claims.user_id()?;
+    let now = Utc::now();
+    let revoked_until = jwt_service.access_tokens_expire_by(now);
+
+    let revoked = sessions
+        .revoke(user_id, Some(id), now, revoked_until)
+        .await?;
+    if revoked.is_empty() {
+        return Err(StatusCode::resource_not_found().into());
+    }
+    for session_id in revoked {
+        jwt_service.revocations().revoke(session_id, revoked_until);
+    }
+
+    Ok(StatusCode::success(None).into())
+}
+
+/// Signs out every session of the current user, including this one
+async fn revoke_all_sessions(
+    State(sessions): State<Arc<dyn SessionRepository>>,
+    State(jwt_service): State<JwtService>,
+    Auth(claims): Auth,
+) -> AppResult<axum::response::Json<SuccessResponse<()>>> {
+    let user_id = claims.user_id()?;
+    let now = Utc::now();
+    let revoked_until = jwt_service.access_tokens_expire_by(now);
+
+    for session_id in sessions.revoke(user_id, None, now, revoked_until).await? {
+        jwt_service.revocations().revoke(session_id, revoked_until);
+    }
+
+    Ok(StatusCode::success(None).into())
+}
+
+async fn get_current_user(
+    State(users): State<Arc<dyn UserRepository>>,
+    Auth(claims): Auth,
+) -> AppResult<axum::response::Json<SuccessResponse<User>>> {
+    let user_id = claims.user_id()?;
+
+    let user = users
+        .find_by_id(user_id)
+        .await?
+        .ok_or_else(StatusCode::resource_not_found)?;
+
+    Ok(StatusCode::success(Some(user)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use crate::repositories::{
+        InMemoryRoleRepository, InMemorySessionRepository, InMemoryUserRepository,
+    };
+    use crate::utils::config::{AdminConfig, JwtConfig};
+    use crate::utils::jwt::JwtError;
+
+    fn config() -> Arc<AppConfig> {
+        Arc::new(AppConfig {
+            jwt: JwtConfig {
+                secret: "test-secret".to_string(),
+                expires_in: "1h".to_string(),
+                ..Default::default()
+            },
+            // 第一个注册的用户是管理员
+            admin: AdminConfig {
+                user_ids: vec![1],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    fn jwt_service() -> JwtService {
+        JwtService::from_config(&config()).unwrap()
+    }
+
+    fn repositories() -> (Arc<dyn UserRepository>, Arc<dyn RoleRepository>) {
+        let roles = Arc::new(InMemoryRoleRepository::default());
+        (
+            Arc::new(InMemoryUserRepository::with_roles(roles.clone())),
+            roles,
+        )
+    }
+
+    fn sessions() -> Arc<dyn SessionRepository> {
+        Arc::new(InMemorySessionRepository::default())
+    }
+
+    async fn register(users: &Arc<dyn UserRepository>, username: &str) -> User {
+        let request = CreateUserRequest {
+            username: username.to_string(),
+            email: format!("{}@example.com", username),
+            password: "correct horse".to_string(),
+        };
+        create_user(
+            State(users.clone()),
+            State(config()),
+            ValidatedJson(request),
+        )
+        .await
+        .unwrap()
+        .0
+        .data
+        .unwrap()
+    }
+
+    async fn login_as(
+        users: &Arc<dyn UserRepository>,
+        roles: &Arc<dyn RoleRepository>,
+        identifier: &str,
+        password: &str,
+    ) -> AppResult<LoginResponse> {
+        login_with(
+            users,
+            roles,
+            &sessions(),
+            &jwt_service(),
+            identifier,
+            password,
+        )
+        .await
+    }
+
+    async fn login_with(
+        users: &Arc<dyn UserRepository>,
+        roles: &Arc<dyn RoleRepository>,
+        sessions: &Arc<dyn SessionRepository>,
+        jwt: &JwtService,
+        identifier: &str,
+        password: &str,
+    ) -> AppResult<LoginResponse> {
+        let request = LoginRequest {
+            identifier: identifier.to_string(),
+            password: password.to_string(),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, "test-agent".parse().unwrap());
+        let response = login(
+            State(users.clone()),
+            State(sessions.clone()),
+            State(jwt.clone()),
+            State(roles.clone()),
+            State(config()),
+            ClientIp(Some([127, 0, 0, 1].into())),
+            headers,
+            ValidatedJson(request),
+        )
+        .await?;
+        Ok(response.0.data.unwrap())
+    }
+
+    async fn refresh_with(
+        roles: &Arc<dyn RoleRepository>,
+        sessions: &Arc<dyn SessionRepository>,
+        jwt: &JwtService,
+        refresh_token: &str,
+    ) -> AppResult<AuthTokens> {
+        let request = RefreshRequest {
+            refresh_token: refresh_token.to_string(),
+        };
+        let response = refresh(
+            State(sessions.clone()),
+            State(jwt.clone()),
+            State(roles.clone()),
+            State(config()),
+            ClientIp(None),
+            ValidatedJson(request),
+        )
+        .await?;
+        Ok(response.0.data.unwrap())
+    }
+
+    async fn list_with(
+        sessions: &Arc<dyn SessionRepository>,
+        jwt: &JwtService,
+        token: &str,
+    ) -> Vec<Session> {
+        let claims = jwt.validate_token(token).unwrap();
+        list_sessions(State(sessions.clone()), Auth(claims))
+            .await
+            .unwrap()
+            .0
+            .data
+            .unwrap()
+    }
+
+    fn error_code(result: AppResult<impl std::fmt::Debug>) -> StatusCode {
+        match result {
+            Err(AppError::Response(err)) => err.code,
+            other => panic!("expected an error response, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_returns_token_for_user() {
+        let (users, roles) = repositories();
+        let alice = register(&users, "alice").await;
+
+        let response = login_as(&users, &roles, "alice@example.com", "correct horse")
+            .await
+            .unwrap();
+        assert_eq!(response.user.id, alice.id);
+
+        let claims = jwt_service()
+            .validate_token(&response.tokens.token)
+            .unwrap();
+        assert_eq!(claims.user_id().unwrap(), alice.id);
+        assert_eq!(claims.iss, "blog-axum");
+        // 超级用户即使没有分配 admin 角色也带上它
+        assert_eq!(claims.roles, vec!["admin", "reader"]);
+        assert_eq!(response.tokens.expires_in, 3600);
+
+        let bob = register(&users, "bob").await;
+        let response = login_as(&users, &roles, "bob@example.com", "correct horse")
+            .await
+            .unwrap();
+        let claims = jwt_service()
+            .validate_token(&response.tokens.token)
+            .unwrap();
+        assert_eq!(claims.roles, vec!["reader"]);
+
+        roles
+            .set_roles(bob.id, &["editor".to_string()], Some(alice.id))
+            .await
+            .unwrap();
+        let response = login_as(&users, &roles, "bob@example.com", "correct horse")
+            .await
+            .unwrap();
+        let claims = jwt_service()
+            .validate_token(&response.tokens.token)
+            .unwrap();
+        assert_eq!(claims.roles, vec!["editor"]);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_tokens() {
+        let (users, roles) = repositories();
+        let tokens = sessions();
+        let jwt = jwt_service();
+        let alice = register(&users, "alice").await;
+        let login = login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "alice@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+
+        let first = refresh_with(&roles, &tokens, &jwt, &login.tokens.refresh_token)
+            .await
+            .unwrap();
+        assert_ne!(first.refresh_token, login.tokens.refresh_token);
+        let claims = jwt_service().validate_token(&first.token).unwrap();
+        assert_eq!(claims.user_id().unwrap(), alice.id);
+
+        let second = refresh_with(&roles, &tokens, &jwt, &first.refresh_token)
+            .await
+            .unwrap();
+        assert_ne!(second.refresh_token, first.refresh_token);
+
+        assert_eq!(
+            error_code(refresh_with(&roles, &tokens, &jwt, "unknown").await),
+            StatusCode::TokenInvalid
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_reuse_revokes_family() {
+        let (users, roles) = repositories();
+        let tokens = sessions();
+        let jwt = jwt_service();
+        register(&users, "alice").await;
+        let stolen = login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "alice@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+        let other_session = login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "alice@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+
+        let rotated = refresh_with(&roles, &tokens, &jwt, &stolen.tokens.refresh_token)
+            .await
+            .unwrap();
+        assert_eq!(
+            error_code(refresh_with(&roles, &tokens, &jwt, &stolen.tokens.refresh_token).await),
+            StatusCode::TokenInvalid
+        );
+        // 重放之后，同一 family 中最新的令牌也失效
+        assert_eq!(
+            error_code(refresh_with(&roles, &tokens, &jwt, &rotated.refresh_token).await),
+            StatusCode::TokenInvalid
+        );
+        assert!(matches!(
+            jwt.validate_token(&rotated.token),
+            Err(JwtError::RevokedToken)
+        ));
+        // 其他登录会话不受影响
+        assert!(
+            refresh_with(&roles, &tokens, &jwt, &other_session.tokens.refresh_token)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_logout_invalidates_refresh_token() {
+        let (users, roles) = repositories();
+        let tokens = sessions();
+        let jwt = jwt_service();
+        register(&users, "alice").await;
+        let login = login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "alice@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+
+        let request = RefreshRequest {
+            refresh_token: login.tokens.refresh_token.clone(),
+        };
+        let response = logout(
+            State(tokens.clone()),
+            State(jwt.clone()),
+            ValidatedJson(request),
+        )
+        .await
+        .unwrap();
+        assert!(response.0.success);
+
+        assert_eq!(
+            error_code(refresh_with(&roles, &tokens, &jwt, &login.tokens.refresh_token).await),
+            StatusCode::TokenInvalid
+        );
+        assert!(matches!(
+            jwt.validate_token(&login.tokens.token),
+            Err(JwtError::RevokedToken)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_sessions_flags_current() {
+        let (users, roles) = repositories();
+        let tokens = sessions();
+        let jwt = jwt_service();
+        register(&users, "alice").await;
+        register(&users, "bob").await;
+        let first = login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "alice@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+        let second = login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "alice@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+        login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "bob@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+
+        let list = list_with(&tokens, &jwt, &second.tokens.token).await;
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.iter().filter(|session| session.current).count(), 1);
+        let session = list.iter().find(|session| session.current).unwrap();
+        assert_eq!(session.user_agent.as_deref(), Some("test-agent"));
+        assert_eq!(session.ip_address.as_deref(), Some("127.0.0.1"));
+
+        let claims = jwt.validate_token(&first.tokens.token).unwrap();
+        assert_ne!(Some(session.id), claims.sid);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_session_invalidates_its_tokens() {
+        let (users, roles) = repositories();
+        let tokens = sessions();
+        let jwt = jwt_service();
+        register(&users, "alice").await;
+        let lost = login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "alice@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+        let current = login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "alice@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+        let lost_id = jwt.validate_token(&lost.tokens.token).unwrap().sid.unwrap();
+
+        let claims = jwt.validate_token(&current.tokens.token).unwrap();
+        let response = revoke_session(
+            State(tokens.clone()),
+            State(jwt.clone()),
+            Auth(claims.clone()),
+            Path(lost_id),
+        )
+        .await
+        .unwrap();
+        assert!(response.0.success);
+
+        assert!(matches!(
+            jwt.validate_token(&lost.tokens.token),
+            Err(JwtError::RevokedToken)
+        ));
+        assert_eq!(
+            error_code(refresh_with(&roles, &tokens, &jwt, &lost.tokens.refresh_token).await),
+            StatusCode::TokenInvalid
+        );
+        assert!(jwt.validate_token(&current.tokens.token).is_ok());
+        assert_eq!(
+            list_with(&tokens, &jwt, &current.tokens.token).await.len(),
+            1
+        );
+
+        // 已撤销或不属于当前用户的会话返回 404
+        let result = revoke_session(
+            State(tokens.clone()),
+            State(jwt.clone()),
+            Auth(claims),
+            Path(lost_id),
+        )
+        .await;
+        assert_eq!(error_code(result), StatusCode::ResourceNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_all_sessions() {
+        let (users, roles) = repositories();
+        let tokens = sessions();
+        let jwt = jwt_service();
+        register(&users, "alice").await;
+        let first = login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "alice@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+        let second = login_with(
+            &users,
+            &roles,
+            &tokens,
+            &jwt,
+            "alice@example.com",
+            "correct horse",
+        )
+        .await
+        .unwrap();
+
+        let claims = jwt.validate_token(&second.tokens.token).unwrap();
+        let response = revoke_all_sessions(State(tokens.clone()), State(jwt.clone()), Auth(claims))
             .await
-            .context("Invalid identifier or password")?;
+            .unwrap();
+        assert!(response.0.success);
+
+        for login in [&first, &second] {
+            assert!(matches!(
+                jwt.validate_token(&login.tokens.token),
+                Err(JwtError::RevokedToken)
+            ));
+            assert_eq!(
+                error_code(refresh_with(&roles, &tokens, &jwt, &login.tokens.refresh_token).await),
+                StatusCode::TokenInvalid
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_rejects_wrong_password_and_unknown_user() {
+        let (users, roles) = repositories();
+        register(&users, "alice").await;
+
+        assert_eq!(
+            error_code(login_as(&users, &roles, "alice@example.com", "wrong password").await),
+            StatusCode::Unauthorized
+        );
+        assert_eq!(
+            error_code(login_as(&users, &roles, "bob@example.com", "correct horse").await),
+            StatusCode::Unauthorized
+        );
+    }
 
-    password::verify_password(&payload.password, &password_hash)?;
+    #[tokio::test]
+    async fn test_create_user_rejects_duplicates() {
+        let (users, _) = repositories();
+        register(&users, "alice").await;
 
-    let token = jwt_service.generate_token(&user.id.to_string())?;
+        let request = CreateUserRequest {
+            username: "alice".to_string(),
+            email: "other@example.com".to_string(),
+            password: "correct horse".to_string(),
+        };
+        let result = create_user(
+            State(users.clone()),
+            State(config()),
+            ValidatedJson(request),
+        )
+        .await;
+        assert_eq!(error_code(result), StatusCode::DuplicateResource);
+    }
+
+    #[tokio::test]
+    async fn test_create_user_with_unknown_default_role_creates_nothing() {
+        let (users, _) = repositories();
+        let mut config = config().as_ref().clone();
+        config.admin.default_role = "owner".to_string();
+
+        let request = CreateUserRequest {
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            password: "correct horse".to_string(),
+        };
+        let result = create_user(
+            State(users.clone()),
+            State(Arc::new(config)),
+            ValidatedJson(request),
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(users.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_users_list_and_current_user() {
+        let (users, _) = repositories();
+        let alice = register(&users, "alice").await;
+        let bob = register(&users, "bob").await;
+
+        let list = get_users_list(State(users.clone()))
+            .await
+            .unwrap()
+            .0
+            .data
+            .unwrap();
+        let ids: Vec<i32> = list.iter().map(|user| user.id).collect();
+        assert_eq!(ids, vec![bob.id, alice.id]);
+
+        let token = jwt_service()
+            .generate_token(&alice.id.to_string(), None, Vec::new())
+            .unwrap();
+        let claims = jwt_service().validate_token(&token).unwrap();
+        let me = get_current_user(State(users.clone()), Auth(claims))
+            .await
+            .unwrap()
+            .0
+            .data
+            .unwrap();
+        assert_eq!(me.username, "alice");
 
-    Ok(StatusCode::success(Some(LoginResponse { user, token })).into())
+        let token = jwt_service()
+            .generate_token("999", None, Vec::new())
+            .unwrap();
+        let claims = jwt_service().validate_token(&token).unwrap();
+        let result = get_current_user(State(users), Auth(claims)).await;
+        assert_eq!(error_code(result), StatusCode::ResourceNotFound);
+    }
 }
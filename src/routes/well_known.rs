@@ -0,0 +1,17 @@
+use axum::{Json, Router, extract::State, routing::get};
+use jsonwebtoken::jwk::JwkSet;
+
+use crate::state::AppState;
+use crate::utils::jwt::JwtService;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/.well-known/jwks.json", get(jwks))
+}
+
+/// Public keys for verifying our access tokens, as a JSON Web Key Set
+///
+/// Served as plain JWKS rather than in the API envelope, since it is read
+/// by JWT libraries. Empty while tokens are signed with the shared secret.
+async fn jwks(State(jwt_service): State<JwtService>) -> Json<JwkSet> {
+    Json(jwt_service.jwks().clone())
+}
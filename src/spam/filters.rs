@@ -0,0 +1,256 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    sync::Mutex,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::{CommentSubmission, SpamFilter, Verdict};
+
+/// Holds comments that contain more links than allowed
+pub struct LinkCountFilter {
+    max_links: usize,
+}
+
+impl LinkCountFilter {
+    pub fn new(max_links: usize) -> Self {
+        Self { max_links }
+    }
+}
+
+/// Counts URL-looking tokens, including ones wrapped in Markdown or HTML syntax
+fn count_links(text: &str) -> usize {
+    text.split(|c: char| c.is_whitespace() || "()[]<>\"'".contains(c))
+        .map(str::to_ascii_lowercase)
+        .filter(|token| {
+            token.starts_with("http://")
+                || token.starts_with("https://")
+                || token.starts_with("www.")
+        })
+        .count()
+}
+
+#[async_trait]
+impl SpamFilter for LinkCountFilter {
+    fn name(&self) -> &'static str {
+        "links"
+    }
+
+    async fn check(&self, submission: &CommentSubmission<'_>) -> Verdict {
+        let links = count_links(submission.body);
+        if links > self.max_links {
+            Verdict::Hold(format!("{} links (max {})", links, self.max_links))
+        } else {
+            Verdict::Pass
+        }
+    }
+}
+
+/// Flags comments containing any of the configured words
+///
+/// Matching is a case-insensitive substring search over the body, the author
+/// name and the email address.
+pub struct BlockedWordFilter {
+    words: Vec<String>,
+}
+
+impl BlockedWordFilter {
+    pub fn new(words: &[String]) -> Self {
+        Self {
+            words: words
+                .iter()
+                .map(|word| word.trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl SpamFilter for BlockedWordFilter {
+    fn name(&self) -> &'static str {
+        "blocked_words"
+    }
+
+    async fn check(&self, submission: &CommentSubmission<'_>) -> Verdict {
+        let haystack = format!(
+            "{}\n{}\n{}",
+            submission.body,
+            submission.author_name,
+            submission.author_email.unwrap_or_default()
+        )
+        .to_lowercase();
+
+        match self
+            .words
+            .iter()
+            .find(|word| haystack.contains(word.as_str()))
+        {
+            Some(word) => Verdict::Spam(format!("contains blocked word \"{}\"", word)),
+            None => Verdict::Pass,
+        }
+    }
+}
+
+/// Flags submissions that filled in the hidden honeypot field
+pub struct HoneypotFilter;
+
+#[async_trait]
+impl SpamFilter for HoneypotFilter {
+    fn name(&self) -> &'static str {
+        "honeypot"
+    }
+
+    async fn check(&self, submission: &CommentSubmission<'_>) -> Verdict {
+        match submission.honeypot {
+            Some(value) if !value.trim().is_empty() => {
+                Verdict::Spam("honeypot field was filled in".to_string())
+            }
+            _ => Verdict::Pass,
+        }
+    }
+}
+
+/// Flags addresses that submit too many comments within a sliding window
+///
+/// Submissions are tracked in memory, so limits are per process and reset on
+/// restart. Requests without a known client address are not limited.
+pub struct RateLimitFilter {
+    limit: usize,
+    window: chrono::Duration,
+    submissions: Mutex<HashMap<IpAddr, VecDeque<DateTime<Utc>>>>,
+}
+
+impl RateLimitFilter {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window: chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX),
+            submissions: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl SpamFilter for RateLimitFilter {
+    fn name(&self) -> &'static str {
+        "rate_limit"
+    }
+
+    async fn check(&self, submission: &CommentSubmission<'_>) -> Verdict {
+        let Some(ip) = submission.ip else {
+            return Verdict::Pass;
+        };
+        let now = submission.submitted_at;
+        let cutoff = now
+            .checked_sub_signed(self.window)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+        let mut submissions = self.submissions.lock().unwrap();
+        // 顺带清理所有过期记录，避免表无限增长
+        submissions.retain(|_, times| {
+            while times.front().is_some_and(|t| *t <= cutoff) {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let times = submissions.entry(ip).or_default();
+        if times.len() >= self.limit {
+            return Verdict::Spam(format!(
+                "more than {} comments from {} within the rate window",
+                self.limit, ip
+            ));
+        }
+        times.push_back(now);
+
+        Verdict::Pass
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submission(body: &str) -> CommentSubmission<'_> {
+        CommentSubmission {
+            body,
+            author_name: "guest",
+            author_email: Some("guest@example.com"),
+            honeypot: None,
+            ip: Some("203.0.113.7".parse().unwrap()),
+            submitted_at: DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn test_count_links() {
+        assert_eq!(count_links("no links here"), 0);
+        assert_eq!(
+            count_links("see [docs](https://a.example) and <http://b.example>, www.c.example"),
+            3
+        );
+        assert_eq!(count_links("HTTPS://LOUD.EXAMPLE"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_link_count_filter_holds() {
+        let filter = LinkCountFilter::new(1);
+        assert_eq!(
+            filter.check(&submission("one https://a.example")).await,
+            Verdict::Pass
+        );
+        assert!(matches!(
+            filter
+                .check(&submission("https://a.example https://b.example"))
+                .await,
+            Verdict::Hold(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_blocked_words_ignore_case() {
+        let filter = BlockedWordFilter::new(&["Casino".to_string(), " ".to_string()]);
+        assert_eq!(filter.check(&submission("Great read")).await, Verdict::Pass);
+        assert!(matches!(
+            filter.check(&submission("Best CASINO bonus")).await,
+            Verdict::Spam(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_honeypot_filter() {
+        let mut s = submission("hello");
+        assert_eq!(HoneypotFilter.check(&s).await, Verdict::Pass);
+        s.honeypot = Some("  ");
+        assert_eq!(HoneypotFilter.check(&s).await, Verdict::Pass);
+        s.honeypot = Some("http://spam.example");
+        assert!(matches!(HoneypotFilter.check(&s).await, Verdict::Spam(_)));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_uses_sliding_window() {
+        let filter = RateLimitFilter::new(2, Duration::from_secs(60));
+        let mut s = submission("hi");
+
+        assert_eq!(filter.check(&s).await, Verdict::Pass);
+        s.submitted_at += chrono::Duration::seconds(10);
+        assert_eq!(filter.check(&s).await, Verdict::Pass);
+        s.submitted_at += chrono::Duration::seconds(10);
+        assert!(matches!(filter.check(&s).await, Verdict::Spam(_)));
+
+        // 其他 IP 不受影响
+        let mut other = s.clone();
+        other.ip = Some("198.51.100.1".parse().unwrap());
+        assert_eq!(filter.check(&other).await, Verdict::Pass);
+
+        // 第一条记录过期后再次放行
+        s.submitted_at += chrono::Duration::seconds(45);
+        assert_eq!(filter.check(&s).await, Verdict::Pass);
+    }
+}
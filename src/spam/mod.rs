@@ -0,0 +1,171 @@
+mod filters;
+
+pub use filters::{BlockedWordFilter, HoneypotFilter, LinkCountFilter, RateLimitFilter};
+
+use std::net::IpAddr;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tracing::debug;
+
+use crate::utils::{config::SpamConfig, parse_duration};
+
+/// Everything the filters may look at when a comment is submitted
+#[derive(Debug, Clone)]
+pub struct CommentSubmission<'a> {
+    pub body: &'a str,
+    pub author_name: &'a str,
+    pub author_email: Option<&'a str>,
+    /// Value of the hidden form field; humans never fill it in
+    pub honeypot: Option<&'a str>,
+    pub ip: Option<IpAddr>,
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// Outcome of a spam check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    /// Looks suspicious; keep the comment for manual review
+    Hold(String),
+    /// Almost certainly spam
+    Spam(String),
+}
+
+/// A single spam heuristic
+///
+/// Filters are async so implementations backed by a database or an external
+/// service can be plugged into the same chain.
+#[async_trait]
+pub trait SpamFilter: Send + Sync {
+    /// Name recorded next to the reason on flagged comments
+    fn name(&self) -> &'static str;
+
+    async fn check(&self, submission: &CommentSubmission<'_>) -> Verdict;
+}
+
+/// Runs spam filters in order
+///
+/// The first `Spam` verdict stops the chain. A `Hold` is remembered but the
+/// remaining filters still run, since one of them may upgrade it to spam.
+/// Reasons are prefixed with the filter name, e.g. `links: 5 links (max 2)`.
+#[derive(Default)]
+pub struct SpamFilterChain {
+    filters: Vec<Box<dyn SpamFilter>>,
+}
+
+impl SpamFilterChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with<F: SpamFilter + 'static>(mut self, filter: F) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Builds the chain of built-in filters described by the configuration
+    pub fn from_config(config: &SpamConfig) -> anyhow::Result<Self> {
+        let mut chain = Self::new();
+        if !config.enabled {
+            return Ok(chain);
+        }
+
+        if config.honeypot {
+            chain = chain.with(HoneypotFilter);
+        }
+        if !config.blocked_words.is_empty() {
+            chain = chain.with(BlockedWordFilter::new(&config.blocked_words));
+        }
+        if config.rate_limit > 0 {
+            let window = parse_duration(&config.rate_window).context("Invalid spam.rate_window")?;
+            chain = chain.with(RateLimitFilter::new(config.rate_limit, window));
+        }
+        chain = chain.with(LinkCountFilter::new(config.max_links));
+
+        Ok(chain)
+    }
+
+    pub async fn check(&self, submission: &CommentSubmission<'_>) -> Verdict {
+        let mut verdict = Verdict::Pass;
+
+        for filter in &self.filters {
+            match filter.check(submission).await {
+                Verdict::Pass => {}
+                Verdict::Hold(reason) => {
+                    debug!(filter = filter.name(), %reason, "Comment held for review");
+                    if verdict == Verdict::Pass {
+                        verdict = Verdict::Hold(format!("{}: {}", filter.name(), reason));
+                    }
+                }
+                Verdict::Spam(reason) => {
+                    debug!(filter = filter.name(), %reason, "Comment flagged as spam");
+                    return Verdict::Spam(format!("{}: {}", filter.name(), reason));
+                }
+            }
+        }
+
+        verdict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(&'static str, Verdict);
+
+    #[async_trait]
+    impl SpamFilter for Fixed {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        async fn check(&self, _submission: &CommentSubmission<'_>) -> Verdict {
+            self.1.clone()
+        }
+    }
+
+    fn submission() -> CommentSubmission<'static> {
+        CommentSubmission {
+            body: "Nice post",
+            author_name: "guest",
+            author_email: None,
+            honeypot: None,
+            ip: None,
+            submitted_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_keeps_first_hold_and_stops_at_spam() {
+        let chain = SpamFilterChain::new()
+            .with(Fixed("a", Verdict::Pass))
+            .with(Fixed("b", Verdict::Hold("odd".into())))
+            .with(Fixed("c", Verdict::Hold("odder".into())));
+        assert_eq!(
+            chain.check(&submission()).await,
+            Verdict::Hold("b: odd".into())
+        );
+
+        let chain = chain.with(Fixed("d", Verdict::Spam("bad".into())));
+        assert_eq!(
+            chain.check(&submission()).await,
+            Verdict::Spam("d: bad".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_chain_passes_everything() {
+        let config = SpamConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let chain = SpamFilterChain::from_config(&config).unwrap();
+
+        let mut submission = submission();
+        submission.honeypot = Some("http://spam.example");
+        assert_eq!(chain.check(&submission).await, Verdict::Pass);
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use sqlx::PgPool;
+
+use crate::db::{Database, ReadPool};
+use crate::images::ImageQueue;
+use crate::repositories::{
+    PgRoleRepository, PgSessionRepository, PgUserRepository, RoleRepository, SessionRepository,
+    UserRepository,
+};
+use crate::routes::sitemap::SitemapCache;
+use crate::spam::SpamFilterChain;
+use crate::storage::Storage;
+use crate::utils::{config::AppConfig, jwt::JwtService};
+
+/// Shared application state handed to every route
+///
+/// Handlers and extractors should not depend on `AppState` directly unless
+/// they need several parts of it; each field is exposed as a sub-state via
+/// `FromRef`, so `State<PgPool>`, `State<JwtService>` and
+/// `State<Arc<AppConfig>>` can all be extracted on their own. `State<PgPool>`
+/// is the primary database; `State<ReadPool>` may be a read replica.
+#[derive(Clone)]
+pub struct AppState {
+    pub db: Database,
+    pub users: Arc<dyn UserRepository>,
+    pub sessions: Arc<dyn SessionRepository>,
+    pub roles: Arc<dyn RoleRepository>,
+    pub jwt: JwtService,
+    pub spam: Arc<SpamFilterChain>,
+    pub storage: Arc<dyn Storage>,
+    pub images: ImageQueue,
+    pub sitemap: Arc<SitemapCache>,
+    pub config: Arc<AppConfig>,
+}
+
+impl AppState {
+    pub fn new(
+        db: Database,
+        jwt: JwtService,
+        spam: SpamFilterChain,
+        storage: Arc<dyn Storage>,
+        images: ImageQueue,
+        config: AppConfig,
+    ) -> Self {
+        Self {
+            users: Arc::new(PgUserRepository::new(db.clone())),
+            sessions: Arc::new(PgSessionRepository::new(db.clone())),
+            roles: Arc::new(PgRoleRepository::new(db.clone())),
+            db,
+            jwt,
+            spam: Arc::new(spam),
+            storage,
+            images,
+            sitemap: Arc::default(),
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.primary().clone()
+    }
+}
+
+impl FromRef<AppState> for ReadPool {
+    fn from_ref(state: &AppState) -> Self {
+        ReadPool(state.db.read().clone())
+    }
+}
+
+impl FromRef<AppState> for Database {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn UserRepository> {
+    fn from_ref(state: &AppState) -> Self {
+        state.users.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn SessionRepository> {
+    fn from_ref(state: &AppState) -> Self {
+        state.sessions.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn RoleRepository> {
+    fn from_ref(state: &AppState) -> Self {
+        state.roles.clone()
+    }
+}
+
+impl FromRef<AppState> for JwtService {
+    fn from_ref(state: &AppState) -> Self {
+        state.jwt.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<AppConfig> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<SpamFilterChain> {
+    fn from_ref(state: &AppState) -> Self {
+        state.spam.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn Storage> {
+    fn from_ref(state: &AppState) -> Self {
+        state.storage.clone()
+    }
+}
+
+impl FromRef<AppState> for ImageQueue {
+    fn from_ref(state: &AppState) -> Self {
+        state.images.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<SitemapCache> {
+    fn from_ref(state: &AppState) -> Self {
+        state.sitemap.clone()
+    }
+}
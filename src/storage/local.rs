@@ -0,0 +1,113 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bytes::Bytes;
+use uuid::Uuid;
+
+use super::{Storage, check_key, join_url};
+
+/// Stores objects as files below a root directory
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl AsRef<Path>, base_url: &str) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            base_url: base_url.to_string(),
+        }
+    }
+
+    fn path(&self, key: &str) -> anyhow::Result<PathBuf> {
+        check_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, data: Bytes, _content_type: &str) -> anyhow::Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+
+        // 先写临时文件再重命名，避免读到写了一半的文件
+        let tmp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+        tokio::fs::write(&tmp, &data)
+            .await
+            .with_context(|| format!("Failed to write {:?}", tmp))?;
+        if let Err(e) = tokio::fs::rename(&tmp, &path).await {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e).with_context(|| format!("Failed to move file into {:?}", path));
+        }
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Bytes> {
+        let path = self.path(key)?;
+        let data = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read {:?}", path))?;
+
+        Ok(Bytes::from(data))
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        let path = self.path(key)?;
+        tokio::fs::try_exists(&path)
+            .await
+            .with_context(|| format!("Failed to check {:?}", path))
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        let path = self.path(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("Failed to delete {:?}", path)),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        join_url(&self.base_url, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_storage_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path(), "/media");
+        let key = "media/ab/abcdef.png";
+
+        assert!(!storage.exists(key).await.unwrap());
+        storage
+            .put(key, Bytes::from_static(b"png"), "image/png")
+            .await
+            .unwrap();
+        assert!(storage.exists(key).await.unwrap());
+        assert_eq!(std::fs::read(dir.path().join(key)).unwrap(), b"png");
+        assert_eq!(storage.get(key).await.unwrap(), "png");
+        assert_eq!(storage.url(key), "/media/media/ab/abcdef.png");
+
+        storage.delete(key).await.unwrap();
+        assert!(!storage.exists(key).await.unwrap());
+        // 重复删除不报错
+        storage.delete(key).await.unwrap();
+
+        assert!(storage.put("../escape", Bytes::new(), "").await.is_err());
+    }
+}
@@ -0,0 +1,91 @@
+mod local;
+mod s3;
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::utils::config::{StorageBackend, StorageConfig};
+
+/// Blob storage for uploaded files
+///
+/// Keys are relative, `/`-separated paths such as `ab/abcd….png`.
+/// Backends never interpret them beyond that.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Stores an object, replacing any existing object under the same key
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> anyhow::Result<()>;
+
+    async fn get(&self, key: &str) -> anyhow::Result<Bytes>;
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool>;
+
+    /// Removes an object; deleting a missing key is not an error
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+
+    /// Public URL the object can be downloaded from
+    fn url(&self, key: &str) -> String;
+}
+
+/// Creates the backend selected in the configuration
+pub fn from_config(config: &StorageConfig) -> anyhow::Result<Arc<dyn Storage>> {
+    let storage: Arc<dyn Storage> = match config.backend {
+        StorageBackend::Local => Arc::new(LocalStorage::new(
+            &config.local.root,
+            &config.local.base_url,
+        )),
+        StorageBackend::S3 => Arc::new(S3Storage::new(&config.s3)?),
+    };
+
+    Ok(storage)
+}
+
+/// Joins a base URL and a key with exactly one slash in between
+fn join_url(base_url: &str, key: &str) -> String {
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        key.trim_start_matches('/')
+    )
+}
+
+/// Rejects keys that could escape the storage root
+fn check_key(key: &str) -> anyhow::Result<()> {
+    if key.is_empty()
+        || key.starts_with('/')
+        || key
+            .split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..")
+    {
+        anyhow::bail!("Invalid storage key: {:?}", key);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_key() {
+        assert!(check_key("media/ab/abcdef.png").is_ok());
+        assert!(check_key("").is_err());
+        assert!(check_key("/etc/passwd").is_err());
+        assert!(check_key("media/../secret").is_err());
+        assert!(check_key("media//x").is_err());
+    }
+
+    #[test]
+    fn test_join_url() {
+        assert_eq!(join_url("/media/", "a/b.png"), "/media/a/b.png");
+        assert_eq!(
+            join_url("https://cdn.example.com", "a.png"),
+            "https://cdn.example.com/a.png"
+        );
+    }
+}
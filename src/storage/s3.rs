@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bytes::Bytes;
+use object_store::{
+    Attribute, Attributes, ObjectStore, PutOptions, PutPayload, aws::AmazonS3Builder, path::Path,
+};
+
+use super::{Storage, check_key, join_url};
+use crate::utils::config::S3StorageConfig;
+
+/// Stores objects in an S3-compatible bucket (AWS S3, MinIO, R2, ...)
+///
+/// The bucket is accessed through `object_store`, so tests can swap in any
+/// other [`ObjectStore`] with [`S3Storage::with_store`].
+pub struct S3Storage {
+    store: Arc<dyn ObjectStore>,
+    base_url: String,
+}
+
+impl S3Storage {
+    pub fn new(config: &S3StorageConfig) -> anyhow::Result<Self> {
+        let mut builder = AmazonS3Builder::new()
+            .with_bucket_name(&config.bucket)
+            .with_region(&config.region)
+            .with_access_key_id(&config.access_key_id)
+            .with_secret_access_key(&config.secret_access_key)
+            .with_allow_http(config.allow_http);
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder.with_endpoint(endpoint);
+        }
+        let store = builder.build().context("Failed to configure S3 storage")?;
+
+        let base_url = match (&config.base_url, &config.endpoint) {
+            (Some(base_url), _) => base_url.clone(),
+            // 自建服务使用 path-style 地址
+            (None, Some(endpoint)) => join_url(endpoint, &config.bucket),
+            (None, None) => format!(
+                "https://{}.s3.{}.amazonaws.com",
+                config.bucket, config.region
+            ),
+        };
+
+        Ok(Self::with_store(Arc::new(store), &base_url))
+    }
+
+    pub fn with_store(store: Arc<dyn ObjectStore>, base_url: &str) -> Self {
+        Self {
+            store,
+            base_url: base_url.to_string(),
+        }
+    }
+
+    fn path(key: &str) -> anyhow::Result<Path> {
+        check_key(key)?;
+        Path::parse(key).with_context(|| format!("Invalid storage key: {:?}", key))
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> anyhow::Result<()> {
+        let path = Self::path(key)?;
+        let mut attributes = Attributes::new();
+        attributes.insert(Attribute::ContentType, content_type.to_string().into());
+
+        self.store
+            .put_opts(
+                &path,
+                PutPayload::from(data),
+                PutOptions {
+                    attributes,
+                    ..Default::default()
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to upload {}", key))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Bytes> {
+        let path = Self::path(key)?;
+        let result = self
+            .store
+            .get(&path)
+            .await
+            .with_context(|| format!("Failed to download {}", key))?;
+
+        result
+            .bytes()
+            .await
+            .with_context(|| format!("Failed to download {}", key))
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        let path = Self::path(key)?;
+        match self.store.head(&path).await {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("Failed to check {}", key)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        let path = Self::path(key)?;
+        match self.store.delete(&path).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("Failed to delete {}", key)),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        join_url(&self.base_url, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    async fn roundtrip(storage: &S3Storage) {
+        let key = format!("media/test/{}.png", uuid::Uuid::new_v4());
+
+        assert!(!storage.exists(&key).await.unwrap());
+        storage
+            .put(&key, Bytes::from_static(b"png"), "image/png")
+            .await
+            .unwrap();
+        assert!(storage.exists(&key).await.unwrap());
+        assert_eq!(storage.get(&key).await.unwrap(), "png");
+
+        storage.delete(&key).await.unwrap();
+        assert!(!storage.exists(&key).await.unwrap());
+        storage.delete(&key).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_s3_storage_with_in_memory_store() {
+        let storage = S3Storage::with_store(Arc::new(InMemory::new()), "https://cdn.example.com");
+        roundtrip(&storage).await;
+        assert_eq!(storage.url("a/b.png"), "https://cdn.example.com/a/b.png");
+    }
+
+    #[test]
+    fn test_s3_base_url() {
+        let mut config = S3StorageConfig {
+            bucket: "blog".to_string(),
+            endpoint: Some("http://localhost:9000".to_string()),
+            allow_http: true,
+            ..Default::default()
+        };
+        let storage = S3Storage::new(&config).unwrap();
+        assert_eq!(storage.url("a.png"), "http://localhost:9000/blog/a.png");
+
+        config.endpoint = None;
+        let storage = S3Storage::new(&config).unwrap();
+        assert_eq!(
+            storage.url("a.png"),
+            "https://blog.s3.us-east-1.amazonaws.com/a.png"
+        );
+    }
+
+    /// Runs against a real S3-compatible server such as MinIO:
+    ///
+    /// `S3_TEST_ENDPOINT=http://localhost:9000 S3_TEST_BUCKET=blog
+    /// S3_TEST_ACCESS_KEY=minioadmin S3_TEST_SECRET_KEY=minioadmin
+    /// cargo test -- --ignored test_s3_storage_against_server`
+    #[tokio::test]
+    #[ignore = "needs an S3-compatible server"]
+    async fn test_s3_storage_against_server() {
+        let env =
+            |name: &str| std::env::var(name).unwrap_or_else(|_| panic!("{} is not set", name));
+        let config = S3StorageConfig {
+            bucket: env("S3_TEST_BUCKET"),
+            endpoint: Some(env("S3_TEST_ENDPOINT")),
+            access_key_id: env("S3_TEST_ACCESS_KEY"),
+            secret_access_key: env("S3_TEST_SECRET_KEY"),
+            allow_http: true,
+            ..Default::default()
+        };
+
+        roundtrip(&S3Storage::new(&config).unwrap()).await;
+    }
+}
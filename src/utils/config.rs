@@ -5,6 +5,18 @@ use serde::Deserialize;
 use std::path::PathBuf;
 use tracing::debug;
 
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    Allow,
+    #[default]
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct PostgresConfig {
     pub host: String,
@@ -12,18 +24,487 @@ pub struct PostgresConfig {
     pub user: String,
     pub password: String,
     pub database: String,
+    // 以下均为可选项，未设置时使用 sqlx 的默认值
+    pub max_connections: Option<u32>,
+    pub min_connections: Option<u32>,
+    // 等待空闲连接的最长时间，格式同 jwt.expires_in
+    pub acquire_timeout: Option<String>,
+    // 空闲连接超过该时间后关闭
+    pub idle_timeout: Option<String>,
+    // 单条语句的执行时限，通过 statement_timeout 会话参数设置
+    pub statement_timeout: Option<String>,
+    #[serde(default)]
+    pub ssl_mode: SslMode,
+    pub ssl_root_cert: Option<PathBuf>,
+    pub ssl_client_cert: Option<PathBuf>,
+    pub ssl_client_key: Option<PathBuf>,
+    // 显示在 pg_stat_activity 中，默认为 blog-axum
+    pub application_name: Option<String>,
+    // 启动时数据库不可用的重试次数，默认 10 次，间隔逐次翻倍
+    pub connect_retries: Option<u32>,
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+/// Signature algorithms accepted for `[[jwt.keys]]`
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum JwtAlgorithm {
+    #[serde(rename = "RS256")]
+    Rs256,
+    #[serde(rename = "ES256")]
+    Es256,
+    #[serde(rename = "EdDSA")]
+    EdDsa,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct JwtKeyConfig {
+    // 写入令牌头部的 kid，验证时据此选择密钥
+    pub kid: String,
+    pub algorithm: JwtAlgorithm,
+    // PEM 格式的私钥文件（PKCS#8，RS256 也可使用 PKCS#1），公钥由私钥推导
+    pub private_key: PathBuf,
+}
+
+#[derive(Debug, Deserialize, Clone)]
 pub struct JwtConfig {
+    // HS256 共享密钥，仅在未配置 keys 时使用
+    #[serde(default)]
     pub secret: String,
+    // 访问令牌的有效期，应尽量短，过期后用刷新令牌换取
     pub expires_in: String,
+    // 刷新令牌的有效期，每次刷新都会重新计算
+    #[serde(default = "default_refresh_expires_in")]
+    pub refresh_expires_in: String,
+    // 非对称签名密钥，全部用于验证并通过 /.well-known/jwks.json 公开；
+    // 轮换时先加入新密钥，再切换 signing_key，旧密钥等到其签发的令牌过期后再移除
+    #[serde(default)]
+    pub keys: Vec<JwtKeyConfig>,
+    // 签发新令牌使用的 kid，默认为 keys 中的第一个
+    pub signing_key: Option<String>,
+    // 写入 iss 和 aud 声明，验证时两者都必须一致
+    #[serde(default = "default_jwt_issuer")]
+    pub issuer: String,
+    #[serde(default = "default_jwt_issuer")]
+    pub audience: String,
+    // 校验 exp 和 nbf 时容忍的时钟偏差
+    #[serde(default = "default_jwt_leeway")]
+    pub leeway: String,
+}
+
+fn default_jwt_issuer() -> String {
+    "blog-axum".to_string()
+}
+
+fn default_jwt_leeway() -> String {
+    "60s".to_string()
+}
+
+fn default_refresh_expires_in() -> String {
+    "30d".to_string()
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            secret: String::new(),
+            expires_in: "15m".to_string(),
+            refresh_expires_in: default_refresh_expires_in(),
+            keys: Vec::new(),
+            signing_key: None,
+            issuer: default_jwt_issuer(),
+            audience: default_jwt_issuer(),
+            leeway: default_jwt_leeway(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct JobsConfig {
+    // 定时发布任务的执行间隔，格式同 jwt.expires_in
+    pub publish_interval: String,
+    // 清理过期刷新令牌的间隔
+    pub purge_tokens_interval: String,
+    // 从数据库同步令牌吊销记录的间隔，多实例部署时决定吊销在其他实例生效的延迟
+    pub revocation_sync_interval: String,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            publish_interval: "1m".to_string(),
+            purge_tokens_interval: "1h".to_string(),
+            revocation_sync_interval: "30s".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReplicaConfig {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ReplicationConfig {
+    // 只读副本，用户名、密码、数据库及连接池参数与主库相同
+    pub replicas: Vec<ReplicaConfig>,
+    // 复制延迟超过该值的副本不再接收读请求，格式同 jwt.expires_in
+    pub max_lag: String,
+    // 副本健康检查间隔
+    pub health_check_interval: String,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            replicas: Vec::new(),
+            max_lag: "30s".to_string(),
+            health_check_interval: "10s".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct MigrationsConfig {
+    // 启动时自动执行未应用的迁移；关闭时需手动运行 `blog-axum migrate up`
+    pub run_on_startup: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ServerConfig {
+    // 部署在反向代理之后时开启，从 X-Forwarded-For 读取客户端 IP
+    pub trust_proxy_headers: bool,
+    // 服务前面的可信代理层数；每层代理在 X-Forwarded-For 末尾追加一项，
+    // 从右数第这么多项才是客户端地址，更靠左的项可由客户端伪造
+    pub trusted_proxy_hops: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            trust_proxy_headers: false,
+            trusted_proxy_hops: 1,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SiteConfig {
+    pub title: String,
+    pub description: String,
+    // 站点对外访问的根地址，用于生成订阅源等处的绝对链接
+    pub base_url: String,
+    pub language: String,
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        Self {
+            title: "Blog".to_string(),
+            description: String::new(),
+            base_url: "http://localhost:8000".to_string(),
+            language: "zh-CN".to_string(),
+        }
+    }
+}
+
+impl SiteConfig {
+    /// Absolute URL for a path on the site
+    pub fn url(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SearchConfig {
+    // PostgreSQL 全文检索配置名（如 english、simple），修改后启动时重建索引
+    pub text_search_config: String,
+    // 查询包含中文等不以空格分词的文字，或全文检索没有结果时，改用 pg_trgm 子串匹配
+    pub trigram_fallback: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            text_search_config: "english".to_string(),
+            trigram_fallback: true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct RobotsConfig {
+    // 生成 robots.txt 中 User-agent: * 下的规则
+    pub allow: Vec<String>,
+    pub disallow: Vec<String>,
+    pub crawl_delay: Option<u32>,
+}
+
+impl Default for RobotsConfig {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            disallow: vec!["/api/".to_string()],
+            crawl_delay: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedContent {
+    // 输出完整的文章 HTML
+    #[default]
+    Full,
+    // 只输出摘要
+    Excerpt,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct FeedConfig {
+    pub content: FeedContent,
+    // 每个订阅源包含的最新文章数
+    pub limit: i64,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        Self {
+            content: FeedContent::Full,
+            limit: 20,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SpamConfig {
+    pub enabled: bool,
+    // 评论中允许的最多链接数，超出后进入人工审核
+    pub max_links: usize,
+    // 命中任意一个词（不区分大小写）即判为垃圾评论
+    pub blocked_words: Vec<String>,
+    // 是否检查表单中的蜜罐字段
+    pub honeypot: bool,
+    // 同一 IP 在 rate_window 内最多提交的评论数
+    pub rate_limit: usize,
+    pub rate_window: String,
+}
+
+impl Default for SpamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_links: 2,
+            blocked_words: Vec::new(),
+            honeypot: true,
+            rate_limit: 5,
+            rate_window: "10m".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Local,
+    S3,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LocalStorageConfig {
+    // 文件保存目录
+    pub root: PathBuf,
+    // 对外访问的 URL 前缀；以 / 开头时由本服务直接提供文件
+    pub base_url: String,
+}
+
+impl Default for LocalStorageConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("uploads"),
+            base_url: "/media".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct S3StorageConfig {
+    pub bucket: String,
+    pub region: String,
+    // 自建的 S3 兼容服务（如 MinIO）地址，留空则使用 AWS
+    pub endpoint: Option<String>,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub allow_http: bool,
+    // 对外访问的 URL 前缀，留空则根据 endpoint 和 bucket 推导
+    pub base_url: Option<String>,
+}
+
+impl Default for S3StorageConfig {
+    fn default() -> Self {
+        Self {
+            bucket: String::new(),
+            region: "us-east-1".to_string(),
+            endpoint: None,
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            allow_http: false,
+            base_url: None,
+        }
+    }
+}
+
+// 启动时会打印整份配置，不能泄露密钥
+impl std::fmt::Debug for S3StorageConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("S3StorageConfig")
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("endpoint", &self.endpoint)
+            .field("access_key_id", &self.access_key_id)
+            .field("allow_http", &self.allow_http)
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    // 单个文件的最大字节数
+    pub max_upload_size: usize,
+    // 按文件内容识别出的 MIME 类型必须在此列表中
+    pub allowed_mime_types: Vec<String>,
+    pub local: LocalStorageConfig,
+    pub s3: S3StorageConfig,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::Local,
+            max_upload_size: 10 * 1024 * 1024,
+            allowed_mime_types: ["image/jpeg", "image/png", "image/gif", "image/webp"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            local: LocalStorageConfig::default(),
+            s3: S3StorageConfig::default(),
+        }
+    }
+}
+
+/// Output format of a generated image variant
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    Webp,
+    Avif,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ImagesConfig {
+    // 关闭后上传的图片原样保存，不做任何处理
+    pub enabled: bool,
+    // 生成的变体宽度（像素），不会超过原图宽度
+    pub widths: Vec<u32>,
+    // 每个宽度生成的格式
+    pub formats: Vec<ImageFormat>,
+    // JPEG 和 AVIF 的编码质量（1-100），WebP 总是无损编码
+    pub quality: u8,
+    // 同时处理图片的工作线程数，上传时去除元数据同样受此限制
+    pub workers: usize,
+    // 等待处理的队列长度，队列满时由定时任务稍后补处理
+    pub queue_size: usize,
+    // 扫描遗漏或中断的待处理图片的间隔
+    pub sweep_interval: String,
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            widths: vec![320, 768, 1280],
+            formats: vec![ImageFormat::Webp, ImageFormat::Avif],
+            quality: 80,
+            workers: 2,
+            queue_size: 64,
+            sweep_interval: "1m".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct AdminConfig {
+    // 超级用户 ID，无需角色即拥有全部权限，用于分配最初的角色
+    pub user_ids: Vec<i32>,
+    // 新注册用户获得的角色；注册接口是公开的，默认只给 reader，写作权限由管理员分配
+    pub default_role: String,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            user_ids: Vec::new(),
+            default_role: "reader".to_string(),
+        }
+    }
+}
+
+impl AdminConfig {
+    pub fn is_admin(&self, user_id: i32) -> bool {
+        self.user_ids.contains(&user_id)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct AppConfig {
     pub postgresql: PostgresConfig,
     pub jwt: JwtConfig,
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+    #[serde(default)]
+    pub migrations: MigrationsConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub site: SiteConfig,
+    #[serde(default)]
+    pub feed: FeedConfig,
+    #[serde(default)]
+    pub robots: RobotsConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub spam: SpamConfig,
+    #[serde(default)]
+    pub jobs: JobsConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub images: ImagesConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
 }
 
 impl AppConfig {
@@ -1,21 +1,76 @@
-use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters, Jwk,
+        JwkSet, KeyAlgorithm, OctetKeyPairParameters, PublicKeyUse, RSAKeyParameters,
+    },
+};
+use ring::{
+    rand::SystemRandom,
+    rsa::PublicKeyComponents,
+    signature::{
+        ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair,
+    },
+};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, RwLock},
+};
+use uuid::Uuid;
 
-use crate::utils::config::AppConfig;
+use crate::utils::config::{AppConfig, JwtAlgorithm, JwtKeyConfig};
+use crate::utils::parse_duration;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String,
+    /// Service that issued the token, `jwt.issuer`
+    pub iss: String,
+    /// Service the token is meant for, `jwt.audience`
+    pub aud: String,
+    pub iat: usize,
+    pub nbf: usize,
     pub exp: usize,
+    /// Unique id of this token, checked against the revocation list
+    pub jti: Uuid,
+    /// Session the token was issued for, see `GET /users/sessions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+    /// Roles of the user when the token was issued
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl Claims {
+    /// Parses the numeric user id carried in `sub`
+    pub fn user_id(&self) -> Result<i32, JwtError> {
+        self.sub.parse().map_err(|_| JwtError::InvalidToken)
+    }
 }
 
 #[derive(Debug)]
 pub enum JwtError {
     InvalidToken,
     ExpiredToken,
+    RevokedToken,
+    /// Signed by us but issued by another service, see `jwt.issuer`
+    InvalidIssuer,
+    /// Meant for another service, see `jwt.audience`
+    InvalidAudience,
+    /// Used before its `nbf` time
+    ImmatureToken,
     JsonWebTokenError(jsonwebtoken::errors::Error),
     ConfigError,
+    /// A configured signing key could not be loaded
+    InvalidKey {
+        kid: String,
+        reason: String,
+    },
 }
 
 impl fmt::Display for JwtError {
@@ -23,8 +78,15 @@ impl fmt::Display for JwtError {
         match self {
             JwtError::InvalidToken => write!(f, "Invalid token"),
             JwtError::ExpiredToken => write!(f, "Expired token"),
+            JwtError::RevokedToken => write!(f, "Revoked token"),
+            JwtError::InvalidIssuer => write!(f, "Invalid token issuer"),
+            JwtError::InvalidAudience => write!(f, "Invalid token audience"),
+            JwtError::ImmatureToken => write!(f, "Token not yet valid"),
             JwtError::JsonWebTokenError(e) => write!(f, "JWT error: {}", e),
             JwtError::ConfigError => write!(f, "JWT config error"),
+            JwtError::InvalidKey { kid, reason } => {
+                write!(f, "Invalid JWT key {}: {}", kid, reason)
+            }
         }
     }
 }
@@ -33,75 +95,399 @@ impl std::error::Error for JwtError {}
 
 impl From<jsonwebtoken::errors::Error> for JwtError {
     fn from(err: jsonwebtoken::errors::Error) -> Self {
+        use jsonwebtoken::errors::ErrorKind;
+
         match err.kind() {
-            jsonwebtoken::errors::ErrorKind::ExpiredSignature => JwtError::ExpiredToken,
+            ErrorKind::ExpiredSignature => JwtError::ExpiredToken,
+            ErrorKind::InvalidIssuer => JwtError::InvalidIssuer,
+            ErrorKind::InvalidAudience => JwtError::InvalidAudience,
+            ErrorKind::ImmatureSignature => JwtError::ImmatureToken,
+            // 客户端提交的 token 无法解析或校验失败
+            ErrorKind::InvalidToken
+            | ErrorKind::InvalidSignature
+            | ErrorKind::InvalidAlgorithm
+            | ErrorKind::MissingRequiredClaim(_)
+            | ErrorKind::Base64(_)
+            | ErrorKind::Json(_)
+            | ErrorKind::Utf8(_) => JwtError::InvalidToken,
             _ => JwtError::JsonWebTokenError(err),
         }
     }
 }
 
-#[derive(Clone)]
-pub struct JwtService {
+/// Ids of revoked tokens and sessions, with the time until which they
+/// must be rejected
+///
+/// Kept in memory so validating a token needs no database query. Revoking
+/// updates the list of this process right away; other instances pick the
+/// change up from the `revoked_tokens` table on their next sync.
+#[derive(Clone, Default)]
+pub struct RevocationList {
+    ids: Arc<RwLock<HashMap<Uuid, DateTime<Utc>>>>,
+}
+
+impl RevocationList {
+    pub fn revoke(&self, id: Uuid, until: DateTime<Utc>) {
+        self.ids.write().unwrap().insert(id, until);
+    }
+
+    pub fn is_revoked(&self, id: &Uuid, now: DateTime<Utc>) -> bool {
+        self.ids
+            .read()
+            .unwrap()
+            .get(id)
+            .is_some_and(|until| *until > now)
+    }
+
+    /// Adds the entries loaded from the database and forgets expired ones
+    ///
+    /// Entries missing from `entries` are kept until they expire, so a
+    /// revocation made in this process while the rows were being loaded is
+    /// not lost.
+    pub fn merge(
+        &self,
+        entries: impl IntoIterator<Item = (Uuid, DateTime<Utc>)>,
+        now: DateTime<Utc>,
+    ) {
+        let mut ids = self.ids.write().unwrap();
+        ids.retain(|_, until| *until > now);
+        for (id, until) in entries {
+            if until > now {
+                let current = ids.entry(id).or_insert(until);
+                *current = (*current).max(until);
+            }
+        }
+    }
+}
+
+/// Key that signs new tokens; `kid` is `None` for the shared secret
+struct SigningKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
     encoding_key: EncodingKey,
+}
+
+/// Key that tokens carrying its `kid` are verified with
+struct VerifyingKey {
+    kid: Option<String>,
     decoding_key: DecodingKey,
     validation: Validation,
+}
+
+/// Keys loaded from the configuration
+struct Keys {
+    signing: SigningKey,
+    verifying: Vec<VerifyingKey>,
+    /// Public halves of the asymmetric keys; empty with the shared secret
+    jwks: JwkSet,
+}
+
+#[derive(Clone)]
+pub struct JwtService {
+    keys: Arc<Keys>,
+    issuer: String,
+    audience: String,
     expires_in: u64,
+    refresh_expires_in: u64,
+    revocations: RevocationList,
+}
+
+/// A newly issued opaque refresh token
+///
+/// Only `hash` is stored; `token` is handed to the client once.
+pub struct RefreshToken {
+    pub token: String,
+    pub hash: String,
+    pub expires_at: DateTime<Utc>,
 }
 
 impl JwtService {
+    /// Signs with the configured key pairs, or with the HS256 secret when
+    /// `jwt.keys` is empty
     pub fn from_config(config: &AppConfig) -> Result<Self, JwtError> {
-        let secret = &config.jwt.secret;
-        if secret.is_empty() {
-            return Err(JwtError::ConfigError);
-        }
+        let jwt = &config.jwt;
+        let mut validation = Validation::default();
+        validation.set_issuer(&[&jwt.issuer]);
+        validation.set_audience(&[&jwt.audience]);
+        validation.set_required_spec_claims(&["sub", "iss", "aud", "exp", "nbf"]);
+        validation.validate_nbf = true;
+        validation.leeway = parse_expires_in(&jwt.leeway)?;
+
+        let keys = if jwt.keys.is_empty() {
+            secret_keys(&jwt.secret, &validation)?
+        } else {
+            asymmetric_keys(&jwt.keys, jwt.signing_key.as_deref(), &validation)?
+        };
 
-        let expires_in = parse_expires_in(&config.jwt.expires_in)?;
+        let expires_in = parse_expires_in(&jwt.expires_in)?;
+        let refresh_expires_in = parse_expires_in(&jwt.refresh_expires_in)?;
 
         Ok(Self {
-            encoding_key: EncodingKey::from_secret(secret.as_ref()),
-            decoding_key: DecodingKey::from_secret(secret.as_ref()),
-            validation: Validation::new(Algorithm::HS256),
+            keys: Arc::new(keys),
+            issuer: jwt.issuer.clone(),
+            audience: jwt.audience.clone(),
             expires_in,
+            refresh_expires_in,
+            revocations: RevocationList::default(),
         })
     }
 
-    pub fn generate_token(&self, user_id: &str) -> Result<String, JwtError> {
+    /// Lifetime of access tokens in seconds
+    pub fn expires_in(&self) -> u64 {
+        self.expires_in
+    }
+
+    /// When every access token issued until `now` will have expired
+    pub fn access_tokens_expire_by(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now + chrono::Duration::seconds(self.expires_in as i64)
+    }
+
+    pub fn revocations(&self) -> &RevocationList {
+        &self.revocations
+    }
+
+    /// Public keys other services verify our tokens with
+    pub fn jwks(&self) -> &JwkSet {
+        &self.keys.jwks
+    }
+
+    pub fn generate_token(
+        &self,
+        user_id: &str,
+        session_id: Option<Uuid>,
+        roles: Vec<String>,
+    ) -> Result<String, JwtError> {
         let now = chrono::Utc::now().timestamp() as usize;
         let claims = Claims {
             sub: user_id.to_string(),
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            iat: now,
+            nbf: now,
             exp: now + self.expires_in as usize,
+            jti: Uuid::new_v4(),
+            sid: session_id,
+            roles,
         };
 
-        encode(&Header::default(), &claims, &self.encoding_key).map_err(JwtError::from)
+        let signing = &self.keys.signing;
+        let mut header = Header::new(signing.algorithm);
+        header.kid = signing.kid.clone();
+
+        encode(&header, &claims, &signing.encoding_key).map_err(JwtError::from)
     }
 
     pub fn validate_token(&self, token: &str) -> Result<Claims, JwtError> {
-        let token_data = decode::<Claims>(token, &self.decoding_key, &self.validation)
-            .map_err(JwtError::from)?;
+        // 按头部的 kid 选择密钥，算法必须与该密钥一致
+        let header = decode_header(token)?;
+        let key = self
+            .keys
+            .verifying
+            .iter()
+            .find(|key| key.kid == header.kid)
+            .ok_or(JwtError::InvalidToken)?;
+
+        let token_data =
+            decode::<Claims>(token, &key.decoding_key, &key.validation).map_err(JwtError::from)?;
+        let claims = token_data.claims;
+
+        let now = Utc::now();
+        if self.revocations.is_revoked(&claims.jti, now)
+            || claims
+                .sid
+                .is_some_and(|sid| self.revocations.is_revoked(&sid, now))
+        {
+            return Err(JwtError::RevokedToken);
+        }
+
+        Ok(claims)
+    }
+
+    pub fn generate_refresh_token(&self, now: DateTime<Utc>) -> RefreshToken {
+        let token = hex::encode(rand::random::<[u8; 32]>());
 
-        Ok(token_data.claims)
+        RefreshToken {
+            hash: hash_refresh_token(&token),
+            token,
+            expires_at: now + chrono::Duration::seconds(self.refresh_expires_in as i64),
+        }
     }
 }
 
-fn parse_expires_in(expires_in: &str) -> Result<u64, JwtError> {
-    let (num, unit) = expires_in.trim().split_at(
-        expires_in
-            .find(|c: char| !c.is_ascii_digit())
-            .ok_or(JwtError::ConfigError)?,
-    );
-
-    let num = num.parse::<u64>().map_err(|_| JwtError::ConfigError)?;
-
-    let seconds = match unit.trim().to_lowercase().as_str() {
-        "s" => num,
-        "m" => num * 60,
-        "h" => num * 3600,
-        "d" => num * 86400,
-        "w" => num * 604800,
-        _ => return Err(JwtError::ConfigError),
+/// The form a refresh token is stored and looked up in
+pub fn hash_refresh_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// `template` with only `algorithm` allowed
+fn validation_for(algorithm: Algorithm, template: &Validation) -> Validation {
+    let mut validation = template.clone();
+    validation.algorithms = vec![algorithm];
+    validation
+}
+
+fn secret_keys(secret: &str, validation: &Validation) -> Result<Keys, JwtError> {
+    if secret.is_empty() {
+        return Err(JwtError::ConfigError);
+    }
+
+    Ok(Keys {
+        signing: SigningKey {
+            kid: None,
+            algorithm: Algorithm::HS256,
+            encoding_key: EncodingKey::from_secret(secret.as_ref()),
+        },
+        verifying: vec![VerifyingKey {
+            kid: None,
+            decoding_key: DecodingKey::from_secret(secret.as_ref()),
+            validation: validation_for(Algorithm::HS256, validation),
+        }],
+        jwks: JwkSet { keys: Vec::new() },
+    })
+}
+
+fn asymmetric_keys(
+    configs: &[JwtKeyConfig],
+    signing_kid: Option<&str>,
+    validation: &Validation,
+) -> Result<Keys, JwtError> {
+    let mut kids = HashSet::new();
+    if let Some(duplicate) = configs.iter().find(|key| !kids.insert(key.kid.as_str())) {
+        return Err(JwtError::InvalidKey {
+            kid: duplicate.kid.clone(),
+            reason: "kid is used more than once".to_string(),
+        });
+    }
+
+    let signing_kid = signing_kid.unwrap_or(&configs[0].kid);
+    let mut signing = None;
+    let mut verifying = Vec::with_capacity(configs.len());
+    let mut jwks = Vec::with_capacity(configs.len());
+
+    for config in configs {
+        let (encoding_key, jwk) = load_key(config)?;
+        if config.kid == signing_kid {
+            signing = Some(SigningKey {
+                kid: Some(config.kid.clone()),
+                algorithm: algorithm(config.algorithm),
+                encoding_key,
+            });
+        }
+        verifying.push(VerifyingKey {
+            kid: Some(config.kid.clone()),
+            decoding_key: DecodingKey::from_jwk(&jwk)?,
+            validation: validation_for(algorithm(config.algorithm), validation),
+        });
+        jwks.push(jwk);
+    }
+
+    let signing = signing.ok_or_else(|| JwtError::InvalidKey {
+        kid: signing_kid.to_string(),
+        reason: "jwt.signing_key is not in jwt.keys".to_string(),
+    })?;
+
+    Ok(Keys {
+        signing,
+        verifying,
+        jwks: JwkSet { keys: jwks },
+    })
+}
+
+fn algorithm(algorithm: JwtAlgorithm) -> Algorithm {
+    match algorithm {
+        JwtAlgorithm::Rs256 => Algorithm::RS256,
+        JwtAlgorithm::Es256 => Algorithm::ES256,
+        JwtAlgorithm::EdDsa => Algorithm::EdDSA,
+    }
+}
+
+/// Reads a private key and derives the JWK of its public half
+fn load_key(config: &JwtKeyConfig) -> Result<(EncodingKey, Jwk), JwtError> {
+    let invalid = |reason: String| JwtError::InvalidKey {
+        kid: config.kid.clone(),
+        reason,
     };
 
-    Ok(seconds)
+    let pem_bytes = std::fs::read(&config.private_key)
+        .map_err(|e| invalid(format!("{}: {}", config.private_key.display(), e)))?;
+    let pem = pem::parse(&pem_bytes).map_err(|e| invalid(e.to_string()))?;
+    let der = pem.contents();
+    let rejected = |e: ring::error::KeyRejected| invalid(e.to_string());
+
+    let (encoding_key, key_algorithm, parameters) = match config.algorithm {
+        JwtAlgorithm::Rs256 => {
+            let key_pair = if pem.tag() == "RSA PRIVATE KEY" {
+                RsaKeyPair::from_der(der)
+            } else {
+                RsaKeyPair::from_pkcs8(der)
+            }
+            .map_err(rejected)?;
+            let public = PublicKeyComponents::<Vec<u8>>::from(key_pair.public());
+
+            (
+                EncodingKey::from_rsa_pem(&pem_bytes).map_err(|e| invalid(e.to_string()))?,
+                KeyAlgorithm::RS256,
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: Default::default(),
+                    n: URL_SAFE_NO_PAD.encode(public.n),
+                    e: URL_SAFE_NO_PAD.encode(public.e),
+                }),
+            )
+        }
+        JwtAlgorithm::Es256 => {
+            let key_pair = EcdsaKeyPair::from_pkcs8(
+                &ECDSA_P256_SHA256_FIXED_SIGNING,
+                der,
+                &SystemRandom::new(),
+            )
+            .map_err(rejected)?;
+            // 未压缩格式：0x04 || x || y
+            let point = &key_pair.public_key().as_ref()[1..];
+            let (x, y) = point.split_at(point.len() / 2);
+
+            (
+                EncodingKey::from_ec_pem(&pem_bytes).map_err(|e| invalid(e.to_string()))?,
+                KeyAlgorithm::ES256,
+                AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                    key_type: Default::default(),
+                    curve: EllipticCurve::P256,
+                    x: URL_SAFE_NO_PAD.encode(x),
+                    y: URL_SAFE_NO_PAD.encode(y),
+                }),
+            )
+        }
+        JwtAlgorithm::EdDsa => {
+            let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(der).map_err(rejected)?;
+
+            (
+                EncodingKey::from_ed_pem(&pem_bytes).map_err(|e| invalid(e.to_string()))?,
+                KeyAlgorithm::EdDSA,
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: Default::default(),
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(key_pair.public_key()),
+                }),
+            )
+        }
+    };
+
+    let jwk = Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(config.kid.clone()),
+            ..Default::default()
+        },
+        algorithm: parameters,
+    };
+
+    Ok((encoding_key, jwk))
+}
+
+fn parse_expires_in(expires_in: &str) -> Result<u64, JwtError> {
+    parse_duration(expires_in)
+        .map(|d| d.as_secs())
+        .ok_or(JwtError::ConfigError)
 }
 
 #[cfg(test)]
@@ -112,11 +498,12 @@ mod tests {
     #[test]
     fn test_jwt_service_from_config() {
         let config = AppConfig {
-            postgresql: Default::default(),
             jwt: crate::utils::config::JwtConfig {
                 secret: "test-secret".to_string(),
                 expires_in: "7d".to_string(),
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         let jwt_service = JwtService::from_config(&config);
@@ -126,21 +513,117 @@ mod tests {
     #[test]
     fn test_generate_and_validate_token() {
         let config = AppConfig {
-            postgresql: Default::default(),
             jwt: crate::utils::config::JwtConfig {
                 secret: "test-secret".to_string(),
                 expires_in: "1h".to_string(),
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         let jwt_service = JwtService::from_config(&config).unwrap();
-        let token = jwt_service.generate_token("user123").unwrap();
+        let token = jwt_service
+            .generate_token("user123", None, Vec::new())
+            .unwrap();
         let claims = jwt_service.validate_token(&token).unwrap();
 
         assert_eq!(claims.sub, "user123");
         assert!(claims.exp > chrono::Utc::now().timestamp() as usize);
     }
 
+    #[test]
+    fn test_validate_token_rejects_revoked_tokens() {
+        let config = AppConfig {
+            jwt: crate::utils::config::JwtConfig {
+                secret: "test-secret".to_string(),
+                expires_in: "1h".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let jwt_service = JwtService::from_config(&config).unwrap();
+        let session_id = Uuid::new_v4();
+        let token = jwt_service
+            .generate_token("user123", None, Vec::new())
+            .unwrap();
+        let session_token = jwt_service
+            .generate_token("user123", Some(session_id), Vec::new())
+            .unwrap();
+        let claims = jwt_service.validate_token(&token).unwrap();
+        assert!(claims.sid.is_none());
+
+        let now = Utc::now();
+        jwt_service
+            .revocations()
+            .revoke(claims.jti, now + chrono::Duration::hours(1));
+        jwt_service
+            .revocations()
+            .revoke(session_id, now + chrono::Duration::hours(1));
+        assert!(matches!(
+            jwt_service.validate_token(&token),
+            Err(JwtError::RevokedToken)
+        ));
+        assert!(matches!(
+            jwt_service.validate_token(&session_token),
+            Err(JwtError::RevokedToken)
+        ));
+
+        // 过期的吊销记录不再生效
+        jwt_service
+            .revocations()
+            .revoke(claims.jti, now - chrono::Duration::seconds(1));
+        jwt_service
+            .revocations()
+            .revoke(session_id, now - chrono::Duration::seconds(1));
+        assert!(jwt_service.validate_token(&token).is_ok());
+        assert!(jwt_service.validate_token(&session_token).is_ok());
+    }
+
+    #[test]
+    fn test_revocation_list_merge() {
+        let revocations = RevocationList::default();
+        let now = Utc::now();
+        let hour = chrono::Duration::hours(1);
+        let (local, expired, synced) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        revocations.revoke(local, now + hour);
+        revocations.revoke(expired, now - hour);
+        revocations.revoke(synced, now + hour);
+
+        // 数据库里还没有本地刚吊销的记录，合并后仍然保留
+        revocations.merge([(synced, now + hour * 2)], now);
+        assert!(revocations.is_revoked(&local, now));
+        assert!(revocations.is_revoked(&synced, now + hour + hour / 2));
+        assert!(!revocations.ids.read().unwrap().contains_key(&expired));
+
+        revocations.merge([], now + hour * 3);
+        assert!(revocations.ids.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_generate_refresh_token() {
+        let config = AppConfig {
+            jwt: crate::utils::config::JwtConfig {
+                secret: "test-secret".to_string(),
+                refresh_expires_in: "30d".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let jwt_service = JwtService::from_config(&config).unwrap();
+        let now = chrono::Utc::now();
+        let first = jwt_service.generate_refresh_token(now);
+        let second = jwt_service.generate_refresh_token(now);
+
+        assert_eq!(first.token.len(), 64);
+        assert_ne!(first.token, second.token);
+        assert_eq!(first.hash, hash_refresh_token(&first.token));
+        assert_ne!(first.hash, first.token);
+        assert_eq!(first.expires_at, now + chrono::Duration::days(30));
+    }
+
     #[test]
     fn test_parse_expires_in() {
         assert_eq!(parse_expires_in("30s").unwrap(), 30);
@@ -149,4 +632,318 @@ mod tests {
         assert_eq!(parse_expires_in("7d").unwrap(), 604800);
         assert_eq!(parse_expires_in("1w").unwrap(), 604800);
     }
+
+    #[test]
+    fn test_validate_token_rejects_foreign_tokens() {
+        let config = |secret: &str| AppConfig {
+            jwt: crate::utils::config::JwtConfig {
+                secret: secret.to_string(),
+                expires_in: "1h".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let issuer = JwtService::from_config(&config("secret-a")).unwrap();
+        let verifier = JwtService::from_config(&config("secret-b")).unwrap();
+        let token = issuer.generate_token("user123", None, Vec::new()).unwrap();
+
+        assert!(matches!(
+            verifier.validate_token(&token),
+            Err(JwtError::InvalidToken)
+        ));
+        assert!(matches!(
+            verifier.validate_token("garbage"),
+            Err(JwtError::InvalidToken)
+        ));
+    }
+
+    fn claims_config(issuer: &str, audience: &str, leeway: &str) -> AppConfig {
+        AppConfig {
+            jwt: crate::utils::config::JwtConfig {
+                secret: "test-secret".to_string(),
+                expires_in: "1h".to_string(),
+                issuer: issuer.to_string(),
+                audience: audience.to_string(),
+                leeway: leeway.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_token_checks_issuer_and_audience() {
+        let jwt_service =
+            JwtService::from_config(&claims_config("blog-axum", "blog-axum", "60s")).unwrap();
+        let token = jwt_service
+            .generate_token("user123", None, vec!["admin".to_string()])
+            .unwrap();
+        let claims = jwt_service.validate_token(&token).unwrap();
+        assert_eq!(claims.iss, "blog-axum");
+        assert_eq!(claims.aud, "blog-axum");
+        assert_eq!(claims.iat, claims.nbf);
+        assert_eq!(claims.exp, claims.iat + 3600);
+        assert_eq!(claims.roles, vec!["admin".to_string()]);
+
+        // 同一密钥签发，但签发方或受众不同
+        let other_issuer =
+            JwtService::from_config(&claims_config("auth", "blog-axum", "60s")).unwrap();
+        let other_audience =
+            JwtService::from_config(&claims_config("blog-axum", "comments", "60s")).unwrap();
+        assert!(matches!(
+            other_issuer.validate_token(&token),
+            Err(JwtError::InvalidIssuer)
+        ));
+        assert!(matches!(
+            other_audience.validate_token(&token),
+            Err(JwtError::InvalidAudience)
+        ));
+    }
+
+    #[test]
+    fn test_validate_token_checks_not_before() {
+        let sign = |claims: serde_json::Value| {
+            encode(
+                &Header::default(),
+                &claims,
+                &EncodingKey::from_secret(b"test-secret"),
+            )
+            .unwrap()
+        };
+        let claims = |nbf_offset: i64| {
+            let now = Utc::now().timestamp();
+            serde_json::json!({
+                "sub": "user123",
+                "iss": "blog-axum",
+                "aud": "blog-axum",
+                "iat": now,
+                "nbf": now + nbf_offset,
+                "exp": now + 3600,
+                "jti": Uuid::new_v4(),
+            })
+        };
+
+        let strict =
+            JwtService::from_config(&claims_config("blog-axum", "blog-axum", "0s")).unwrap();
+        let lenient =
+            JwtService::from_config(&claims_config("blog-axum", "blog-axum", "60s")).unwrap();
+
+        let early = sign(claims(30));
+        assert!(matches!(
+            strict.validate_token(&early),
+            Err(JwtError::ImmatureToken)
+        ));
+        // 时钟偏差在容忍范围内
+        assert!(lenient.validate_token(&early).is_ok());
+        assert!(matches!(
+            lenient.validate_token(&sign(claims(3600))),
+            Err(JwtError::ImmatureToken)
+        ));
+
+        // 缺少必需的声明
+        let mut incomplete = claims(0);
+        incomplete.as_object_mut().unwrap().remove("aud");
+        assert!(matches!(
+            lenient.validate_token(&sign(incomplete)),
+            Err(JwtError::InvalidToken)
+        ));
+    }
+
+    /// Checked-in RSA test key, since ring cannot generate RSA keys
+    fn rsa_fixture(file: &str) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/jwt")
+            .join(file)
+    }
+
+    /// Writes a PKCS#8 key to `dir` and returns its config
+    ///
+    /// EC and Ed25519 keys are freshly generated; RSA keys are copies of the
+    /// PKCS#8 fixture.
+    fn generate_key(dir: &std::path::Path, kid: &str, algorithm: JwtAlgorithm) -> JwtKeyConfig {
+        let rng = SystemRandom::new();
+        let encode = |pkcs8: ring::pkcs8::Document| {
+            pem::encode(&pem::Pem::new("PRIVATE KEY", pkcs8.as_ref()))
+        };
+        let pem = match algorithm {
+            JwtAlgorithm::Es256 => encode(
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap(),
+            ),
+            JwtAlgorithm::EdDsa => encode(Ed25519KeyPair::generate_pkcs8(&rng).unwrap()),
+            JwtAlgorithm::Rs256 => std::fs::read_to_string(rsa_fixture("rsa-pkcs8.pem")).unwrap(),
+        };
+        let path = dir.join(format!("{}.pem", kid));
+        std::fs::write(&path, pem).unwrap();
+
+        JwtKeyConfig {
+            kid: kid.to_string(),
+            algorithm,
+            private_key: path,
+        }
+    }
+
+    fn keys_config(keys: &[&JwtKeyConfig], signing_key: Option<&str>) -> AppConfig {
+        AppConfig {
+            jwt: crate::utils::config::JwtConfig {
+                expires_in: "1h".to_string(),
+                keys: keys.iter().map(|&key| key.clone()).collect(),
+                signing_key: signing_key.map(str::to_string),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_asymmetric_keys_and_jwks() {
+        let dir = tempfile::tempdir().unwrap();
+        let ec = generate_key(dir.path(), "ec-1", JwtAlgorithm::Es256);
+        let ed = generate_key(dir.path(), "ed-1", JwtAlgorithm::EdDsa);
+
+        for signing_key in ["ec-1", "ed-1"] {
+            let config = keys_config(&[&ec, &ed], Some(signing_key));
+            let jwt_service = JwtService::from_config(&config).unwrap();
+            let token = jwt_service
+                .generate_token("user123", None, Vec::new())
+                .unwrap();
+
+            let header = decode_header(&token).unwrap();
+            assert_eq!(header.kid.as_deref(), Some(signing_key));
+            assert_eq!(jwt_service.validate_token(&token).unwrap().sub, "user123");
+
+            // 仅凭 JWKS 中的公钥即可验证
+            let jwk = jwt_service.jwks().find(signing_key).unwrap();
+            let mut validation = Validation::new(header.alg);
+            validation.set_audience(&["blog-axum"]);
+            let decoded =
+                decode::<Claims>(&token, &DecodingKey::from_jwk(jwk).unwrap(), &validation);
+            assert_eq!(decoded.unwrap().claims.sub, "user123");
+        }
+
+        let jwt_service = JwtService::from_config(&keys_config(&[&ec, &ed], None)).unwrap();
+        let jwks = serde_json::to_value(jwt_service.jwks()).unwrap();
+        let keys = jwks["keys"].as_array().unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0]["kid"], "ec-1");
+        assert_eq!(keys[0]["kty"], "EC");
+        assert_eq!(keys[0]["alg"], "ES256");
+        assert_eq!(keys[1]["kty"], "OKP");
+        assert_eq!(keys[1]["crv"], "Ed25519");
+        assert!(keys.iter().all(|key| key.get("d").is_none()));
+    }
+
+    #[test]
+    fn test_rsa_keys() {
+        let pkcs1 = JwtKeyConfig {
+            kid: "rsa-pkcs1".to_string(),
+            algorithm: JwtAlgorithm::Rs256,
+            private_key: rsa_fixture("rsa-pkcs1.pem"),
+        };
+        let pkcs8 = JwtKeyConfig {
+            kid: "rsa-pkcs8".to_string(),
+            private_key: rsa_fixture("rsa-pkcs8.pem"),
+            ..pkcs1.clone()
+        };
+
+        for key in [&pkcs1, &pkcs8] {
+            let jwt_service = JwtService::from_config(&keys_config(&[key], None)).unwrap();
+            let token = jwt_service
+                .generate_token("user123", None, Vec::new())
+                .unwrap();
+
+            let header = decode_header(&token).unwrap();
+            assert_eq!(header.alg, Algorithm::RS256);
+            assert_eq!(header.kid.as_deref(), Some(key.kid.as_str()));
+            assert_eq!(jwt_service.validate_token(&token).unwrap().sub, "user123");
+
+            let jwk = jwt_service.jwks().find(&key.kid).unwrap();
+            let mut validation = Validation::new(Algorithm::RS256);
+            validation.set_audience(&["blog-axum"]);
+            let decoded =
+                decode::<Claims>(&token, &DecodingKey::from_jwk(jwk).unwrap(), &validation);
+            assert_eq!(decoded.unwrap().claims.sub, "user123");
+        }
+
+        // 两个文件是同一把密钥，公开的 n 和 e 一致
+        let jwt_service = JwtService::from_config(&keys_config(&[&pkcs1, &pkcs8], None)).unwrap();
+        let jwks = serde_json::to_value(jwt_service.jwks()).unwrap();
+        let keys = jwks["keys"].as_array().unwrap();
+        assert_eq!(keys[0]["kty"], "RSA");
+        assert_eq!(keys[0]["alg"], "RS256");
+        assert_eq!(keys[0]["e"], "AQAB");
+        assert_eq!(keys[0]["n"], keys[1]["n"]);
+        assert!(keys.iter().all(|key| key.get("d").is_none()));
+
+        let mut wrong_algorithm = pkcs1;
+        wrong_algorithm.algorithm = JwtAlgorithm::Es256;
+        assert!(matches!(
+            JwtService::from_config(&keys_config(&[&wrong_algorithm], None)),
+            Err(JwtError::InvalidKey { .. })
+        ));
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = generate_key(dir.path(), "2026-09", JwtAlgorithm::Es256);
+        let new = generate_key(dir.path(), "2026-10", JwtAlgorithm::Es256);
+
+        let before = JwtService::from_config(&keys_config(&[&old, &new], None)).unwrap();
+        let during = JwtService::from_config(&keys_config(&[&old, &new], Some("2026-10"))).unwrap();
+        let after = JwtService::from_config(&keys_config(&[&new], None)).unwrap();
+
+        let old_token = before.generate_token("user123", None, Vec::new()).unwrap();
+        let new_token = during.generate_token("user123", None, Vec::new()).unwrap();
+
+        // 轮换期间新旧密钥签发的令牌都有效
+        assert!(during.validate_token(&old_token).is_ok());
+        assert!(before.validate_token(&new_token).is_ok());
+        // 移除旧密钥后，它签发的令牌失效
+        assert!(after.validate_token(&new_token).is_ok());
+        assert!(matches!(
+            after.validate_token(&old_token),
+            Err(JwtError::InvalidToken)
+        ));
+
+        // 共享密钥签发的令牌没有 kid，不被接受
+        let hs256 = JwtService::from_config(&AppConfig {
+            jwt: crate::utils::config::JwtConfig {
+                secret: "test-secret".to_string(),
+                expires_in: "1h".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(hs256.jwks().keys.is_empty());
+        let token = hs256.generate_token("user123", None, Vec::new()).unwrap();
+        assert!(matches!(
+            after.validate_token(&token),
+            Err(JwtError::InvalidToken)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_key_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = generate_key(dir.path(), "ec-1", JwtAlgorithm::Es256);
+
+        let error = |config: AppConfig| match JwtService::from_config(&config) {
+            Err(JwtError::InvalidKey { kid, .. }) => kid,
+            Err(other) => panic!("expected an invalid key error, got {}", other),
+            Ok(_) => panic!("expected an invalid key error"),
+        };
+
+        assert_eq!(error(keys_config(&[&key, &key], None)), "ec-1");
+        assert_eq!(error(keys_config(&[&key], Some("missing"))), "missing");
+
+        let mut wrong_algorithm = key.clone();
+        wrong_algorithm.algorithm = JwtAlgorithm::EdDsa;
+        assert_eq!(error(keys_config(&[&wrong_algorithm], None)), "ec-1");
+
+        let mut missing_file = key;
+        missing_file.private_key = dir.path().join("missing.pem");
+        assert_eq!(error(keys_config(&[&missing_file], None)), "ec-1");
+    }
 }
@@ -0,0 +1,538 @@
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd, html};
+use serde::{Deserialize, Serialize};
+use syntect::{
+    html::{ClassStyle, ClassedHTMLGenerator},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+// 语法定义加载开销较大，全局只加载一次
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+/// Prefix added to `id` attributes that come from raw HTML in the source
+///
+/// Only heading anchors and footnote definitions generated by the renderer
+/// keep their id as-is, so author HTML cannot clobber page globals or clash
+/// with the ids of the surrounding site.
+pub const USER_CONTENT_ID_PREFIX: &str = "user-content-";
+
+/// Builds the allow-list sanitiser for one document
+///
+/// The renderer writes its own ids (and the footnote links pointing at them)
+/// with `marker` in front. The sanitiser removes the marker again and
+/// prefixes every other `id` with [`USER_CONTENT_ID_PREFIX`]; the marker is
+/// random per document, so raw HTML in the source cannot carry it.
+fn sanitizer(marker: String) -> ammonia::Builder<'static> {
+    let mut builder = ammonia::Builder::default();
+    builder
+        // 标题锚点、脚注以及代码高亮依赖 id/class
+        .add_generic_attributes(["id", "class", "aria-hidden"])
+        .add_tags(["input", "span"])
+        // 任务列表的复选框只能是只读的 checkbox
+        .add_tag_attributes("input", ["checked"])
+        .set_tag_attribute_value("input", "type", "checkbox")
+        .set_tag_attribute_value("input", "disabled", "")
+        .add_tag_attributes("th", ["style"])
+        .add_tag_attributes("td", ["style"])
+        .attribute_filter(move |element, attribute, value| match attribute {
+            // 表格只保留对齐方式
+            "style" => matches!(
+                value,
+                "text-align: left" | "text-align: center" | "text-align: right"
+            )
+            .then(|| value.into()),
+            "id" => match value.strip_prefix(marker.as_str()) {
+                Some(id) => Some(id.into()),
+                None => Some(format!("{}{}", USER_CONTENT_ID_PREFIX, value).into()),
+            },
+            "href" if element == "a" => match value
+                .strip_prefix('#')
+                .and_then(|id| id.strip_prefix(marker.as_str()))
+            {
+                Some(id) => Some(format!("#{}", id).into()),
+                None => Some(value.into()),
+            },
+            _ => Some(value.into()),
+        });
+    builder
+}
+
+/// CSS class prefix of highlighted code tokens, e.g. `hl-keyword`
+pub const HIGHLIGHT_CLASS_PREFIX: &str = "hl-";
+
+/// Reading speed for space separated languages
+pub const WORDS_PER_MINUTE: u32 = 200;
+
+/// Reading speed for Chinese, Japanese and Korean text, counted per character
+pub const CJK_CHARS_PER_MINUTE: u32 = 400;
+
+/// Result of rendering a Markdown document
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedMarkdown {
+    /// Sanitised HTML, safe to embed in a page as-is
+    pub html: String,
+    /// Heading tree, nested by heading level
+    pub toc: Vec<TocEntry>,
+    /// Words outside code blocks; every CJK character counts as one word
+    pub word_count: u32,
+    /// Estimated reading time, rounded up to whole minutes
+    pub reading_time_minutes: u32,
+}
+
+/// One heading in the table of contents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TocEntry {
+    pub level: u8,
+    pub text: String,
+    /// The `id` of the heading element in the rendered HTML
+    pub anchor: String,
+    pub children: Vec<TocEntry>,
+}
+
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_HEADING_ATTRIBUTES
+}
+
+/// Renders CommonMark with GFM extensions into sanitised HTML
+///
+/// Tables, footnotes, task lists and strikethrough are enabled. Headings get
+/// a unique `id` and an anchor link, fenced code blocks are highlighted on
+/// the server using CSS classes prefixed with [`HIGHLIGHT_CLASS_PREFIX`], and
+/// the final HTML is passed through an allow-list sanitiser so raw HTML in
+/// the source cannot inject scripts.
+pub fn render(markdown: &str) -> RenderedMarkdown {
+    // 渲染器自己生成的 id 带上随机标记，清洗时据此区分作者写的原始 HTML
+    let marker = format!("md{:016x}-", rand::random::<u64>());
+    let mut anchors = Anchors::default();
+    let mut headings = Vec::new();
+    let mut words = WordCount::default();
+    let mut events = Vec::new();
+    let mut parser = Parser::new_ext(markdown, parser_options());
+
+    while let Some(event) = parser.next() {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+
+                let mut code = String::new();
+                for event in parser.by_ref() {
+                    match event {
+                        Event::Text(text) => code.push_str(&text),
+                        Event::End(TagEnd::CodeBlock) => break,
+                        _ => {}
+                    }
+                }
+
+                events.push(Event::Html(highlight_code(&code, &lang).into()));
+            }
+            Event::Start(Tag::Heading {
+                level,
+                id,
+                classes,
+                attrs,
+            }) => {
+                let mut inner = Vec::new();
+                let mut text = String::new();
+                for event in parser.by_ref() {
+                    match &event {
+                        Event::End(TagEnd::Heading(_)) => break,
+                        Event::Text(t) | Event::Code(t) => text.push_str(t),
+                        _ => {}
+                    }
+                    inner.push(event);
+                }
+
+                let anchor = anchors.unique(id.as_deref().unwrap_or(&text));
+                words.add(&text);
+                words.add(" ");
+                headings.push((level as u8, text.trim().to_string(), anchor.clone()));
+
+                events.push(Event::Start(Tag::Heading {
+                    level,
+                    id: Some(CowStr::from(format!("{}{}", marker, anchor))),
+                    classes,
+                    attrs,
+                }));
+                events.extend(inner);
+                events.push(Event::InlineHtml(
+                    format!(
+                        r##"<a class="heading-anchor" href="#{}" aria-hidden="true">#</a>"##,
+                        anchor
+                    )
+                    .into(),
+                ));
+                events.push(Event::End(TagEnd::Heading(level)));
+            }
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                events.push(Event::Start(Tag::FootnoteDefinition(
+                    format!("{}{}", marker, label).into(),
+                )));
+            }
+            Event::FootnoteReference(label) => {
+                events.push(Event::FootnoteReference(
+                    format!("{}{}", marker, label).into(),
+                ));
+            }
+            event => {
+                match &event {
+                    Event::Text(text) | Event::Code(text) => words.add(text),
+                    // 块结束和换行处断词，避免相邻段落的单词被拼在一起
+                    Event::End(_) | Event::SoftBreak | Event::HardBreak => words.add(" "),
+                    _ => {}
+                }
+                events.push(event);
+            }
+        }
+    }
+
+    let mut unsafe_html = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, events.into_iter());
+
+    let mut position = 0;
+    let toc = build_toc(&headings, &mut position, 0);
+
+    RenderedMarkdown {
+        html: sanitizer(marker).clean(&unsafe_html).to_string(),
+        toc,
+        word_count: words.total(),
+        reading_time_minutes: words.reading_time_minutes(),
+    }
+}
+
+/// Plain text of a document, cut to at most `max_chars` characters
+///
+/// Markup, raw HTML and code blocks are left out and whitespace is collapsed.
+/// Longer text is cut at the last space before the limit (or mid-word when
+/// there is none) and ends with an ellipsis.
+pub fn summary(markdown: &str, max_chars: usize) -> String {
+    let mut text = String::new();
+    let mut in_code_block = false;
+
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(t) | Event::Code(t) if !in_code_block => text.push_str(&t),
+            Event::End(_) | Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let text = words.join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+
+    // 留出省略号的位置；截断点落在单词中间时退回到上一个空格
+    let limit = max_chars.saturating_sub(1);
+    let mut cut: String = text.chars().take(limit).collect();
+    if text.chars().nth(limit) != Some(' ')
+        && let Some(end) = cut.rfind(' ')
+    {
+        cut.truncate(end);
+    }
+    format!("{}…", cut.trim_end())
+}
+
+/// Nests a flat list of `(level, text, anchor)` headings into a tree
+///
+/// Collects the headings deeper than `parent_level` starting at `position`;
+/// skipped levels (an `h4` right after an `h2`) are simply nested one deeper.
+fn build_toc(
+    headings: &[(u8, String, String)],
+    position: &mut usize,
+    parent_level: u8,
+) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+
+    while let Some((level, text, anchor)) = headings.get(*position) {
+        if *level <= parent_level {
+            break;
+        }
+        *position += 1;
+
+        let children = build_toc(headings, position, *level);
+        entries.push(TocEntry {
+            level: *level,
+            text: text.clone(),
+            anchor: anchor.clone(),
+            children,
+        });
+    }
+
+    entries
+}
+
+/// Counts words in streamed text, treating each CJK character as a word
+#[derive(Default)]
+struct WordCount {
+    words: u32,
+    cjk_chars: u32,
+    in_word: bool,
+}
+
+impl WordCount {
+    fn add(&mut self, text: &str) {
+        for c in text.chars() {
+            if is_cjk(c) {
+                self.cjk_chars += 1;
+                self.in_word = false;
+            } else if c.is_alphanumeric() || c == '\'' || c == '’' {
+                if !self.in_word {
+                    self.words += 1;
+                    self.in_word = true;
+                }
+            } else {
+                self.in_word = false;
+            }
+        }
+    }
+
+    fn total(&self) -> u32 {
+        self.words + self.cjk_chars
+    }
+
+    fn reading_time_minutes(&self) -> u32 {
+        let minutes = self.words as f64 / WORDS_PER_MINUTE as f64
+            + self.cjk_chars as f64 / CJK_CHARS_PER_MINUTE as f64;
+        minutes.ceil() as u32
+    }
+}
+
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // 平假名、片假名
+        | '\u{3400}'..='\u{4DBF}' // CJK 扩展 A
+        | '\u{4E00}'..='\u{9FFF}' // CJK 统一汉字
+        | '\u{AC00}'..='\u{D7AF}' // 韩文音节
+        | '\u{F900}'..='\u{FAFF}' // CJK 兼容汉字
+    )
+}
+
+/// Hands out heading ids that are unique within one document
+#[derive(Default)]
+struct Anchors {
+    seen: HashSet<String>,
+}
+
+impl Anchors {
+    fn unique(&mut self, text: &str) -> String {
+        let mut base = slug::slugify(text);
+        if base.is_empty() {
+            base = "section".to_string();
+        }
+
+        // 生成的后缀也可能与后面的标题或显式 id 重名，直到找到未被占用的 id
+        let mut anchor = base.clone();
+        let mut suffix = 0;
+        while self.seen.contains(&anchor) {
+            suffix += 1;
+            anchor = format!("{}-{}", base, suffix);
+        }
+
+        self.seen.insert(anchor.clone());
+        anchor
+    }
+}
+
+fn highlight_code(code: &str, lang: &str) -> String {
+    // 语言名会写入 class 属性，只保留安全字符
+    let lang: String = lang
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '#' | '-' | '_'))
+        .collect();
+
+    let syntax_set = &*SYNTAX_SET;
+    let syntax = syntax_set
+        .find_syntax_by_token(&lang)
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(
+        syntax,
+        syntax_set,
+        ClassStyle::SpacedPrefixed {
+            prefix: HIGHLIGHT_CLASS_PREFIX,
+        },
+    );
+
+    for line in LinesWithEndings::from(code) {
+        if generator
+            .parse_html_for_line_which_includes_newline(line)
+            .is_err()
+        {
+            // 高亮失败时退回纯文本
+            let mut escaped = String::new();
+            html::push_html(&mut escaped, std::iter::once(Event::Text(code.into())));
+            return format!("<pre><code>{}</code></pre>", escaped);
+        }
+    }
+
+    let class = if lang.is_empty() {
+        String::new()
+    } else {
+        format!(r#" class="language-{}""#, lang)
+    };
+
+    format!("<pre><code{}>{}</code></pre>", class, generator.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_basic_markdown() {
+        let rendered = render("Hello **world**");
+        assert_eq!(rendered.html.trim(), "<p>Hello <strong>world</strong></p>");
+    }
+
+    #[test]
+    fn test_render_gfm_extensions() {
+        let html = render(
+            "| a | b |\n|:--|--:|\n| 1 | 2 |\n\n- [x] done\n- [ ] todo\n\nNote[^1]\n\n[^1]: Footnote\n\n~~old~~",
+        )
+        .html;
+
+        assert!(html.contains("<table>"));
+        assert!(html.contains(r#"<th style="text-align: left">a</th>"#));
+        assert!(html.contains(r#"type="checkbox""#));
+        assert!(html.contains("checked"));
+        assert!(html.contains(r#"class="footnote-reference""#));
+        assert!(html.contains(r#"class="footnote-definition""#));
+        assert!(html.contains("<del>old</del>"));
+    }
+
+    #[test]
+    fn test_render_heading_anchors() {
+        let html = render("# Intro\n\n## Intro\n\n## Custom {#my-id}\n\n## `code` title").html;
+
+        assert!(html.contains(r#"<h1 id="intro">"#));
+        assert!(html.contains(r#"<h2 id="intro-1">"#));
+        assert!(html.contains(r#"<h2 id="my-id">"#));
+        assert!(html.contains(r#"<h2 id="code-title">"#));
+        assert!(html.contains(r##"href="#intro""##));
+        assert!(html.contains(r#"aria-hidden="true""#));
+    }
+
+    #[test]
+    fn test_render_heading_anchors_never_collide() {
+        let html = render("# Intro\n\n# Intro\n\n# Intro 1").html;
+        assert!(html.contains(r#"<h1 id="intro">"#));
+        assert!(html.contains(r#"<h1 id="intro-1">"#));
+        assert!(html.contains(r#"<h1 id="intro-1-1">"#));
+
+        let toc = render("# Intro\n\n# Intro\n\n## Explicit {#intro-1}\n\n# Intro").toc;
+        let anchors: Vec<&str> = toc
+            .iter()
+            .flat_map(|e| std::iter::once(e).chain(e.children.iter()))
+            .map(|e| e.anchor.as_str())
+            .collect();
+        assert_eq!(anchors, vec!["intro", "intro-1", "intro-1-1", "intro-2"]);
+    }
+
+    #[test]
+    fn test_render_toc() {
+        let rendered = render(
+            "# Title\n\n## One\n\n### One A\n\n#### Deep\n\n## Two\n\n#### Skipped\n\n# Appendix",
+        );
+
+        let toc = &rendered.toc;
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].text, "Title");
+        assert_eq!(toc[0].anchor, "title");
+        assert_eq!(toc[0].children.len(), 2);
+        assert_eq!(toc[0].children[0].text, "One");
+        assert_eq!(toc[0].children[0].children[0].text, "One A");
+        assert_eq!(toc[0].children[0].children[0].children[0].level, 4);
+        assert_eq!(toc[0].children[1].children[0].text, "Skipped");
+        assert_eq!(toc[1].anchor, "appendix");
+        assert!(toc[1].children.is_empty());
+
+        assert!(render("no headings here").toc.is_empty());
+    }
+
+    #[test]
+    fn test_render_word_count_and_reading_time() {
+        let rendered = render("# Hello world\n\nIt's a *small* test.\nNext line");
+        assert_eq!(rendered.word_count, 8);
+        assert_eq!(rendered.reading_time_minutes, 1);
+
+        // 代码块不计入字数
+        let rendered = render("one two\n\n```\nlet a = b;\n```");
+        assert_eq!(rendered.word_count, 2);
+
+        let rendered = render("你好，世界");
+        assert_eq!(rendered.word_count, 4);
+
+        let long = "word ".repeat(450);
+        assert_eq!(render(&long).reading_time_minutes, 3);
+        assert_eq!(render(&"字".repeat(800)).reading_time_minutes, 2);
+        assert_eq!(render("").reading_time_minutes, 0);
+    }
+
+    #[test]
+    fn test_summary() {
+        let markdown = "# Title\n\nSome *emphasis* and `code`.\n\n```\nlet hidden = 1;\n```\n\n<div>raw</div>\n\nLast line";
+        assert_eq!(
+            summary(markdown, 100),
+            "Title Some emphasis and code. Last line"
+        );
+        assert_eq!(summary(markdown, 20), "Title Some emphasis…");
+        assert_eq!(summary("abcdefghij", 5), "abcd…");
+        assert_eq!(summary("", 10), "");
+    }
+
+    #[test]
+    fn test_render_highlights_code() {
+        let html = render("```rust\nfn main() {}\n```").html;
+
+        assert!(html.contains(r#"<code class="language-rust">"#));
+        assert!(html.contains(&format!(r#"class="{}"#, HIGHLIGHT_CLASS_PREFIX)));
+        assert!(html.contains("main"));
+
+        let html = render("```\n<b>plain</b>\n```").html;
+        assert!(html.contains("&lt;b&gt;plain&lt;/b&gt;"));
+    }
+
+    #[test]
+    fn test_render_sanitises_html() {
+        let html = render(
+            "<script>alert(1)</script>\n\n[x](javascript:alert(1)) <img src=x onerror=alert(1)>\n\n<input type=\"text\" value=\"a\">",
+        )
+        .html;
+
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("onerror"));
+        assert!(!html.contains(r#"type="text""#));
+    }
+
+    #[test]
+    fn test_render_prefixes_author_ids() {
+        let html = render(
+            "# Intro\n\n<div id=\"config\">x</div>\n\n<h2 id=\"intro\">raw</h2>\n\n<h3 id=\"other\">raw</h3>\n\nNote[^n]\n\n[^n]: Footnote",
+        )
+        .html;
+
+        assert!(html.contains(r#"<h1 id="intro">"#));
+        assert!(html.contains(r#"<div id="user-content-config">"#));
+        assert!(html.contains(r#"<h2 id="user-content-intro">"#));
+        assert_eq!(html.matches(r#"id="intro""#).count(), 1);
+        assert!(html.contains(r#"<h3 id="user-content-other">"#));
+        assert!(html.contains(r#"class="footnote-definition" id="n""#));
+        assert!(html.contains(r##"<a href="#n" rel="noopener noreferrer">1</a>"##));
+    }
+}
@@ -1,9 +1,42 @@
 pub mod config;
 pub mod jwt;
+pub mod markdown;
 pub mod password;
 
+use std::time::Duration;
+
 use tracing_subscriber::{EnvFilter, Registry, fmt, prelude::__tracing_subscriber_SubscriberExt};
 
+/// Parses a human readable duration such as `30s`, `10m`, `2h`, `7d` or `1w`
+///
+/// This is the format used by duration values in `config.toml`. Returns
+/// `None` if the number or the unit cannot be understood, or if the result
+/// does not fit in a `u64` of seconds.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (num, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit())?);
+
+    let num = num.parse::<u64>().ok()?;
+
+    let seconds = match unit.trim().to_lowercase().as_str() {
+        "s" => num,
+        "m" => num.checked_mul(60)?,
+        "h" => num.checked_mul(3600)?,
+        "d" => num.checked_mul(86400)?,
+        "w" => num.checked_mul(604800)?,
+        _ => return None,
+    };
+
+    Some(Duration::from_secs(seconds))
+}
+
+/// Parses the period of a background job, see [`parse_duration`]
+///
+/// Zero is rejected as well, since a timer cannot tick every zero seconds.
+pub fn parse_interval(value: &str) -> Option<Duration> {
+    parse_duration(value).filter(|interval| !interval.is_zero())
+}
+
 /// Initializes the tracing/logging system for the application.
 ///
 /// This function sets up the global tracing subscriber with different configurations
@@ -102,3 +135,30 @@ pub fn init_tracing() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration(" 10m "), Some(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2H"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1w"), Some(Duration::from_secs(604800)));
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration("5y"), None);
+        assert_eq!(parse_duration("0s"), Some(Duration::ZERO));
+        assert_eq!(parse_duration("30500568904943664w"), None);
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("15m"), Some(Duration::from_secs(900)));
+        assert_eq!(parse_interval("0s"), None);
+        assert_eq!(parse_interval("0w"), None);
+        assert_eq!(parse_interval("1x"), None);
+    }
+}